@@ -0,0 +1,29 @@
+# Prints the value of r1 in decimal over serial
+# Digits are produced from the least significant one and reversed on the stack
+
+LDI r1 203
+LDI r0 0
+LDI r2 10
+LDI r3 0x30 # '0'
+LDI r4 0    # number of digits
+LDI r5 1
+LDI r6 hi(digit)
+LDI r7 lo(digit)
+LDI r8 hi(print)
+LDI r9 lo(print)
+
+digit:
+MOD r10 r1 r2
+ADD r10 r10 r3
+PUSH r10
+ADD r4 r4 r5
+DIV r1 r1 r2 # sets the zero flag once all digits are pushed
+JNZ r6 r7
+
+print:
+POP r10
+SB r10 r0 r0
+SUB r4 r4 r5
+JNZ r8 r9
+
+HLT
@@ -0,0 +1,25 @@
+# Echoes serial input until a new line is received
+# Serial data memory mapped to [0], status to [1] (bit 0: data available)
+
+LDI r0 0
+LDI r1 1 # status register and data available mask
+LDI r2 hi(wait)
+LDI r3 lo(wait)
+LDI r4 hi(echo)
+LDI r5 lo(echo)
+LDI r6 0x0a
+
+wait:
+LB r8 r0 r1
+AND r8 r8 r1
+JNZ r4 r5
+OR r8 r1 r1 # clears the zero flag to jump back unconditionally
+JNZ r2 r3
+
+echo:
+LB r8 r0 r0
+SB r8 r0 r0
+XOR r8 r8 r6 # sets the zero flag at the end of the line
+JNZ r2 r3
+
+HLT
@@ -1,33 +1,28 @@
-# Loading
-LDI r0 0x00
-LDI r1 72 # H
-LDI r2 69 # E
-LDI r3 76 # L
-LDI r4 79 # O
-LDI r5 32 # ' '
-LDI r6 87 # W
-LDI r7 82 # R
-LDI r8 68 # D
-
-# Write out
-# [0 << 8 | 0]
+# Prints the zero terminated string at `message`
 # Serial OUT memory mapped to [0]
 
-# HELLO
-SB r1 r0 r0
-SB r2 r0 r0
-SB r3 r0 r0
-SB r3 r0 r0
-SB r4 r0 r0
+LDI r0 0x00
+LDI r1 1
+LDI r2 hi(message)
+LDI r3 lo(message)
+LDI r4 hi(loop)
+LDI r5 lo(loop)
+LDI r6 hi(print)
+LDI r7 lo(print)
+
+loop:
+LB r8 r2 r3
+OR r8 r8 r0 # sets the zero flag at the end of the string
+JNZ r6 r7
 
-# ' '
-SB r5 r0 r0
+HLT
 
-# WORLD
-SB r6 r0 r0
-SB r4 r0 r0
-SB r7 r0 r0
-SB r3 r0 r0
+print:
 SB r8 r0 r0
+ADD r3 r3 r1
+JNZ r4 r5
+ADD r2 r2 r1 # lower byte wrapped around
+JNZ r4 r5
 
-HLT
+message:
+.asciiz "HELLO WORLD"
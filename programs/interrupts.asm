@@ -0,0 +1,29 @@
+# Echoes serial input from an interrupt handler while the main program idles
+# Serial data memory mapped to [0], status to [1] (bit 1: receive interrupt enable)
+# Interrupt vector memory mapped to [2] (high byte) and [3] (low byte)
+
+LDI r0 0
+LDI r1 hi(handler)
+LDI r2 2
+SB r1 r0 r2
+LDI r1 lo(handler)
+LDI r2 3
+SB r1 r0 r2
+
+LDI r1 0x02
+LDI r2 1
+SB r1 r0 r2
+
+LDI r4 hi(idle)
+LDI r5 lo(idle)
+LDI r6 1
+EI
+
+idle:
+OR r7 r6 r6 # clears the zero flag to jump back unconditionally
+JNZ r4 r5
+
+handler:
+LB r8 r0 r0
+SB r8 r0 r0
+RETI
@@ -34,19 +34,21 @@ JC r9 r9
 
 HLT
 
-LDI r2 0 # 0 extend
-LDI r3 8
+LDI r2 hi(loop)
+LDI r3 lo(loop)
 
 LDI r0 0 
 LDI r1 1
 
-LDI r5 0
-LDI r6 17
+loop:
+LDI r5 hi(increment)
+LDI r6 lo(increment)
 JAL r4 r5 r6
 JNZ r2 r3
 
 HLT
 
+increment:
 ADD r0 r0 r1
 LDI r6 128
 ADD r6 r6 r0 
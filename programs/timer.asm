@@ -0,0 +1,44 @@
+# Prints a dot on every timer overflow and halts after ten of them
+# Timer memory mapped to [4] (counter), [5] (reload), [6] (prescaler) and [7] (control/status)
+# Control bits: 0 enables counting, 1 enables the overflow interrupt, 7 is the overflow flag
+# Interrupt vector memory mapped to [2] (high byte) and [3] (low byte)
+
+LDI r0 0
+LDI r1 hi(handler)
+LDI r2 2
+SB r1 r0 r2
+LDI r1 lo(handler)
+LDI r2 3
+SB r1 r0 r2
+
+# overflow every (0x100 - 0xf0) * (3 + 1) = 64 cycles
+LDI r1 0xf0
+LDI r2 4
+SB r1 r0 r2
+LDI r2 5
+SB r1 r0 r2
+LDI r1 3
+LDI r2 6
+SB r1 r0 r2
+LDI r1 0x03
+LDI r2 7
+SB r1 r0 r2
+
+LDI r3 10 # overflows left
+LDI r4 hi(idle)
+LDI r5 lo(idle)
+LDI r6 1
+LDI r9 0x2e # '.'
+LDI r10 0x83 # keeps the timer running while clearing the overflow flag
+EI
+
+idle:
+OR r7 r3 r3 # zero once all overflows were counted
+JNZ r4 r5
+HLT
+
+handler:
+SB r10 r0 r2
+SB r9 r0 r0
+SUB r3 r3 r6
+RETI
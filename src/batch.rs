@@ -0,0 +1,325 @@
+use std::{
+    collections::HashMap,
+    io::{stdout, Write},
+    path::{Path, PathBuf},
+    process::ExitCode,
+    sync::{atomic::AtomicBool, Arc},
+};
+
+use crate::{
+    cli::StdinInput,
+    compiler::fold,
+    gdb,
+    machine::{
+        computer::System,
+        debugger::StopReason,
+        devices::serial::Serial,
+        word::{Word, WordSize},
+    },
+    new_compiler::{self, error::CompilationError},
+    symbols::SymbolTable,
+};
+
+// Non-interactive subcommands of the binary for use in scripts, the interactive prompt starts
+// without a subcommand
+
+pub const USAGE: &str = "Usage: mrt-cpu <--word-size 8|16> <--script file>
+       mrt-cpu asm [file] <-o out> <--listing>
+       mrt-cpu run [rom] <--max-steps N> <--ram-size S> <--word-size 8|16>
+       mrt-cpu disasm [rom] <--raw>
+       mrt-cpu gdb [rom] <--port P> <--ram-size S> <--word-size 8|16>
+
+    asm - compile with the new compiler, `out' defaults to the file with the extension .rom
+    run - run until the program halts, serial output is written to stdout and input read from stdin
+    disasm - disassemble the whole rom with the labels of <rom>.sym, --raw does not fold pseudo-instructions
+    gdb - wait for gdb to connect on the local port (default 1234) and serve it until it detaches
+
+Exit codes: 0 success or halted, 1 invalid arguments or files, 2 compilation error, 3 fault, 4 step limit reached,
+5 assertion of a script failed";
+
+// Exit code of a subcommand
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Status {
+    Success = 0, // compiled, disassembled or halted
+    Usage = 1,   // invalid arguments, unreadable or unwritable files
+    CompilationError = 2,
+    Fault = 3,
+    Timeout = 4,         // the step limit was reached before the program halted
+    AssertionFailed = 5, // by a script of the interactive prompt
+}
+
+impl From<Status> for ExitCode {
+    fn from(status: Status) -> Self {
+        ExitCode::from(status as u8)
+    }
+}
+
+// Ram of `run` without --ram-size, the whole address space
+const DEFAULT_RAM_SIZE: usize = 0x10000;
+
+// Instructions executed between writing the serial output to stdout
+pub(crate) const OUTPUT_INTERVAL: usize = 1000;
+
+// Positional arguments and options of a subcommand
+struct Arguments<'a> {
+    positional: Vec<&'a str>,
+    options: HashMap<&'a str, &'a str>, // `--name value`
+    flags: Vec<&'a str>,                // `--name`
+}
+
+impl<'a> Arguments<'a> {
+    fn parse(arguments: &'a [String], options: &[&str], flags: &[&str]) -> Result<Self, String> {
+        let mut parsed = Arguments {
+            positional: vec![],
+            options: HashMap::new(),
+            flags: vec![],
+        };
+
+        let mut arguments = arguments.iter().map(String::as_str);
+        while let Some(argument) = arguments.next() {
+            if options.contains(&argument) {
+                let Some(value) = arguments.next() else {
+                    return Err(format!("missing value of {}", argument));
+                };
+
+                parsed.options.insert(argument, value);
+            } else if flags.contains(&argument) {
+                parsed.flags.push(argument);
+            } else if argument.starts_with('-') {
+                return Err(format!("unknown option {}", argument));
+            } else {
+                parsed.positional.push(argument);
+            }
+        }
+
+        Ok(parsed)
+    }
+
+    // The only positional argument
+    fn file(&self) -> Result<&'a Path, String> {
+        match self.positional[..] {
+            [file] => Ok(Path::new(file)),
+            [] => Err("missing file".to_string()),
+            _ => Err(format!("unexpected argument {}", self.positional[1])),
+        }
+    }
+
+    fn number(&self, option: &str) -> Result<Option<usize>, String> {
+        let Some(value) = self.options.get(option) else {
+            return Ok(None);
+        };
+
+        let number = match value.strip_prefix("0x") {
+            Some(hexadecimal) => usize::from_str_radix(hexadecimal, 16),
+            None => value.parse(),
+        };
+
+        match number {
+            Ok(number) => Ok(Some(number)),
+            Err(_) => Err(format!("invalid number {} of {}", value, option)),
+        }
+    }
+
+    fn has_flag(&self, flag: &str) -> bool {
+        self.flags.contains(&flag)
+    }
+}
+
+fn usage_error(message: String) -> Status {
+    eprintln!("Error: {}", message);
+    eprintln!("{}", USAGE);
+    Status::Usage
+}
+
+// Runs the subcommand named by the first argument, None if there is none and the interactive
+// prompt should start
+pub fn run(args: &[String]) -> Option<Status> {
+    let (command, arguments) = args.split_first()?;
+
+    let result = match command.as_str() {
+        "asm" => assemble(arguments),
+        "run" => execute(arguments),
+        "disasm" => disassemble(arguments),
+        "gdb" => debug(arguments),
+        "help" | "--help" | "-h" => {
+            println!("{}", USAGE);
+            Ok(Status::Success)
+        }
+        // options of the interactive prompt
+        command if command.starts_with("--") => return None,
+        command => Err(format!("unknown subcommand {}", command)),
+    };
+
+    Some(result.unwrap_or_else(usage_error))
+}
+
+fn assemble(arguments: &[String]) -> Result<Status, String> {
+    let arguments = Arguments::parse(arguments, &["-o"], &["--listing"])?;
+    let input_path = arguments.file()?;
+    let output_path = match arguments.options.get("-o") {
+        Some(path) => PathBuf::from(path),
+        None => input_path.with_extension("rom"),
+    };
+
+    let diagnostics = match new_compiler::compile_file(input_path, &output_path) {
+        Ok(diagnostics) => diagnostics,
+        Err(error @ CompilationError::Rejected(_)) => {
+            eprintln!("{}", error);
+            return Ok(Status::CompilationError);
+        }
+        Err(error) => {
+            eprintln!("{}", error);
+            return Ok(Status::Usage);
+        }
+    };
+
+    if !diagnostics.is_empty() {
+        eprintln!("{}", diagnostics);
+    }
+
+    if arguments.has_flag("--listing") {
+        let listing_path = new_compiler::listing_path_for_rom(&output_path);
+        if let Err(error) = new_compiler::listing_file(input_path, &listing_path) {
+            eprintln!("{}", error);
+            return Ok(Status::Usage);
+        }
+    }
+
+    Ok(Status::Success)
+}
+
+fn read_rom(path: &Path) -> Result<Vec<u8>, String> {
+    match std::fs::read(path) {
+        Ok(rom) if rom.is_empty() => Err(format!("{} is empty", path.display())),
+        Ok(rom) => Ok(rom),
+        Err(error) => Err(format!("unable to read {}: {}", path.display(), error)),
+    }
+}
+
+impl Arguments<'_> {
+    fn ram_size(&self) -> Result<usize, String> {
+        Ok(self.number("--ram-size")?.unwrap_or(DEFAULT_RAM_SIZE))
+    }
+
+    fn word_size(&self) -> Result<WordSize, String> {
+        match self.number("--word-size")? {
+            None => Ok(WordSize::Bits8),
+            Some(bits) => Ok(WordSize::from_bits(bits as u32).ok_or("word size is 8 or 16")?),
+        }
+    }
+}
+
+fn execute(arguments: &[String]) -> Result<Status, String> {
+    let options = ["--max-steps", "--ram-size", "--word-size"];
+    let arguments = Arguments::parse(arguments, &options, &[])?;
+    let rom = read_rom(arguments.file()?)?;
+
+    let max_steps = arguments.number("--max-steps")?;
+    let ram_size = arguments.ram_size()?;
+
+    Ok(match arguments.word_size()? {
+        WordSize::Bits8 => run_rom(System::<u8>::with_ram_size(ram_size), rom, max_steps),
+        WordSize::Bits16 => run_rom(System::<u16>::with_ram_size(ram_size), rom, max_steps),
+    })
+}
+
+// Writes what the program wrote to the serial port to stdout
+fn flush_serial<W: Word>(system: &mut System<W>) {
+    if let Some(serial) = system.bus_mut().device_mut::<Serial>() {
+        _ = stdout().write_all(&serial.take_output());
+        _ = stdout().flush();
+    }
+}
+
+fn run_rom<W: Word>(mut system: System<W>, rom: Vec<u8>, max_steps: Option<usize>) -> Status {
+    // not empty, checked by `read_rom`
+    _ = system.load_rom(rom);
+    if let Some(serial) = system.bus_mut().device_mut::<Serial>() {
+        serial.set_input_source(Some(Box::<StdinInput>::default()));
+    }
+
+    let mut steps = 0;
+    loop {
+        let interval = max_steps.map_or(OUTPUT_INTERVAL, |max_steps| {
+            (max_steps - steps).min(OUTPUT_INTERVAL)
+        });
+
+        let reason = system.run(Some(interval), || false);
+        flush_serial(&mut system);
+
+        match reason {
+            StopReason::Halted => return Status::Success,
+            StopReason::Fault(fault) => {
+                let ip = system.get_ip();
+                let ram = system.bus().ram().as_slice();
+                let instruction = fold::disassemble(&ram[ip as usize..], |_| None, true);
+
+                eprintln!("Error: {} ip={:#06x}: {}", fault, ip, instruction.text);
+                return Status::Fault;
+            }
+            _ => steps += interval,
+        }
+
+        if max_steps.is_some_and(|max_steps| steps >= max_steps) {
+            eprintln!("Error: no halt after {} steps", steps);
+            return Status::Timeout;
+        }
+    }
+}
+
+fn debug(arguments: &[String]) -> Result<Status, String> {
+    let options = ["--port", "--ram-size", "--word-size"];
+    let arguments = Arguments::parse(arguments, &options, &[])?;
+    let rom = read_rom(arguments.file()?)?;
+
+    let port = match arguments.number("--port")? {
+        Some(port) => u16::try_from(port).map_err(|_| "invalid port")?,
+        None => gdb::DEFAULT_PORT,
+    };
+
+    let ram_size = arguments.ram_size()?;
+    let result = match arguments.word_size()? {
+        WordSize::Bits8 => serve_rom(System::<u8>::with_ram_size(ram_size), rom, port),
+        WordSize::Bits16 => serve_rom(System::<u16>::with_ram_size(ram_size), rom, port),
+    };
+
+    match result {
+        Ok(()) => Ok(Status::Success),
+        Err(error) => Err(format!("gdb server on port {}: {}", port, error)),
+    }
+}
+
+fn serve_rom<W: Word>(mut system: System<W>, rom: Vec<u8>, port: u16) -> std::io::Result<()> {
+    // not empty, checked by `read_rom`
+    _ = system.load_rom(rom);
+
+    eprintln!("Info: waiting for gdb on 127.0.0.1:{}", port);
+    let result = gdb::listen(&mut system, port, Arc::new(AtomicBool::new(false)));
+    flush_serial(&mut system);
+    result
+}
+
+fn disassemble(arguments: &[String]) -> Result<Status, String> {
+    let arguments = Arguments::parse(arguments, &[], &["--raw"])?;
+    let path = arguments.file()?;
+    let rom = read_rom(path)?;
+
+    // labels are optional
+    let symbols = SymbolTable::read(&SymbolTable::path_for_rom(path)).unwrap_or_default();
+    let name_of = |address| symbols.name_of(address).map(str::to_string);
+
+    let mut output = stdout().lock();
+    let mut address = 0;
+    while address < rom.len() {
+        if let Some(name) = symbols.name_of(address as u16) {
+            _ = writeln!(output, "{}:", name);
+        }
+
+        let line = fold::disassemble(&rom[address..], name_of, arguments.has_flag("--raw"));
+        _ = writeln!(output, "{:#06x}: {}", address, line.text);
+        address += line.length as usize;
+    }
+
+    Ok(Status::Success)
+}
@@ -0,0 +1,645 @@
+use std::{fmt::Display, path::Path};
+
+use crate::machine::word::{Word, WordSize};
+
+use super::{read_script, Cli, CliError};
+
+// Commands of the interactive prompt and scripts. Each command declares its parameters, the
+// registry checks the arguments against them and generates the help from them.
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ParameterKind {
+    Number,  // decimal or hexadecimal
+    Address, // number or label of the loaded rom
+    File,
+    Word,                            // any single argument
+    Text,                            // the remaining arguments
+    Choice(&'static [&'static str]), // one of the words
+    Flag,                            // `--name` anywhere in the arguments
+}
+
+impl Display for ParameterKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Number => write!(f, "decimal or hexadecimal number"),
+            Self::Address => write!(f, "address or label"),
+            Self::File => write!(f, "file path"),
+            Self::Word => write!(f, "word"),
+            Self::Text => write!(f, "rest of the line"),
+            Self::Choice(choices) => write!(f, "one of {}", choices.join(", ")),
+            Self::Flag => write!(f, "flag"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Parameter {
+    pub name: &'static str,
+    pub kind: ParameterKind,
+    pub required: bool,
+}
+
+impl Parameter {
+    pub fn required(name: &'static str, kind: ParameterKind) -> Self {
+        Self {
+            name,
+            kind,
+            required: true,
+        }
+    }
+
+    pub fn optional(name: &'static str, kind: ParameterKind) -> Self {
+        Self {
+            name,
+            kind,
+            required: false,
+        }
+    }
+
+    pub fn flag(name: &'static str) -> Self {
+        Self::optional(name, ParameterKind::Flag)
+    }
+}
+
+impl Display for Parameter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.required {
+            true => write!(f, "[{}]", self.name),
+            false => write!(f, "<{}>", self.name),
+        }
+    }
+}
+
+// Arguments of a command after checking them against its parameters
+pub struct Arguments<'a> {
+    name: &'a str,
+    positional: Vec<&'a str>,
+    flags: Vec<&'a str>,
+}
+
+impl<'a> Arguments<'a> {
+    // Splits off the flags and checks the number of positional arguments and their kinds
+    pub fn parse(command: &[&'a str], parameters: &[Parameter]) -> Result<Self, CliError> {
+        let Some((&name, arguments)) = command.split_first() else {
+            return Err(CliError::MissingParameter("command"));
+        };
+
+        let (flags, positional): (Vec<&str>, Vec<&str>) = arguments.iter().partition(|argument| {
+            parameters.iter().any(|parameter| {
+                parameter.kind == ParameterKind::Flag && parameter.name == **argument
+            })
+        });
+
+        let parameters = parameters
+            .iter()
+            .filter(|parameter| parameter.kind != ParameterKind::Flag)
+            .collect::<Vec<_>>();
+
+        if let Some(missing) = parameters
+            .iter()
+            .skip(positional.len())
+            .find(|parameter| parameter.required)
+        {
+            return Err(CliError::MissingParameter(missing.name));
+        }
+
+        let takes_rest = parameters
+            .last()
+            .is_some_and(|parameter| parameter.kind == ParameterKind::Text);
+
+        if positional.len() > parameters.len() && !takes_rest {
+            return Err(CliError::UnexpectedParameter(
+                positional[parameters.len()].to_string(),
+            ));
+        }
+
+        for (parameter, argument) in parameters.iter().zip(&positional) {
+            match parameter.kind {
+                ParameterKind::Number if !is_number(argument) => {
+                    return Err(CliError::InvalidParameterType(parameter.name, "number"))
+                }
+                ParameterKind::Choice(choices) if !choices.contains(argument) => {
+                    return Err(CliError::FailedParameterConstraint(parameter.name))
+                }
+                _ => {}
+            }
+        }
+
+        Ok(Self {
+            name,
+            positional,
+            flags,
+        })
+    }
+
+    // The command name followed by the positional arguments
+    pub fn command(&self) -> Vec<&'a str> {
+        let mut command = vec![self.name];
+        command.extend(&self.positional);
+        command
+    }
+
+    pub fn get(&self, index: usize) -> Option<&'a str> {
+        self.positional.get(index).copied()
+    }
+
+    pub fn has_flag(&self, flag: &str) -> bool {
+        self.flags.contains(&flag)
+    }
+}
+
+// Numbers as accepted by the commands, decimal or hexadecimal with or without `0x`
+fn is_number(argument: &str) -> bool {
+    argument.parse::<u64>().is_ok()
+        || u64::from_str_radix(argument.trim_start_matches("0x"), 16).is_ok()
+}
+
+// What the prompt does after a command
+#[derive(Debug, PartialEq)]
+pub enum Outcome {
+    Continue,
+    Exit,
+    WordSize(WordSize),   // start over with a new machine of the word size
+    Source(Vec<String>),  // execute the commands before reading the next line
+    Help(Option<String>), // print the help of all commands or of one
+}
+
+pub trait Command<W: Word> {
+    fn name(&self) -> &str;
+
+    fn aliases(&self) -> &[&str] {
+        &[]
+    }
+
+    fn parameters(&self) -> &[Parameter] {
+        &[]
+    }
+
+    // One line description
+    fn help(&self) -> &str;
+
+    fn execute(&self, cli: &mut Cli<W>, arguments: &Arguments) -> Result<Outcome, CliError>;
+}
+
+type Handler<W> = fn(&mut Cli<W>, &Arguments) -> Result<Outcome, CliError>;
+
+// Command of the table of built-in commands
+struct Builtin<W: Word> {
+    name: &'static str,
+    aliases: &'static [&'static str],
+    parameters: Vec<Parameter>,
+    help: &'static str,
+    handler: Handler<W>,
+}
+
+impl<W: Word> Command<W> for Builtin<W> {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn aliases(&self) -> &[&str] {
+        self.aliases
+    }
+
+    fn parameters(&self) -> &[Parameter] {
+        &self.parameters
+    }
+
+    fn help(&self) -> &str {
+        self.help
+    }
+
+    fn execute(&self, cli: &mut Cli<W>, arguments: &Arguments) -> Result<Outcome, CliError> {
+        (self.handler)(cli, arguments)
+    }
+}
+
+fn builtin<W: Word>(
+    name: &'static str,
+    aliases: &'static [&'static str],
+    parameters: Vec<Parameter>,
+    help: &'static str,
+    handler: Handler<W>,
+) -> Box<dyn Command<W>> {
+    Box::new(Builtin {
+        name,
+        aliases,
+        parameters,
+        help,
+        handler,
+    })
+}
+
+// Most commands print their result and let the prompt continue
+fn done(result: Result<(), CliError>) -> Result<Outcome, CliError> {
+    result.map(|_| Outcome::Continue)
+}
+
+const FOOTER: &str =
+    "  conditions compare registers, memory and constants, e.g. `r3 == 0x10` or `[0x20] >= r1`";
+
+pub struct Registry<W: Word> {
+    commands: Vec<Box<dyn Command<W>>>,
+}
+
+impl<W: Word> Registry<W> {
+    // Registry of the built-in commands
+    pub fn new() -> Self {
+        use ParameterKind::*;
+
+        let required = Parameter::required;
+        let optional = Parameter::optional;
+
+        let commands = vec![
+            builtin(
+                "help",
+                &[],
+                vec![optional("command", Word)],
+                "list the commands or describe one",
+                |_, args| Ok(Outcome::Help(args.get(0).map(str::to_string))),
+            ),
+            builtin("exit", &["quit"], vec![], "exit application", |_, _| {
+                Ok(Outcome::Exit)
+            }),
+            builtin(
+                "load_rom",
+                &["lr"],
+                vec![required("rom_file", File)],
+                "load a rom",
+                |cli, args| done(cli.load_rom(args.command())),
+            ),
+            builtin(
+                "ram_size",
+                &[],
+                vec![required("ram_size", Number)],
+                "set ram size",
+                |cli, args| done(cli.ram_size(args.command())),
+            ),
+            builtin(
+                "step",
+                &["s"],
+                vec![optional("step_count", Number)],
+                "step N amount of instructions, an empty line repeats the last step",
+                |cli, args| done(cli.step(args.command())),
+            ),
+            builtin(
+                "continue",
+                &["c"],
+                vec![],
+                "continue running until Ctrl+C",
+                |cli, _| done(cli.continue_exec()),
+            ),
+            builtin(
+                "back",
+                &["bk"],
+                vec![optional("step_count", Number)],
+                "undo the last N executed instructions",
+                |cli, args| done(cli.step_back(args.command())),
+            ),
+            builtin(
+                "reverse-continue",
+                &["rc"],
+                vec![],
+                "undo instructions until a breakpoint, watchpoint or the start of the history",
+                |cli, _| done(cli.reverse_continue()),
+            ),
+            builtin(
+                "history",
+                &[],
+                vec![optional("limit", Number)],
+                "print or set the number of instructions recorded for undo, 0 disables recording",
+                |cli, args| done(cli.history(args.command())),
+            ),
+            builtin(
+                "fault_policy",
+                &[],
+                vec![
+                    optional("halt|ignore|trap", Choice(&["halt", "ignore", "trap"])),
+                    optional("address|label", Address),
+                ],
+                "print or set what happens when an instruction faults, trap takes the handler address",
+                |cli, args| done(cli.fault_policy(args.command())),
+            ),
+            builtin(
+                "compile",
+                &["com"],
+                vec![
+                    required("file", File),
+                    optional("out", File),
+                    Parameter::flag("--new"),
+                    Parameter::flag("--listing"),
+                ],
+                "compile assembly file and output to `out', --new uses the new compiler and --listing with it also writes a listing to `out.lst'",
+                |cli, args| match (args.has_flag("--new"), args.has_flag("--listing")) {
+                    (true, listing) => done(cli.new_compile(args.command(), listing)),
+                    (false, false) => done(cli.compile(args.command())),
+                    (false, true) => Err(CliError::FailedParameterConstraint(
+                        "--listing requires --new",
+                    )),
+                },
+            ),
+            builtin(
+                "regs",
+                &[],
+                vec![],
+                "print system registers",
+                |cli, _| done(cli.print_regs()),
+            ),
+            builtin(
+                "stack",
+                &[],
+                vec![],
+                "print the stack from the stack pointer up to the end of ram",
+                |cli, _| done(cli.print_stack()),
+            ),
+            builtin(
+                "goto",
+                &[],
+                vec![required("address", Number)],
+                "set ip to address",
+                |cli, args| done(cli.goto(args.command())),
+            ),
+            builtin(
+                "disassemble",
+                &["dis"],
+                vec![optional("count|from", Number), optional("to", Number)],
+                "disassemble N instruction at ip or from range, expanded pseudo-instructions are folded",
+                |cli, args| done(cli.disassemble(args.command())),
+            ),
+            builtin(
+                "write",
+                &["w"],
+                vec![
+                    required("address", Number),
+                    required("byte", Number),
+                    optional("count", Number),
+                ],
+                "write byte N times at address in memory",
+                |cli, args| done(cli.write_memory(args.command())),
+            ),
+            builtin(
+                "read",
+                &["r"],
+                vec![required("address", Number), optional("count", Number)],
+                "read N bytes from address in memory",
+                |cli, args| done(cli.read_memory(args.command())),
+            ),
+            builtin(
+                "memory_map",
+                &["mm"],
+                vec![],
+                "print the address ranges of ram and devices",
+                |cli, _| done(cli.print_memory_map()),
+            ),
+            builtin(
+                "word_size",
+                &[],
+                vec![optional("8|16", Choice(&["8", "16"]))],
+                "print the word size or start over with a new machine of the given word size",
+                |cli, args| match cli.word_size(args.command())? {
+                    Some(word_size) => Ok(Outcome::WordSize(word_size)),
+                    None => Ok(Outcome::Continue),
+                },
+            ),
+            builtin(
+                "input",
+                &[],
+                vec![optional("text", Text)],
+                "queue a line of serial input, `continue' reads lines from stdin once the queue is empty",
+                |cli, args| done(cli.serial_input(args.command())),
+            ),
+            builtin(
+                "break",
+                &["b"],
+                vec![
+                    required("address|label", Address),
+                    optional("if condition", Text),
+                ],
+                "stop before executing the instruction at address",
+                |cli, args| done(cli.add_breakpoint(args.command())),
+            ),
+            builtin(
+                "watch",
+                &[],
+                vec![
+                    required("address|label", Address),
+                    optional("r|w|rw", Word),
+                    optional("if condition", Text),
+                ],
+                "stop after memory at address is read and/or written (default w)",
+                |cli, args| done(cli.add_watchpoint(args.command())),
+            ),
+            builtin(
+                "delete",
+                &[],
+                vec![required("id", Number)],
+                "delete breakpoint or watchpoint",
+                |cli, args| done(cli.delete_breakpoint(args.command())),
+            ),
+            builtin(
+                "enable",
+                &[],
+                vec![required("id", Number)],
+                "enable breakpoint or watchpoint",
+                |cli, args| done(cli.enable_breakpoint(args.command(), true)),
+            ),
+            builtin(
+                "disable",
+                &[],
+                vec![required("id", Number)],
+                "disable breakpoint or watchpoint",
+                |cli, args| done(cli.enable_breakpoint(args.command(), false)),
+            ),
+            builtin(
+                "breakpoints",
+                &["bl"],
+                vec![],
+                "list breakpoints and watchpoints",
+                |cli, _| done(cli.list_breakpoints()),
+            ),
+            builtin(
+                "symbols",
+                &[],
+                vec![],
+                "list labels of the loaded rom",
+                |cli, _| done(cli.print_symbols()),
+            ),
+            builtin(
+                "save_state",
+                &[],
+                vec![required("file", File)],
+                "write ram, registers, ip and flags to file",
+                |cli, args| done(cli.save_state(args.command())),
+            ),
+            builtin(
+                "load_state",
+                &[],
+                vec![required("file", File)],
+                "restore state written by save_state",
+                |cli, args| done(cli.load_state(args.command())),
+            ),
+            builtin(
+                "snapshot",
+                &[],
+                vec![optional("name", Word)],
+                "keep a copy of the current state in memory, lists snapshots without name",
+                |cli, args| done(cli.take_snapshot(args.command())),
+            ),
+            builtin(
+                "restore",
+                &[],
+                vec![required("name", Word)],
+                "restore the state of a snapshot",
+                |cli, args| done(cli.restore_snapshot(args.command())),
+            ),
+            builtin(
+                "diff",
+                &[],
+                vec![required("from", Word), optional("to", Word)],
+                "print changed registers, flags and memory between snapshots, `to' defaults to the current state",
+                |cli, args| done(cli.diff(args.command())),
+            ),
+            builtin(
+                "gdb",
+                &[],
+                vec![optional("port", Number)],
+                "wait for gdb to connect on the local port (default 1234) and serve it until it detaches",
+                |cli, args| done(cli.gdb_server(args.command())),
+            ),
+            builtin(
+                "source",
+                &[],
+                vec![required("file", File)],
+                "execute the commands of a script file, lines starting with `#' are comments",
+                |_, args| {
+                    let path = args.get(0).unwrap_or_default();
+                    Ok(Outcome::Source(read_script(Path::new(path))?))
+                },
+            ),
+            builtin(
+                "echo",
+                &[],
+                vec![optional("text", Text)],
+                "print text",
+                |cli, args| done(cli.echo(args.command())),
+            ),
+            builtin(
+                "assert",
+                &[],
+                vec![required("condition", Text)],
+                "fail when the condition does not hold, stops the running scripts",
+                |cli, args| done(cli.assert(args.command())),
+            ),
+        ];
+
+        Self { commands }
+    }
+
+    // Adds a command, replacing a command of the same name
+    pub fn register(&mut self, command: Box<dyn Command<W>>) {
+        match self
+            .commands
+            .iter()
+            .position(|c| c.name() == command.name())
+        {
+            Some(index) => self.commands[index] = command,
+            None => self.commands.push(command),
+        }
+    }
+
+    // Command by its name or one of its aliases
+    pub fn get(&self, name: &str) -> Option<&dyn Command<W>> {
+        self.commands
+            .iter()
+            .find(|command| command.name() == name || command.aliases().contains(&name))
+            .map(|command| command.as_ref())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &dyn Command<W>> {
+        self.commands.iter().map(|command| command.as_ref())
+    }
+
+    // Checks the arguments of the command line and executes it, prints the help itself
+    pub fn execute(&self, cli: &mut Cli<W>, command: &[&str]) -> Result<Outcome, CliError> {
+        let Some(name) = command.first() else {
+            return Ok(Outcome::Continue);
+        };
+
+        let Some(handler) = self.get(name) else {
+            return Err(CliError::UnrecognizedCommand(name.to_string()));
+        };
+
+        let arguments = Arguments::parse(command, handler.parameters())?;
+        match handler.execute(cli, &arguments)? {
+            Outcome::Help(topic) => {
+                print!("{}", self.help(topic.as_deref())?);
+                Ok(Outcome::Continue)
+            }
+            outcome => Ok(outcome),
+        }
+    }
+
+    // `name, alias [required] <optional>`
+    fn synopsis(command: &dyn Command<W>) -> String {
+        let mut synopsis = [command.name()]
+            .into_iter()
+            .chain(command.aliases().iter().copied())
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        for parameter in command.parameters() {
+            synopsis.push_str(&format!(" {}", parameter));
+        }
+
+        synopsis
+    }
+
+    // Overview of all commands or the description of one
+    pub fn help(&self, command: Option<&str>) -> Result<String, CliError> {
+        let Some(name) = command else {
+            let mut help = "Help: main, alias [required] <optional> - description\n".to_string();
+            for command in self.iter() {
+                help.push_str(&format!(
+                    "    {} - {}\n",
+                    Self::synopsis(command),
+                    command.help()
+                ));
+            }
+
+            help.push_str(FOOTER);
+            help.push('\n');
+            return Ok(help);
+        };
+
+        let Some(command) = self.get(name) else {
+            return Err(CliError::UnrecognizedCommand(name.to_string()));
+        };
+
+        let mut help = format!("{}\n    {}\n", Self::synopsis(command), command.help());
+        for parameter in command.parameters() {
+            help.push_str(&format!("    {} - {}\n", parameter, parameter.kind));
+        }
+
+        Ok(help)
+    }
+
+    // Names and aliases with the parameters of their commands, for completion
+    pub fn signatures(&self) -> Vec<(String, Vec<Parameter>)> {
+        self.iter()
+            .flat_map(|command| {
+                let names = [command.name()]
+                    .into_iter()
+                    .chain(command.aliases().iter().copied());
+                names
+                    .map(|name| (name.to_string(), command.parameters().to_vec()))
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+}
+
+impl<W: Word> Default for Registry<W> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
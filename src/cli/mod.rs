@@ -0,0 +1,983 @@
+pub mod command;
+
+use std::{
+    any::type_name,
+    collections::HashMap,
+    fs::File,
+    io::{stdout, Write},
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use crate::machine::{
+    computer::System,
+    debugger::{BreakpointKind, Condition, ConditionOperand, StopReason, WatchMode},
+    devices::serial::{InputSource, Serial},
+    fault::{ExecutionFault, FaultPolicy},
+    snapshot::Snapshot,
+    word::{Word, WordSize},
+};
+use crate::{
+    batch,
+    compiler::{compiler::Compiler, fold, instruction::Instruction},
+    gdb, new_compiler,
+    symbols::SymbolTable,
+};
+
+pub struct Cli<W: Word = u8> {
+    system: System<W>,
+    interrupt: Arc<AtomicBool>,
+    symbols: SymbolTable,
+    snapshots: HashMap<String, Snapshot>,
+}
+
+// Feeds the serial port with what is typed on stdin while a program runs with `continue`
+#[derive(Default)]
+pub(crate) struct StdinInput {
+    polls: u32,
+}
+
+impl StdinInput {
+    // Checking stdin after every instruction would slow the program down
+    const POLL_INTERVAL: u32 = 1000;
+}
+
+impl InputSource for StdinInput {
+    fn receive(&mut self, output: &mut Vec<u8>) -> Option<Vec<u8>> {
+        // show what the program wrote so far, e.g. a prompt
+        if !output.is_empty() {
+            print!("{}", String::from_utf8_lossy(output));
+            _ = stdout().flush();
+            output.clear();
+        }
+
+        read_stdin()
+    }
+
+    fn poll(&mut self, output: &mut Vec<u8>) -> Option<Vec<u8>> {
+        self.polls = (self.polls + 1) % Self::POLL_INTERVAL;
+        if self.polls > 0 || !stdin_ready() {
+            return Some(vec![]);
+        }
+
+        self.receive(output)
+    }
+}
+
+#[cfg(unix)]
+fn stdin_ready() -> bool {
+    let mut fd = libc::pollfd {
+        fd: libc::STDIN_FILENO,
+        events: libc::POLLIN,
+        revents: 0,
+    };
+
+    // returns at once, the end of input counts as ready
+    unsafe { libc::poll(&mut fd, 1, 0) > 0 }
+}
+
+// Without a way to wait for stdin the program only gets input by reading the data register
+#[cfg(not(unix))]
+fn stdin_ready() -> bool {
+    false
+}
+
+// Bypasses the buffer of `stdin()`, `stdin_ready` can't see lines kept in there
+#[cfg(unix)]
+fn read_stdin() -> Option<Vec<u8>> {
+    let mut buffer = [0u8; 1024];
+    let count = unsafe { libc::read(libc::STDIN_FILENO, buffer.as_mut_ptr().cast(), buffer.len()) };
+    (count > 0).then(|| buffer[..count as usize].to_vec())
+}
+
+#[cfg(not(unix))]
+fn read_stdin() -> Option<Vec<u8>> {
+    let mut line = String::new();
+    match std::io::stdin().read_line(&mut line) {
+        Ok(0) | Err(_) => None,
+        Ok(_) => Some(line.into_bytes()),
+    }
+}
+
+// Commands of a script, blank lines and comments starting with `#` are left out
+pub fn script_commands(script: &str) -> Vec<String> {
+    script
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_string)
+        .collect()
+}
+
+pub fn read_script(path: &Path) -> Result<Vec<String>, CliError> {
+    match std::fs::read_to_string(path) {
+        Ok(script) => Ok(script_commands(&script)),
+        Err(_) => Err(CliError::FailedToReadFromFile),
+    }
+}
+
+#[derive(Debug)]
+pub enum CliError {
+    MissingParameter(&'static str),
+    InvalidParameterType(&'static str, &'static str),
+    FailedParameterConstraint(&'static str),
+    FailedToReadFromFile,
+    FailedToWriteToFile,
+    OperationError,
+    AssertionFailed,
+    UnrecognizedCommand(String),
+    UnexpectedParameter(String),
+}
+
+impl<W: Word> Cli<W> {
+    pub fn new(interrupt: Arc<AtomicBool>) -> Self {
+        Self {
+            system: System::with_ram_size(64),
+            interrupt,
+            symbols: SymbolTable::new(),
+            snapshots: HashMap::new(),
+        }
+    }
+
+    pub fn system(&self) -> &System<W> {
+        &self.system
+    }
+
+    pub fn system_mut(&mut self) -> &mut System<W> {
+        &mut self.system
+    }
+
+    fn unpack<T: std::str::FromStr + num_traits::Num>(
+        param_name: &'static str,
+        string: &str,
+    ) -> Result<T, CliError> {
+        if let Ok(n) = string.parse::<T>() {
+            Ok(n)
+        } else if let Ok(n) = T::from_str_radix(string.trim_start_matches("0x"), 16) {
+            Ok(n)
+        } else {
+            Err(CliError::InvalidParameterType(param_name, type_name::<T>()))
+        }
+    }
+
+    // Address given as number or as label of the loaded symbol table
+    fn unpack_address(&self, param_name: &'static str, string: &str) -> Result<u16, CliError> {
+        match self.symbols.get(string) {
+            Some(address) => Ok(address),
+            None => Self::unpack::<u16>(param_name, string),
+        }
+    }
+
+    // Optional condition at the end of a command, e.g. `if r3 == 0x10`
+    fn unpack_condition(arguments: &[&str]) -> Result<Option<Condition>, CliError> {
+        let arguments = match arguments.first() {
+            Some(&"if") => &arguments[1..],
+            _ => arguments,
+        };
+
+        if arguments.is_empty() {
+            return Ok(None);
+        }
+
+        match Condition::parse_sized(&arguments.join(" "), W::SIZE) {
+            Ok(condition) => Ok(Some(condition)),
+            Err(_) => Err(CliError::InvalidParameterType(
+                "condition",
+                type_name::<Condition>(),
+            )),
+        }
+    }
+
+    // Prints what the program wrote to the serial port
+    fn flush_serial(&mut self) {
+        if let Some(serial) = self.system.bus_mut().device_mut::<Serial>() {
+            let output = serial.take_output();
+            print!("{}", String::from_utf8_lossy(&output));
+            _ = stdout().flush();
+        }
+    }
+
+    fn print_stop_reason(&mut self, reason: &StopReason) {
+        self.flush_serial();
+
+        match reason {
+            StopReason::Halted => println!("Info: halting at ip={}", self.system.get_ip()),
+            StopReason::Breakpoint(id) => {
+                let breakpoint = self.system.breakpoints().get(*id).unwrap();
+                println!(
+                    "Info: hit {}: {}, ip={:#06x}",
+                    id,
+                    breakpoint,
+                    self.system.get_ip()
+                );
+            }
+            StopReason::Watchpoint(id, access) => {
+                let breakpoint = self.system.breakpoints().get(*id).unwrap();
+                println!(
+                    "Info: hit {}: {}, {:?} of {:#04x}, ip={:#06x}",
+                    id,
+                    breakpoint,
+                    access.kind,
+                    access.value,
+                    self.system.get_ip()
+                );
+            }
+            StopReason::StartOfHistory => {
+                println!("Info: reached the start of the recorded history")
+            }
+            StopReason::Fault(fault) => self.print_fault(fault),
+            StopReason::StepLimit | StopReason::Interrupted => {}
+        }
+    }
+
+    // Prints the fault together with the instruction the system halted in front of
+    fn print_fault(&self, fault: &ExecutionFault) {
+        let ip = self.system.get_ip();
+        println!("Error: {} ip={:#06x}", fault, ip);
+
+        if *fault != ExecutionFault::IllegalOpcode {
+            _ = self.disassemble_single(ip);
+            return;
+        }
+
+        let ram = self.system.bus().ram().as_slice();
+        let bytes = ram.get(ip as usize..).unwrap_or(&[]);
+        let bytes = bytes
+            .iter()
+            .take(2)
+            .map(|byte| format!("{:02x}", byte))
+            .collect::<Vec<_>>();
+
+        println!("{:#04x}: {}", ip, bytes.join(" "));
+    }
+
+    // Out of bounds accesses of the memory commands
+    fn memory_error(fault: ExecutionFault) -> CliError {
+        println!("Cli Operation Error: {}", fault);
+        CliError::OperationError
+    }
+
+    pub fn load_rom(&mut self, command: Vec<&str>) -> Result<(), CliError> {
+        let path = command.get(1);
+        if path.is_none() {
+            return Err(CliError::MissingParameter(stringify!(path)));
+        }
+
+        let rom = std::fs::read(Path::new(path.unwrap()));
+        if rom.is_err() {
+            return Err(CliError::FailedToReadFromFile);
+        }
+
+        let result = self.system.load_rom(rom.unwrap());
+        if result.is_err() {
+            println!("Cli Operation Error: {:?}", result);
+            return Err(CliError::OperationError);
+        }
+
+        // labels are optional, roms without symbol table can still be debugged by address
+        let symbols_path = SymbolTable::path_for_rom(Path::new(path.unwrap()));
+        self.symbols = SymbolTable::read(&symbols_path).unwrap_or_default();
+
+        Ok(())
+    }
+
+    pub fn ram_size(&mut self, command: Vec<&str>) -> Result<(), CliError> {
+        let size = command.get(1);
+        if size.is_none() {
+            return Err(CliError::MissingParameter(stringify!(size)));
+        }
+
+        let size = Self::unpack::<usize>(stringify!(size), size.unwrap())?;
+        let result = self.system.load_ram(vec![0; size]);
+        if result.is_err() {
+            println!("Cli Operation Error: {:?}", result);
+            return Err(CliError::OperationError);
+        }
+
+        Ok(())
+    }
+
+    pub fn step(&mut self, command: Vec<&str>) -> Result<(), CliError> {
+        let step_count = command.get(1);
+        let step_count = if step_count.is_some() {
+            Self::unpack::<usize>(stringify!(step_count), step_count.unwrap())?
+        } else {
+            1
+        };
+
+        let reason = self.system.run(Some(step_count), || false);
+        self.print_stop_reason(&reason);
+
+        Ok(())
+    }
+
+    fn set_serial_input(&mut self, source: Option<Box<dyn InputSource>>) {
+        if let Some(serial) = self.system.bus_mut().device_mut::<Serial>() {
+            serial.set_input_source(source);
+        }
+    }
+
+    pub fn continue_exec(&mut self) -> Result<(), CliError> {
+        self.set_serial_input(Some(Box::<StdinInput>::default()));
+
+        // the output shows up while the program runs, not only once it stops
+        let interrupt = self.interrupt.clone();
+        let reason = loop {
+            let reason = self.system.run(Some(batch::OUTPUT_INTERVAL), || {
+                interrupt.load(Ordering::Acquire)
+            });
+
+            if reason != StopReason::StepLimit {
+                break reason;
+            }
+
+            self.flush_serial();
+        };
+
+        self.set_serial_input(None);
+
+        self.print_stop_reason(&reason);
+
+        self.interrupt.store(false, Ordering::Release);
+        Ok(())
+    }
+
+    pub fn step_back(&mut self, command: Vec<&str>) -> Result<(), CliError> {
+        let step_count = match command.get(1) {
+            Some(step_count) => Self::unpack::<usize>(stringify!(step_count), step_count)?,
+            None => 1,
+        };
+
+        let reason = self.system.run_back(Some(step_count), || false);
+        self.print_stop_reason(&reason);
+
+        Ok(())
+    }
+
+    pub fn reverse_continue(&mut self) -> Result<(), CliError> {
+        let interrupt = self.interrupt.clone();
+        let reason = self
+            .system
+            .run_back(None, || interrupt.load(Ordering::Acquire));
+
+        self.print_stop_reason(&reason);
+
+        self.interrupt.store(false, Ordering::Release);
+        Ok(())
+    }
+
+    pub fn fault_policy(&mut self, command: Vec<&str>) -> Result<(), CliError> {
+        let policy = match command.get(1) {
+            None => None,
+            Some(&"halt") => Some(FaultPolicy::Halt),
+            Some(&"ignore") => Some(FaultPolicy::Ignore),
+            Some(&"trap") => {
+                let Some(vector) = command.get(2) else {
+                    return Err(CliError::MissingParameter("vector"));
+                };
+
+                Some(FaultPolicy::Trap(self.unpack_address("vector", vector)?))
+            }
+            Some(_) => {
+                return Err(CliError::InvalidParameterType(
+                    "policy",
+                    type_name::<FaultPolicy>(),
+                ))
+            }
+        };
+
+        if let Some(policy) = policy {
+            self.system.set_fault_policy(policy);
+        }
+
+        println!("Info: fault policy is {}", self.system.fault_policy());
+        Ok(())
+    }
+
+    pub fn history(&mut self, command: Vec<&str>) -> Result<(), CliError> {
+        if let Some(limit) = command.get(1) {
+            let limit = Self::unpack::<usize>(stringify!(limit), limit)?;
+            self.system.journal_mut().set_limit(limit);
+        }
+
+        let journal = self.system.journal();
+        println!(
+            "Info: {} of at most {} instructions recorded",
+            journal.len(),
+            journal.limit()
+        );
+
+        Ok(())
+    }
+
+    pub fn add_breakpoint(&mut self, command: Vec<&str>) -> Result<(), CliError> {
+        let address = command.get(1);
+        if address.is_none() {
+            return Err(CliError::MissingParameter(stringify!(address)));
+        }
+
+        let address = self.unpack_address(stringify!(address), address.unwrap())?;
+        let condition = Self::unpack_condition(&command[2..])?;
+
+        let id = self
+            .system
+            .breakpoints_mut()
+            .add(BreakpointKind::Address(address), condition);
+
+        println!("Info: breakpoint {} set at {:#06x}", id, address);
+        Ok(())
+    }
+
+    pub fn add_watchpoint(&mut self, command: Vec<&str>) -> Result<(), CliError> {
+        let address = command.get(1);
+        if address.is_none() {
+            return Err(CliError::MissingParameter(stringify!(address)));
+        }
+
+        let address = self.unpack_address(stringify!(address), address.unwrap())?;
+
+        // the mode is optional and defaults to writes
+        let (mode, condition) = match command.get(2).map(|mode| WatchMode::try_from(*mode)) {
+            Some(Ok(mode)) => (mode, &command[3..]),
+            _ => (WatchMode::Write, &command[2.min(command.len())..]),
+        };
+
+        let condition = Self::unpack_condition(condition)?;
+
+        let id = self
+            .system
+            .breakpoints_mut()
+            .add(BreakpointKind::Watch(address, mode), condition);
+
+        println!("Info: watchpoint {} set on [{:#06x}]", id, address);
+        Ok(())
+    }
+
+    fn unpack_breakpoint_id(command: &[&str]) -> Result<usize, CliError> {
+        let id = command.get(1);
+        if id.is_none() {
+            return Err(CliError::MissingParameter(stringify!(id)));
+        }
+
+        Self::unpack::<usize>(stringify!(id), id.unwrap())
+    }
+
+    pub fn delete_breakpoint(&mut self, command: Vec<&str>) -> Result<(), CliError> {
+        let id = Self::unpack_breakpoint_id(&command)?;
+        if self.system.breakpoints_mut().delete(id).is_err() {
+            return Err(CliError::FailedParameterConstraint("no such breakpoint"));
+        }
+
+        Ok(())
+    }
+
+    pub fn enable_breakpoint(&mut self, command: Vec<&str>, enabled: bool) -> Result<(), CliError> {
+        let id = Self::unpack_breakpoint_id(&command)?;
+        if self
+            .system
+            .breakpoints_mut()
+            .set_enabled(id, enabled)
+            .is_err()
+        {
+            return Err(CliError::FailedParameterConstraint("no such breakpoint"));
+        }
+
+        Ok(())
+    }
+
+    pub fn list_breakpoints(&self) -> Result<(), CliError> {
+        for breakpoint in self.system.breakpoints().iter() {
+            let address = match breakpoint.kind {
+                BreakpointKind::Address(address) | BreakpointKind::Watch(address, _) => address,
+            };
+
+            let label = match self.symbols.name_of(address) {
+                Some(name) => format!(" <{}>", name),
+                None => String::new(),
+            };
+
+            println!(
+                "{:3}: {}{}{}, hits: {}",
+                breakpoint.id,
+                breakpoint,
+                label,
+                if breakpoint.enabled {
+                    ""
+                } else {
+                    " (disabled)"
+                },
+                breakpoint.hits
+            );
+        }
+
+        Ok(())
+    }
+
+    pub fn serial_input(&mut self, command: Vec<&str>) -> Result<(), CliError> {
+        let Some(serial) = self.system.bus_mut().device_mut::<Serial>() else {
+            return Err(CliError::FailedParameterConstraint(
+                "no serial device attached",
+            ));
+        };
+
+        if command.len() > 1 {
+            let mut text = command[1..].join(" ");
+            text.push('\n');
+            serial.push_input(text.as_bytes());
+        }
+
+        println!("Info: {} byte(s) queued", serial.pending_input());
+        Ok(())
+    }
+
+    pub fn print_memory_map(&self) -> Result<(), CliError> {
+        print!("{}", self.system.bus().memory_map());
+        Ok(())
+    }
+
+    // Prints the word size of the machine or returns the requested one, switching replaces the
+    // machine by a new one
+    pub fn word_size(&self, command: Vec<&str>) -> Result<Option<WordSize>, CliError> {
+        let Some(bits) = command.get(1) else {
+            println!("{}", W::SIZE);
+            return Ok(None);
+        };
+
+        let bits = Self::unpack::<u32>(stringify!(bits), bits)?;
+        match WordSize::from_bits(bits) {
+            Some(word_size) => Ok(Some(word_size)),
+            None => Err(CliError::FailedParameterConstraint("word size is 8 or 16")),
+        }
+    }
+
+    // Prints the stack from the last pushed byte up to the end of ram
+    pub fn print_stack(&self) -> Result<(), CliError> {
+        let stack = self.system.stack();
+        let sp = self.system.get_sp();
+        println!("sp={:#06x}, {} bytes", sp, stack.len());
+
+        for (offset, value) in stack.iter().enumerate() {
+            self.print_single_read_memory(sp + offset as u16, *value);
+        }
+
+        Ok(())
+    }
+
+    // Labels of the loaded rom
+    pub fn labels(&self) -> Vec<String> {
+        let symbols = self.symbols.sorted();
+        symbols.iter().map(|(name, _)| name.to_string()).collect()
+    }
+
+    pub fn print_symbols(&self) -> Result<(), CliError> {
+        print!("{}", self.symbols);
+        Ok(())
+    }
+
+    pub fn save_state(&self, command: Vec<&str>) -> Result<(), CliError> {
+        let path = command.get(1);
+        if path.is_none() {
+            return Err(CliError::MissingParameter(stringify!(path)));
+        }
+
+        let data = self.system.snapshot().serialize();
+        if std::fs::write(Path::new(path.unwrap()), data).is_err() {
+            return Err(CliError::FailedToWriteToFile);
+        }
+
+        Ok(())
+    }
+
+    pub fn load_state(&mut self, command: Vec<&str>) -> Result<(), CliError> {
+        let path = command.get(1);
+        if path.is_none() {
+            return Err(CliError::MissingParameter(stringify!(path)));
+        }
+
+        let data = std::fs::read(Path::new(path.unwrap()));
+        if data.is_err() {
+            return Err(CliError::FailedToReadFromFile);
+        }
+
+        match Snapshot::deserialize(&data.unwrap()) {
+            Ok(snapshot) if snapshot.word_size != W::SIZE => {
+                println!(
+                    "Cli Operation Error: state of a {} machine, the current machine is {}",
+                    snapshot.word_size,
+                    W::SIZE
+                );
+                Err(CliError::OperationError)
+            }
+            Ok(snapshot) => {
+                self.system.restore(&snapshot);
+                Ok(())
+            }
+            Err(error) => {
+                println!("Cli Operation Error: {:?}", error);
+                Err(CliError::OperationError)
+            }
+        }
+    }
+
+    pub fn take_snapshot(&mut self, command: Vec<&str>) -> Result<(), CliError> {
+        let name = command.get(1);
+        if name.is_none() {
+            let mut names = self.snapshots.keys().collect::<Vec<_>>();
+            names.sort();
+
+            for name in names {
+                println!("{}", name);
+            }
+
+            return Ok(());
+        }
+
+        self.snapshots
+            .insert(name.unwrap().to_string(), self.system.snapshot());
+
+        Ok(())
+    }
+
+    fn get_snapshot(&self, name: &str) -> Result<&Snapshot, CliError> {
+        match self.snapshots.get(name) {
+            Some(snapshot) => Ok(snapshot),
+            None => Err(CliError::FailedParameterConstraint("no such snapshot")),
+        }
+    }
+
+    pub fn restore_snapshot(&mut self, command: Vec<&str>) -> Result<(), CliError> {
+        let name = command.get(1);
+        if name.is_none() {
+            return Err(CliError::MissingParameter(stringify!(name)));
+        }
+
+        let snapshot = self.get_snapshot(name.unwrap())?.clone();
+        self.system.restore(&snapshot);
+        Ok(())
+    }
+
+    pub fn diff(&self, command: Vec<&str>) -> Result<(), CliError> {
+        let from = command.get(1);
+        if from.is_none() {
+            return Err(CliError::MissingParameter(stringify!(from)));
+        }
+
+        let from = self.get_snapshot(from.unwrap())?;
+
+        // compare against the current state if no second snapshot is given
+        let current;
+        let to = match command.get(2) {
+            Some(to) => self.get_snapshot(to)?,
+            None => {
+                current = self.system.snapshot();
+                &current
+            }
+        };
+
+        print!("{}", from.diff(to));
+        Ok(())
+    }
+
+    // Serves a gdb client on the local port until it detaches
+    pub fn gdb_server(&mut self, command: Vec<&str>) -> Result<(), CliError> {
+        let port = match command.get(1) {
+            Some(port) => Self::unpack::<u16>(stringify!(port), port)?,
+            None => gdb::DEFAULT_PORT,
+        };
+
+        println!("Info: waiting for gdb on 127.0.0.1:{}", port);
+        let result = gdb::listen(&mut self.system, port, self.interrupt.clone());
+        self.flush_serial();
+
+        if let Err(error) = result {
+            println!("Cli Operation Error: {}", error);
+            return Err(CliError::OperationError);
+        }
+
+        println!("Info: gdb detached");
+        Ok(())
+    }
+
+    pub fn echo(&self, command: Vec<&str>) -> Result<(), CliError> {
+        println!("{}", command[1..].join(" "));
+        Ok(())
+    }
+
+    // Compares registers, memory and constants like breakpoint conditions, e.g. `assert r2 == 0xd3`
+    pub fn assert(&self, command: Vec<&str>) -> Result<(), CliError> {
+        let Some(condition) = Self::unpack_condition(&command[1..])? else {
+            return Err(CliError::MissingParameter("condition"));
+        };
+
+        if condition.evaluate(&self.system) {
+            return Ok(());
+        }
+
+        let values = [condition.left, condition.right]
+            .iter()
+            .filter(|operand| !matches!(operand, ConditionOperand::Constant(_)))
+            .map(|operand| format!("{} = {:#04x}", operand, operand.value(&self.system)))
+            .collect::<Vec<_>>();
+
+        println!("Assertion failed: {}, {}", condition, values.join(", "));
+        Err(CliError::AssertionFailed)
+    }
+
+    pub fn new_compile(&mut self, command: Vec<&str>, listing: bool) -> Result<(), CliError> {
+        let input_path = command.get(1);
+        if input_path.is_none() {
+            return Err(CliError::MissingParameter(stringify!(input_path)));
+        }
+
+        let input_path = input_path.unwrap();
+
+        let output_path = command.get(2);
+        let output_path = output_path.unwrap_or(&"out.rom");
+
+        let compilation_result =
+            new_compiler::compile_file(Path::new(input_path), Path::new(output_path));
+
+        let diagnostics = match compilation_result {
+            Ok(diagnostics) => diagnostics,
+            Err(error) => {
+                println!("{}", error);
+                println!("Error: compilation failed");
+                return Err(CliError::OperationError);
+            }
+        };
+
+        if !diagnostics.is_empty() {
+            println!("{}", diagnostics);
+        }
+
+        println!(
+            "Info: Compilation successful, written to file: {}",
+            output_path
+        );
+
+        if listing {
+            let listing_path = new_compiler::listing_path_for_rom(Path::new(output_path));
+            if let Err(error) = new_compiler::listing_file(Path::new(input_path), &listing_path) {
+                println!("{}", error);
+                return Err(CliError::OperationError);
+            }
+
+            println!("Info: Listing written to file: {}", listing_path.display());
+        }
+
+        return Ok(());
+    }
+
+    pub fn compile(&mut self, command: Vec<&str>) -> Result<(), CliError> {
+        let input_path = command.get(1);
+        if input_path.is_none() {
+            return Err(CliError::MissingParameter(stringify!(input_path)));
+        }
+
+        let output_path = command.get(2);
+        let output_path = output_path.unwrap_or(&"out.rom");
+
+        let input_file = File::open(Path::new(input_path.unwrap()));
+        if input_file.is_err() {
+            return Err(CliError::FailedToReadFromFile);
+        }
+
+        let output_file = File::create(Path::new(output_path));
+        if output_file.is_err() {
+            return Err(CliError::FailedToWriteToFile);
+        }
+
+        let mut compiler = Compiler::new(input_file.unwrap(), output_file.unwrap());
+        let result = compiler.compile();
+        if result.is_ok() {
+            let symbols_path = SymbolTable::path_for_rom(Path::new(output_path));
+            if compiler.symbols().write(&symbols_path).is_err() {
+                return Err(CliError::FailedToWriteToFile);
+            }
+
+            println!(
+                "Info: Compilation succesful, written to file: {}",
+                output_path
+            );
+            Ok(())
+        } else {
+            println!("Error: Compilation failed: {:?}", result.err().unwrap());
+            Err(CliError::OperationError)
+        }
+    }
+
+    pub fn print_regs(&self) -> Result<(), CliError> {
+        let regs = self.system.get_regs();
+        for y in 0..4 {
+            for x in 0..4 {
+                let idx = y * 4 + x;
+
+                if idx < 10 {
+                    print!(" ");
+                }
+
+                let val = regs[idx];
+                match W::SIZE {
+                    WordSize::Bits8 => print!(
+                        "r{} = {:#04x}, {:#3}, '{}'\t",
+                        idx,
+                        val,
+                        val,
+                        val.low_byte() as char
+                    ),
+                    WordSize::Bits16 => print!("r{} = {:#06x}, {:#5}\t", idx, val, val),
+                }
+            }
+
+            match y {
+                0 => print!(
+                    "ip={:#04x} sp={:#06x}",
+                    self.system.get_ip(),
+                    self.system.get_sp()
+                ),
+                1 => {
+                    for flag in self.system.get_flags_register().get_flags() {
+                        print!("{}", flag);
+                    }
+                }
+                2 => print!("interrupts {}", self.system.interrupts()),
+                3 => print!("cycles={}", self.system.cycles()),
+                _ => {}
+            }
+
+            println!();
+        }
+
+        Ok(())
+    }
+
+    pub fn goto(&mut self, command: Vec<&str>) -> Result<(), CliError> {
+        let address = command.get(1);
+        if address.is_none() {
+            return Err(CliError::MissingParameter(stringify!(address)));
+        }
+
+        let address = Self::unpack::<u8>(stringify!(address), address.unwrap())?;
+        self.system.jump(address);
+        Ok(())
+    }
+
+    fn disassemble_single(&self, ip: u16) -> u16 {
+        let ram = self.system.bus().ram().as_slice();
+        let bytes = ram.get(ip as usize..).unwrap_or(&[]);
+
+        let name_of = |address| self.symbols.name_of(address).map(str::to_string);
+        if let Some(folded) = fold::fold(bytes, name_of) {
+            println!("{:#04x}: {}", ip, folded.text);
+            return folded.length;
+        }
+
+        let generated = Instruction::disassemble(bytes);
+
+        if let Ok(instruction) = generated {
+            println!("{:#04x}: {}", ip, instruction);
+
+            instruction.length()
+        } else {
+            println!("Error: Disassembly failed: {:?}", generated.unwrap_err());
+            1
+        }
+    }
+
+    pub fn disassemble(&self, command: Vec<&str>) -> Result<(), CliError> {
+        let from = command.get(1);
+        let to = command.get(2);
+
+        if let Some(to) = to {
+            let from = Self::unpack::<u16>(stringify!(from), from.unwrap())?;
+            let to = Self::unpack::<u16>(stringify!(to), to)?;
+
+            if from > to {
+                return Err(CliError::FailedParameterConstraint(stringify!(from > to)));
+            }
+
+            let mut ip = from as u16;
+            while ip < to {
+                ip += self.disassemble_single(ip);
+            }
+        } else if let Some(count) = from {
+            let count = Self::unpack::<u16>(stringify!(count), count)?;
+            let mut ip = self.system.get_ip() as u16;
+            for _ in 0..count {
+                ip += self.disassemble_single(ip);
+            }
+        } else {
+            _ = self.disassemble_single(self.system.get_ip() as u16);
+        }
+
+        Ok(())
+    }
+
+    fn print_single_read_memory(&self, address: u16, value: u8) {
+        println!(
+            "[{:#06x}]: {:#04x}, {:#3}, '{}'",
+            address, value, value, value as char
+        );
+    }
+
+    pub fn read_memory(&self, command: Vec<&str>) -> Result<(), CliError> {
+        let address = command.get(1);
+        if address.is_none() {
+            return Err(CliError::MissingParameter(stringify!(address)));
+        }
+
+        let mut address = Self::unpack::<u16>(stringify!(address), address.unwrap())?;
+
+        let count = command.get(2);
+        if let Some(count) = count {
+            let count = Self::unpack::<usize>(stringify!(count), count)?;
+
+            for _ in 0..count {
+                let value = self.system.get_mem(address).map_err(Self::memory_error)?;
+                self.print_single_read_memory(address, value);
+                address += 1;
+            }
+        } else {
+            let value = self.system.get_mem(address).map_err(Self::memory_error)?;
+            self.print_single_read_memory(address, value);
+        }
+
+        Ok(())
+    }
+
+    pub fn write_memory(&mut self, command: Vec<&str>) -> Result<(), CliError> {
+        let address = command.get(1);
+        if address.is_none() {
+            return Err(CliError::MissingParameter(stringify!(address)));
+        }
+
+        let byte = command.get(2);
+        if byte.is_none() {
+            return Err(CliError::MissingParameter(stringify!(byte)));
+        }
+
+        let mut address = Self::unpack::<u16>(stringify!(address), address.unwrap())?;
+        let byte = Self::unpack::<u8>(stringify!(byte), byte.unwrap())?;
+
+        let count = command.get(3);
+        if let Some(count) = count {
+            let count = Self::unpack::<usize>(stringify!(count), count)?;
+
+            for _ in 0..count {
+                self.system
+                    .set_mem(address, byte)
+                    .map_err(Self::memory_error)?;
+                address += 1;
+            }
+        } else {
+            self.system
+                .set_mem(address, byte)
+                .map_err(Self::memory_error)?;
+        }
+
+        Ok(())
+    }
+}
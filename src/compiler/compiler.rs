@@ -1,38 +1,53 @@
 use std::{
-    collections::VecDeque,
-    fs::File,
+    collections::{HashMap, VecDeque},
     io::{Read, Write},
 };
 
-use crate::types::*;
+use crate::{machine::word::WordSize, symbols::SymbolTable, types::*};
 
 use crate::compiler::{instruction::Instruction, token::Token};
 
 pub struct Bytecode {
-    instructions: Vec<Instruction>,
+    binary: Vec<u8>,
+    word_size: WordSize, // selected by `.wordsize`
 }
 
 impl Bytecode {
     pub fn new() -> Self {
         Self {
-            instructions: vec![],
+            binary: vec![],
+            word_size: WordSize::Bits8,
         }
     }
 
-    pub fn create_binary(&self) -> Vec<u8> {
-        let mut binary: Vec<u8> = vec![];
-        for instruction in &self.instructions {
-            binary.append(&mut instruction.serialize());
+    // address at which the next instruction or data will be placed
+    pub fn address(&self) -> u16 {
+        self.binary.len() as u16
+    }
+
+    pub fn push(&mut self, bytes: &[u8]) {
+        self.binary.extend_from_slice(bytes);
+    }
+
+    pub fn pad_to(&mut self, address: u16) -> Result<(), CompileError> {
+        if (address as usize) < self.binary.len() {
+            return Err(CompileError::InvalidOrigin(address));
         }
 
-        binary
+        self.binary.resize(address as usize, 0);
+        Ok(())
+    }
+
+    pub fn create_binary(&self) -> Vec<u8> {
+        self.binary.clone()
     }
 }
 
-pub struct Compiler {
-    input_file: File,
-    output_file: File,
+pub struct Compiler<I: Read, O: Write> {
+    input_file: I,
+    output_file: O,
     generated: Bytecode,
+    symbols: SymbolTable,
     collected_states: Vec<CompilationState>,
     line_number: i32,
     data: Vec<char>,
@@ -48,6 +63,15 @@ pub enum CompileError {
     InvalidNumber(String),
     UnexpectedTokenType(Token),
     UnexpectedCharacter(char),
+    UnknownLabel(String),
+    DuplicateLabel(String),
+    LabelOutOfRange(String, u16),
+    UnknownDirective(String),
+    InvalidDirectiveArguments(Directive),
+    InvalidOrigin(u16),
+    InvalidEscapeSequence(char),
+    ProgramTooLarge(usize),
+    UnknownOpcode(u8), // first byte of the instruction
 }
 
 #[derive(Debug, Clone)]
@@ -55,14 +79,21 @@ pub enum CompilationState {
     Comment(Vec<char>),
     Symbol(Vec<char>),
     Numeric(Vec<char>),
+    Label(Vec<char>),
+    Selector(Vec<char>, Vec<char>), // selector name, label name: `hi(label)`
+    String(Vec<char>),              // raw contents between the quotes, escapes not yet applied
 }
 
-impl Compiler {
-    pub fn new(input_file: File, output_file: File) -> Self {
+// Maximum size of the generated binary, the address space is 16-bit
+const ADDRESS_SPACE: usize = 0x10000;
+
+impl<I: Read, O: Write> Compiler<I, O> {
+    pub fn new(input_file: I, output_file: O) -> Self {
         Self {
             input_file,
             output_file,
             generated: Bytecode::new(),
+            symbols: SymbolTable::new(),
             collected_states: vec![],
             line_number: 0,
             data: vec![],
@@ -84,13 +115,42 @@ impl Compiler {
                 }
             }
             CompilationState::Symbol(ref mut data) => {
-                if !c.is_ascii_alphanumeric() {
+                if c == ':' {
+                    self.collected_states
+                        .push(CompilationState::Label(data.clone()));
+                    return Ok(None);
+                } else if c == '(' {
+                    return Ok(Some(CompilationState::Selector(data.clone(), vec![])));
+                } else if !(c.is_ascii_alphanumeric() || c == '_') {
                     self.collected_states.push(state);
                     return Ok(None);
                 } else {
                     data.push(c);
                 }
             }
+            CompilationState::Selector(_, ref mut label) => {
+                if c == ')' {
+                    self.collected_states.push(state);
+                    return Ok(None);
+                } else if c.is_ascii_alphanumeric() || c == '_' {
+                    label.push(c);
+                } else {
+                    return Err(CompileError::UnexpectedCharacter(c));
+                }
+            }
+            CompilationState::String(ref mut data) => {
+                let escaped = data.iter().rev().take_while(|c| **c == '\\').count() % 2 == 1;
+                if c == '"' && !escaped {
+                    self.collected_states.push(state);
+                    return Ok(None);
+                } else {
+                    data.push(c);
+                }
+            }
+            CompilationState::Label(_) => {
+                // labels are pushed as soon as the ':' is consumed
+                return Err(CompileError::UnhandledState(state));
+            }
             CompilationState::Numeric(ref mut data) => {
                 if !(c.is_ascii_digit()
                     || ('a'..='f').contains(&c)
@@ -123,7 +183,9 @@ impl Compiler {
                 match c {
                     '#' => current_state = Some(CompilationState::Comment(vec![])),
 
-                    _ if c.is_ascii_alphabetic() => {
+                    '"' => current_state = Some(CompilationState::String(vec![])),
+
+                    _ if c.is_ascii_alphabetic() || c == '_' || c == '.' => {
                         current_state = Some(CompilationState::Symbol(vec![c]))
                     }
 
@@ -131,7 +193,7 @@ impl Compiler {
                         current_state = Some(CompilationState::Numeric(vec![c]))
                     }
 
-                    _ if c.is_ascii_whitespace() => {}
+                    _ if c.is_ascii_whitespace() || c == ',' => {}
 
                     _ => return Err(CompileError::UnexpectedCharacter(c)),
                 };
@@ -159,6 +221,24 @@ impl Compiler {
         for state in &self.collected_states {
             match state {
                 CompilationState::Comment(_) => {}
+                CompilationState::Label(data) => tokens.push(Token::Label(String::from_iter(data))),
+                CompilationState::Selector(selector, label) => {
+                    let selector = match String::from_iter(selector).to_lowercase().as_str() {
+                        "hi" => LabelSelector::High,
+                        "lo" => LabelSelector::Low,
+                        _ => return Err(CompileError::UnknownSymbol(String::from_iter(selector))),
+                    };
+
+                    tokens.push(Token::LabelRef(String::from_iter(label), selector));
+                }
+                CompilationState::String(data) => tokens.push(Token::String(Self::unescape(data)?)),
+                CompilationState::Symbol(data) if data[0] == '.' => {
+                    let data_str = String::from_iter(data);
+                    match Directive::try_from(data_str.as_str()) {
+                        Ok(directive) => tokens.push(Token::Directive(directive)),
+                        Err(_) => return Err(CompileError::UnknownDirective(data_str)),
+                    }
+                }
                 CompilationState::Symbol(data) => {
                     let data_str = String::from_iter(data);
                     let result = Opcode::try_from(data_str.as_str());
@@ -173,14 +253,15 @@ impl Compiler {
                         continue;
                     }
 
-                    return Err(CompileError::UnknownSymbol(String::from_iter(data)));
+                    // anything else is a reference to a (possibly not yet defined) label
+                    tokens.push(Token::LabelRef(data_str, LabelSelector::Address));
                 }
                 CompilationState::Numeric(data) => {
                     let str = String::from_iter(data);
-                    let mut number = str.parse::<u8>();
+                    let mut number = str.parse::<u16>();
                     if number.is_err() {
                         if data.len() > 2 && data[0] == '0' && data[1] == 'x' {
-                            number = u8::from_str_radix(str.trim_start_matches("0x"), 16);
+                            number = u16::from_str_radix(str.trim_start_matches("0x"), 16);
                         }
 
                         // second pass
@@ -189,7 +270,11 @@ impl Compiler {
                         }
                     }
 
-                    tokens.push(Token::Immediate(number.unwrap()));
+                    let number = number.unwrap();
+                    match u8::try_from(number) {
+                        Ok(byte) => tokens.push(Token::Immediate(byte)),
+                        Err(_) => tokens.push(Token::Word(number)),
+                    }
                 }
             }
         }
@@ -197,39 +282,248 @@ impl Compiler {
         Ok(tokens)
     }
 
-    fn create_bytecode(&mut self, tokens: &mut VecDeque<Token>) -> Result<(), CompileError> {
-        let mut control_token: Option<Token> = None;
-        loop {
-            if let Some(ref token) = control_token {
-                if let Token::Opcode(opcode) = token {
-                    let mut token_consumer = || {
-                        if tokens.is_empty() {
-                            Err(CompileError::UnexpectedEOF)
-                        } else {
-                            Ok(tokens.pop_front().unwrap())
+    fn unescape(data: &[char]) -> Result<Vec<u8>, CompileError> {
+        let mut bytes = vec![];
+        let mut chars = data.iter();
+        while let Some(c) = chars.next() {
+            if *c != '\\' {
+                bytes.push(*c as u8);
+                continue;
+            }
+
+            bytes.push(match chars.next() {
+                Some('n') => b'\n',
+                Some('r') => b'\r',
+                Some('t') => b'\t',
+                Some('0') => 0,
+                Some('\\') => b'\\',
+                Some('"') => b'"',
+                Some(c) => return Err(CompileError::InvalidEscapeSequence(*c)),
+                None => return Err(CompileError::UnexpectedEOF),
+            });
+        }
+
+        Ok(bytes)
+    }
+
+    // Resolves a label to its address. During the first pass labels may not be defined yet,
+    // which is fine since the address does not influence the size of the output.
+    fn label_address(
+        name: &str,
+        labels: &HashMap<String, u16>,
+        resolve: bool,
+    ) -> Result<u16, CompileError> {
+        match labels.get(name) {
+            Some(address) => Ok(*address),
+            None if resolve => Err(CompileError::UnknownLabel(name.to_string())),
+            None => Ok(0),
+        }
+    }
+
+    // Converts an operand token to the token an instruction expects
+    fn resolve_operand(
+        token: Token,
+        labels: &HashMap<String, u16>,
+        resolve: bool,
+    ) -> Result<Token, CompileError> {
+        Ok(match token {
+            Token::LabelRef(name, selector) => {
+                let address = Self::label_address(&name, labels, resolve)?;
+
+                Token::Immediate(match selector {
+                    LabelSelector::High => (address >> 8) as u8,
+                    LabelSelector::Low => address as u8,
+                    LabelSelector::Address => match u8::try_from(address) {
+                        Ok(byte) => byte,
+                        Err(_) => return Err(CompileError::LabelOutOfRange(name, address)),
+                    },
+                })
+            }
+            Token::Word(number) => return Err(CompileError::InvalidNumber(number.to_string())),
+            token => token,
+        })
+    }
+
+    fn emit_directive(
+        directive: Directive,
+        arguments: Vec<Token>,
+        bytecode: &mut Bytecode,
+        labels: &HashMap<String, u16>,
+        resolve: bool,
+    ) -> Result<(), CompileError> {
+        let invalid = || CompileError::InvalidDirectiveArguments(directive);
+
+        // numeric arguments that determine the layout may not depend on labels
+        let number = |token: Option<&Token>| match token {
+            Some(Token::Immediate(value)) => Ok(*value as u16),
+            Some(Token::Word(value)) => Ok(*value),
+            _ => Err(invalid()),
+        };
+
+        match directive {
+            Directive::Org => {
+                if arguments.len() != 1 {
+                    return Err(invalid());
+                }
+
+                bytecode.pad_to(number(arguments.first())?)?;
+            }
+            Directive::Db => {
+                if arguments.is_empty() {
+                    return Err(invalid());
+                }
+
+                for argument in arguments {
+                    match Self::resolve_operand(argument, labels, resolve)? {
+                        Token::Immediate(byte) => bytecode.push(&[byte]),
+                        Token::String(bytes) => bytecode.push(&bytes),
+                        _ => return Err(invalid()),
+                    }
+                }
+            }
+            Directive::Dw | Directive::Word => {
+                if arguments.is_empty() {
+                    return Err(invalid());
+                }
+
+                let word_size = match directive {
+                    Directive::Dw => WordSize::Bits16,
+                    _ => bytecode.word_size,
+                };
+
+                for argument in arguments {
+                    let word = match argument {
+                        Token::Immediate(value) => value as u16,
+                        Token::Word(value) => value,
+                        Token::LabelRef(name, LabelSelector::Address) => {
+                            Self::label_address(&name, labels, resolve)?
                         }
+                        Token::LabelRef(..) => {
+                            match Self::resolve_operand(argument, labels, resolve)? {
+                                Token::Immediate(byte) => byte as u16,
+                                _ => return Err(invalid()),
+                            }
+                        }
+                        _ => return Err(invalid()),
                     };
 
-                    let instruction = Instruction::generate(*opcode, &mut token_consumer)?;
-                    self.generated.instructions.push(instruction);
-                    control_token = None;
+                    match word_size {
+                        WordSize::Bits8 if word > u8::MAX as u16 => return Err(invalid()),
+                        WordSize::Bits8 => bytecode.push(&[word as u8]),
+                        WordSize::Bits16 => bytecode.push(&word.to_be_bytes()),
+                    }
+                }
+            }
+            Directive::WordSize => {
+                if arguments.len() != 1 {
+                    return Err(invalid());
+                }
 
-                    continue;
+                let bits = number(arguments.first())?;
+                bytecode.word_size = WordSize::from_bits(bits as u32).ok_or_else(invalid)?;
+            }
+            Directive::Ascii | Directive::Asciiz => {
+                if arguments.is_empty() {
+                    return Err(invalid());
                 }
 
-                return Err(CompileError::UnexpectedTokenType(token.clone()));
+                for argument in arguments {
+                    let Token::String(bytes) = argument else {
+                        return Err(invalid());
+                    };
+
+                    bytecode.push(&bytes);
+                    if matches!(directive, Directive::Asciiz) {
+                        bytecode.push(&[0]);
+                    }
+                }
             }
+            Directive::Fill => {
+                let value = match arguments.get(1) {
+                    Some(Token::Immediate(value)) => *value,
+                    None => 0,
+                    _ => return Err(invalid()),
+                };
 
-            if tokens.is_empty() {
-                break;
+                if arguments.len() > 2 {
+                    return Err(invalid());
+                }
+
+                let count = number(arguments.first())?;
+                bytecode.push(&vec![value; count as usize]);
             }
+            Directive::Align => {
+                let alignment = number(arguments.first())?;
+                if arguments.len() != 1 || alignment == 0 {
+                    return Err(invalid());
+                }
 
-            control_token = Some(tokens.pop_front().unwrap());
+                let address = bytecode.address() as usize;
+                let aligned = address.next_multiple_of(alignment as usize);
+                bytecode.push(&vec![0; aligned - address]);
+            }
         }
 
         Ok(())
     }
 
+    // Lays out all instructions and data. The first pass (resolve = false) only collects the
+    // address of every label, the second pass uses those to generate the final bytecode.
+    fn create_bytecode(
+        tokens: &mut VecDeque<Token>,
+        labels: &mut HashMap<String, u16>,
+        resolve: bool,
+    ) -> Result<Bytecode, CompileError> {
+        let mut bytecode = Bytecode::new();
+
+        while let Some(token) = tokens.pop_front() {
+            match token {
+                Token::Label(name) => {
+                    if !resolve && labels.insert(name.clone(), bytecode.address()).is_some() {
+                        return Err(CompileError::DuplicateLabel(name));
+                    }
+                }
+                Token::Opcode(opcode) => {
+                    let mut token_consumer = || match tokens.pop_front() {
+                        Some(token) => Self::resolve_operand(token, labels, resolve),
+                        None => Err(CompileError::UnexpectedEOF),
+                    };
+
+                    let instruction = Instruction::generate(opcode, &mut token_consumer)?;
+                    bytecode.push(&instruction.serialize());
+                }
+                Token::Directive(directive) => {
+                    let mut arguments = vec![];
+                    while matches!(
+                        tokens.front(),
+                        Some(
+                            Token::Immediate(_)
+                                | Token::Word(_)
+                                | Token::String(_)
+                                | Token::LabelRef(..)
+                        )
+                    ) {
+                        arguments.push(tokens.pop_front().unwrap());
+                    }
+
+                    Self::emit_directive(directive, arguments, &mut bytecode, labels, resolve)?;
+                }
+                token => return Err(CompileError::UnexpectedTokenType(token)),
+            }
+
+            if bytecode.binary.len() > ADDRESS_SPACE {
+                return Err(CompileError::ProgramTooLarge(bytecode.binary.len()));
+            }
+        }
+
+        Ok(bytecode)
+    }
+
+    // Label addresses of the last compilation
+    pub fn symbols(&self) -> &SymbolTable {
+        &self.symbols
+    }
+
     pub fn compile(&mut self) -> Result<(), CompileError> {
         let mut data = String::new();
         if self.input_file.read_to_string(&mut data).is_err() {
@@ -240,7 +534,11 @@ impl Compiler {
         _ = self.collect_states()?;
 
         let tokens = self.flatten_states()?;
-        _ = self.create_bytecode(&mut VecDeque::from(tokens))?;
+
+        let mut labels = HashMap::new();
+        _ = Self::create_bytecode(&mut VecDeque::from(tokens.clone()), &mut labels, false)?;
+        self.generated = Self::create_bytecode(&mut VecDeque::from(tokens), &mut labels, true)?;
+        self.symbols = SymbolTable::from(labels);
 
         // flush to output
         let binary = &self.generated.create_binary();
@@ -0,0 +1,93 @@
+use crate::types::{Opcode, ADDRESS_REGISTERS};
+
+use super::instruction::Instruction;
+
+// Pseudo-instruction recognized in disassembled code
+#[derive(Debug, Clone, PartialEq)]
+pub struct Folded {
+    pub text: String,
+    pub length: u16, // bytes of the folded instructions
+}
+
+// Recognizes the instruction sequences the assembler expands pseudo-instructions and
+// instructions given an address into, `name_of` supplies label names for addresses. Two LDIs
+// are only folded into LDI16 if they load the address of a label into consecutive registers,
+// unrelated loads look the same.
+pub fn fold<F>(bytes: &[u8], name_of: F) -> Option<Folded>
+where
+    F: Fn(u16) -> Option<String>,
+{
+    let mut instructions = vec![];
+    let mut offset = 0;
+    while instructions.len() < 3 {
+        let Ok(instruction) = Instruction::disassemble(bytes.get(offset..).unwrap_or(&[])) else {
+            break;
+        };
+
+        offset += instruction.length() as usize;
+        instructions.push(instruction);
+    }
+
+    let length = |count: usize| instructions[..count].iter().map(Instruction::length).sum();
+    let address = |value: u16| name_of(value).unwrap_or_else(|| format!("{:#06x}", value));
+    let (high, low) = ADDRESS_REGISTERS;
+
+    let (text, count) = match instructions.as_slice() {
+        [Instruction::RegImm(Opcode::LDI, rh, h), Instruction::RegImm(Opcode::LDI, rl, l), rest @ ..] =>
+        {
+            let value = u16::from_be_bytes([*h, *l]);
+            let loads_address = *rh == high && *rl == low;
+
+            match rest.first() {
+                Some(Instruction::DoubleReg(opcode, a, b))
+                    if loads_address && opcode.is_jump() && *a == high && *b == low =>
+                {
+                    (format!("{:?} {}", opcode, address(value)), 3)
+                }
+                Some(Instruction::TripleReg(opcode @ (Opcode::LB | Opcode::SB), rd, a, b))
+                    if loads_address && *a == high && *b == low =>
+                {
+                    (format!("{:?} {:?} [{}]", opcode, rd, address(value)), 3)
+                }
+                _ if *rl as u8 == *rh as u8 + 1 => {
+                    let name = name_of(value)?;
+                    (format!("LDI16 {:?}:{:?} {}", rh, rl, name), 2)
+                }
+                _ => return None,
+            }
+        }
+        [Instruction::TripleReg(Opcode::OR, rd, rs, rt), ..] if rs == rt => {
+            (format!("MOV {:?} {:?}", rd, rs), 1)
+        }
+        _ => return None,
+    };
+
+    Some(Folded {
+        text,
+        length: length(count),
+    })
+}
+
+// Text and length of the instruction at the start of `bytes`, expanded pseudo-instructions are
+// folded unless `raw`. A byte that starts no instruction is shown as `.db`.
+pub fn disassemble<F>(bytes: &[u8], name_of: F, raw: bool) -> Folded
+where
+    F: Fn(u16) -> Option<String>,
+{
+    if !raw {
+        if let Some(folded) = fold(bytes, name_of) {
+            return folded;
+        }
+    }
+
+    match Instruction::disassemble(bytes) {
+        Ok(instruction) => Folded {
+            text: instruction.to_string(),
+            length: instruction.length(),
+        },
+        Err(_) => Folded {
+            text: format!(".db {:#04x}", bytes.first().copied().unwrap_or(0)),
+            length: 1,
+        },
+    }
+}
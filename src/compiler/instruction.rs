@@ -3,9 +3,10 @@ use std::fmt::Display;
 use crate::compiler::{compiler::CompileError, token::Token};
 use crate::types::*;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Instruction {
     NoParam(Opcode),
+    SingleReg(Opcode, Register),
     RegImm(Opcode, Register, u8),
     DoubleReg(Opcode, Register, Register),
     DoubleRegImm4(Opcode, Register, Register, u8),
@@ -13,17 +14,28 @@ pub enum Instruction {
 }
 
 impl Instruction {
-    pub fn disassemble(first_byte: u8, second_byte: u8) -> Result<Instruction, CompileError> {
-        let opcode_raw = first_byte >> 4;
-        let opcode = Opcode::try_from(opcode_raw);
-        if opcode.is_err() {
-            println!("Error: Disassembly failed: Unknown opcode {}", opcode_raw);
-            return Err(CompileError::UnexpectedEOF);
-        }
+    // Decodes the instruction at the start of `bytes`, the operands of extended instructions
+    // follow the extended opcode
+    pub fn disassemble(bytes: &[u8]) -> Result<Instruction, CompileError> {
+        let byte = |index: usize| bytes.get(index).copied().ok_or(CompileError::UnexpectedEOF);
+
+        let first_byte = byte(0)?;
+        let (opcode, operands) = match Opcode::decode(|offset| bytes.get(offset as usize).copied())
+        {
+            Ok((opcode, operands)) => (opcode, operands as usize),
+            Err(OpcodeConversionError::Truncated) => return Err(CompileError::UnexpectedEOF),
+            Err(OpcodeConversionError::NoSuchOpcode) => {
+                return Err(CompileError::UnknownOpcode(first_byte))
+            }
+        };
 
-        let opcode = opcode.unwrap();
         let reg_raw = first_byte & 0b1111;
 
+        let second_byte = match Instruction::get_type(opcode) {
+            InstructionType::NoParam | InstructionType::SingleReg => 0,
+            _ => byte(operands)?,
+        };
+
         let get_reg = |raw: u8| {
             if let Ok(result) = Register::try_from(raw) {
                 Ok(Token::Register(result))
@@ -39,6 +51,8 @@ impl Instruction {
         let mut tokens = match Instruction::get_type(opcode) {
             InstructionType::NoParam => vec![],
 
+            InstructionType::SingleReg => vec![get_reg(reg_raw)],
+
             InstructionType::RegImm => vec![get_reg(reg_raw), Ok(imm())],
 
             InstructionType::DoubleReg => vec![get_reg(reg_raw), get_reg(second_byte >> 4)],
@@ -83,16 +97,64 @@ impl Instruction {
             Opcode::NOT => InstructionType::DoubleReg,
             Opcode::AND => InstructionType::TripleReg,
             Opcode::OR => InstructionType::TripleReg,
+            Opcode::EI => InstructionType::NoParam,
+            Opcode::DI => InstructionType::NoParam,
+            Opcode::RETI => InstructionType::NoParam,
+            Opcode::ADC => InstructionType::TripleReg,
+            Opcode::SBC => InstructionType::TripleReg,
+            Opcode::CMP => InstructionType::DoubleReg,
+            Opcode::JZ
+            | Opcode::JNC
+            | Opcode::JS
+            | Opcode::JNS
+            | Opcode::JO
+            | Opcode::JNO
+            | Opcode::JL
+            | Opcode::JGE
+            | Opcode::JLE
+            | Opcode::JG
+            | Opcode::JBE
+            | Opcode::JA
+            | Opcode::JMP => InstructionType::DoubleReg,
+            Opcode::PUSH => InstructionType::SingleReg,
+            Opcode::POP => InstructionType::SingleReg,
+            Opcode::CALL => InstructionType::DoubleReg,
+            Opcode::RET => InstructionType::NoParam,
+            Opcode::MUL
+            | Opcode::MULH
+            | Opcode::IMUL
+            | Opcode::IMULH
+            | Opcode::DIV
+            | Opcode::MOD
+            | Opcode::IDIV
+            | Opcode::IMOD => InstructionType::TripleReg,
+            Opcode::ROL | Opcode::ROR | Opcode::RCL | Opcode::RCR | Opcode::ASR => {
+                InstructionType::DoubleRegImm4
+            }
+            Opcode::SHLV
+            | Opcode::SHRV
+            | Opcode::ASRV
+            | Opcode::ROLV
+            | Opcode::RORV
+            | Opcode::RCLV
+            | Opcode::RCRV => InstructionType::TripleReg,
         }
     }
 
     pub fn get_length(opcode: Opcode) -> u16 {
-        match Self::get_type(opcode) {
+        let length = match Self::get_type(opcode) {
             InstructionType::NoParam => 1,
+            InstructionType::SingleReg => 1,
             InstructionType::RegImm => 2,
             InstructionType::DoubleReg => 2,
             InstructionType::DoubleRegImm4 => 2,
             InstructionType::TripleReg => 2,
+        };
+
+        // the extended opcode is inserted after the first byte
+        match opcode.extension_bytes() {
+            Some(extension) => length + extension.len() as u16,
+            None => length,
         }
     }
 }
@@ -102,6 +164,8 @@ impl Display for Instruction {
         match self {
             Self::NoParam(opcode) => write!(f, "{:?}", opcode),
 
+            Self::SingleReg(opcode, reg) => write!(f, "{:?} {:?}", opcode, reg),
+
             Self::RegImm(opcode, reg, imm) => write!(f, "{:?} {:?} {:#02x}", opcode, reg, imm),
 
             Self::DoubleReg(opcode, reg, regb) => write!(f, "{:?} {:?} {:?}", opcode, reg, regb),
@@ -118,9 +182,44 @@ impl Display for Instruction {
 }
 
 impl Instruction {
+    pub fn opcode(&self) -> Opcode {
+        match self {
+            Self::NoParam(opcode)
+            | Self::SingleReg(opcode, ..)
+            | Self::RegImm(opcode, ..)
+            | Self::DoubleReg(opcode, ..)
+            | Self::DoubleRegImm4(opcode, ..)
+            | Self::TripleReg(opcode, ..) => *opcode,
+        }
+    }
+
+    pub fn length(&self) -> u16 {
+        Self::get_length(self.opcode())
+    }
+
     pub fn serialize(&self) -> Vec<u8> {
+        let mut bytes = self.serialize_primary();
+        let opcode = self.opcode();
+
+        // [1111 reg1] [escapes..] [extended opcode] [remaining operands]
+        if let Some(extension) = opcode.extension_bytes() {
+            bytes[0] = Opcode::EXTENSION << 4 | (bytes[0] & 0b1111);
+            bytes.splice(1..1, extension);
+        }
+
+        // [0101 reg1] [reg2 condition]
+        if let Some(condition) = opcode.branch_condition() {
+            bytes[0] = (Opcode::JNZ as u8) << 4 | (bytes[0] & 0b1111);
+            bytes[1] |= condition as u8;
+        }
+
+        bytes
+    }
+
+    fn serialize_primary(&self) -> Vec<u8> {
         return match self {
             Self::NoParam(opcode) => vec![(*opcode as u8) << 4],
+            Self::SingleReg(opcode, reg) => vec![(*opcode as u8) << 4 | *reg as u8],
             Self::RegImm(opcode, reg, imm) => vec![(*opcode as u8) << 4 | *reg as u8, *imm],
             Self::DoubleReg(opcode, reg, reg2) => {
                 vec![(*opcode as u8) << 4 | *reg as u8, (*reg2 as u8) << 4]
@@ -151,6 +250,10 @@ impl Instruction {
 
         let create_no_param = |opcode| Instruction::NoParam(opcode);
 
+        let create_single_reg = |opcode, consumer: &mut F| -> Result<Instruction, CompileError> {
+            Ok(Instruction::SingleReg(opcode, reg(consumer)?))
+        };
+
         let create_reg_imm = |opcode, consumer: &mut F| -> Result<Instruction, CompileError> {
             Ok(Instruction::RegImm(opcode, reg(consumer)?, imm(consumer)?))
         };
@@ -185,6 +288,7 @@ impl Instruction {
         let itype = Instruction::get_type(opcode);
         Ok(match itype {
             InstructionType::NoParam => create_no_param(opcode),
+            InstructionType::SingleReg => create_single_reg(opcode, &mut consumer)?,
             InstructionType::RegImm => create_reg_imm(opcode, &mut consumer)?,
             InstructionType::DoubleReg => create_double_reg(opcode, &mut consumer)?,
             InstructionType::DoubleRegImm4 => create_double_reg_imm4(opcode, &mut consumer)?,
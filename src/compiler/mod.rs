@@ -1,3 +1,4 @@
 pub mod compiler;
+pub mod fold;
 pub mod instruction;
 pub mod token;
@@ -1,8 +1,13 @@
-use crate::types::{Opcode, Register};
+use crate::types::{Directive, LabelSelector, Opcode, Register};
 
 #[derive(Debug, Clone)]
 pub enum Token {
     Opcode(Opcode),
     Register(Register),
     Immediate(u8),
+    Word(u16), // number that does not fit in an immediate byte
+    String(Vec<u8>),
+    Directive(Directive),
+    Label(String),                   // label definition, `name:`
+    LabelRef(String, LabelSelector), // label used as an operand, resolved in the second pass
 }
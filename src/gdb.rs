@@ -0,0 +1,552 @@
+use std::{
+    collections::HashMap,
+    io::{self, ErrorKind, Read, Write},
+    net::{Ipv4Addr, TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use crate::machine::{
+    computer::System,
+    debugger::{BreakpointKind, StopReason, WatchMode},
+    fault::ExecutionFault,
+    flags::FlagsRegister,
+    word::Word,
+};
+
+// Server of the GDB remote serial protocol. GDB reads the registers from the target description,
+// r0-r15 are followed by sp, ip and flags, all in big-endian byte order like words in memory.
+
+pub const DEFAULT_PORT: u16 = 1234;
+
+const REGISTER_SP: usize = 16;
+const REGISTER_IP: usize = 17;
+const REGISTER_FLAGS: usize = 18;
+const REGISTER_COUNT: usize = 19;
+
+// Instructions executed by `c` between looking for an interrupt request of the client
+const POLL_INTERVAL: usize = 1000;
+
+const INTERRUPT_REQUEST: u8 = 0x03;
+
+// Signals of stop replies
+const SIGINT: u8 = 2;
+const SIGILL: u8 = 4;
+const SIGTRAP: u8 = 5;
+const SIGFPE: u8 = 8;
+const SIGSEGV: u8 = 11;
+
+// Connection to the client, running programs are interrupted when it requests so
+pub trait Connection: Read + Write {
+    // Whether the client sent an interrupt request without waiting for one
+    fn interrupt_requested(&mut self) -> bool {
+        false
+    }
+}
+
+impl Connection for TcpStream {
+    fn interrupt_requested(&mut self) -> bool {
+        if self.set_nonblocking(true).is_err() {
+            return false;
+        }
+
+        let mut byte = [0];
+        let requested = matches!(self.peek(&mut byte), Ok(1) if byte[0] == INTERRUPT_REQUEST);
+        if requested {
+            _ = self.read(&mut byte);
+        }
+
+        _ = self.set_nonblocking(false);
+        requested
+    }
+}
+
+// Target description of a machine with words of type `W`
+pub fn target_xml<W: Word>() -> String {
+    let bits = W::SIZE.bits();
+    let mut xml = "<?xml version=\"1.0\"?>\n\
+        <!DOCTYPE target SYSTEM \"gdb-target.dtd\">\n\
+        <target version=\"1.0\">\n\
+        <feature name=\"org.mrt-cpu.core\">\n"
+        .to_string();
+
+    for index in 0..16 {
+        xml.push_str(&format!(
+            "<reg name=\"r{}\" bitsize=\"{}\" type=\"int\" regnum=\"{}\"/>\n",
+            index, bits, index
+        ));
+    }
+
+    xml.push_str(
+        "<reg name=\"sp\" bitsize=\"16\" type=\"data_ptr\"/>\n\
+        <reg name=\"ip\" bitsize=\"16\" type=\"code_ptr\"/>\n\
+        <reg name=\"flags\" bitsize=\"8\" type=\"int\"/>\n\
+        </feature>\n\
+        </target>\n",
+    );
+
+    xml
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn unhex(text: &[u8]) -> Option<Vec<u8>> {
+    if !text.len().is_multiple_of(2) {
+        return None;
+    }
+
+    text.chunks(2)
+        .map(|pair| u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok())
+        .collect()
+}
+
+fn number(text: &[u8]) -> Option<usize> {
+    usize::from_str_radix(std::str::from_utf8(text).ok()?, 16).ok()
+}
+
+// `address,length` of memory and breakpoint packets
+fn address_and_length(text: &[u8]) -> Option<(u16, usize)> {
+    let comma = text.iter().position(|&c| c == b',')?;
+    let address = u16::try_from(number(&text[..comma])?).ok()?;
+    Some((address, number(&text[comma + 1..])?))
+}
+
+// Binary data of `qXfer` replies, `#`, `$`, `}` and `*` are escaped
+fn escape(data: &[u8]) -> Vec<u8> {
+    let mut escaped = vec![];
+    for &byte in data {
+        match byte {
+            b'#' | b'$' | b'}' | b'*' => escaped.extend([b'}', byte ^ 0x20]),
+            _ => escaped.push(byte),
+        }
+    }
+
+    escaped
+}
+
+fn checksum(data: &[u8]) -> u8 {
+    data.iter().fold(0, |sum: u8, &byte| sum.wrapping_add(byte))
+}
+
+// Whether the session goes on after a packet
+enum Session {
+    Continue,
+    Detach,
+}
+
+pub struct GdbStub<'a, W: Word, C: Connection> {
+    system: &'a mut System<W>,
+    connection: C,
+    acknowledge: bool,                      // until the client asks for no-ack mode
+    breakpoints: HashMap<(u8, u16), usize>, // ids by `Z` type and address
+    interrupt: Option<Arc<AtomicBool>>,
+}
+
+impl<'a, W: Word, C: Connection> GdbStub<'a, W, C> {
+    pub fn new(system: &'a mut System<W>, connection: C) -> Self {
+        Self {
+            system,
+            connection,
+            acknowledge: true,
+            breakpoints: HashMap::new(),
+            interrupt: None,
+        }
+    }
+
+    // Flag that stops a running program like an interrupt request of the client, e.g. Ctrl-C
+    pub fn set_interrupt(&mut self, interrupt: Arc<AtomicBool>) {
+        self.interrupt = Some(interrupt);
+    }
+
+    // Answers packets until the client detaches, kills the program or disconnects. The
+    // breakpoints set by the client are deleted afterwards.
+    pub fn serve(&mut self) -> io::Result<()> {
+        let result = self.serve_packets();
+
+        for (_, id) in self.breakpoints.drain() {
+            _ = self.system.breakpoints_mut().delete(id);
+        }
+
+        result
+    }
+
+    fn serve_packets(&mut self) -> io::Result<()> {
+        while let Some(packet) = self.receive()? {
+            if let Session::Detach = self.handle(&packet)? {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn read_byte(&mut self) -> io::Result<Option<u8>> {
+        let mut byte = [0];
+        match self.connection.read(&mut byte) {
+            Ok(0) => Ok(None),
+            Ok(_) => Ok(Some(byte[0])),
+            Err(error) if error.kind() == ErrorKind::Interrupted => self.read_byte(),
+            Err(error) => Err(error),
+        }
+    }
+
+    // Next packet with a valid checksum, None once the client disconnected
+    fn receive(&mut self) -> io::Result<Option<Vec<u8>>> {
+        loop {
+            // acknowledgements and interrupt requests of stopped programs are skipped
+            match self.read_byte()? {
+                None => return Ok(None),
+                Some(b'$') => {}
+                Some(_) => continue,
+            }
+
+            let mut packet = vec![];
+            loop {
+                match self.read_byte()? {
+                    None => return Ok(None),
+                    Some(b'#') => break,
+                    Some(byte) => packet.push(byte),
+                }
+            }
+
+            let mut sum = [0; 2];
+            self.connection.read_exact(&mut sum)?;
+
+            let valid = unhex(&sum).is_some_and(|sum| sum[0] == checksum(&packet));
+            if self.acknowledge {
+                self.connection.write_all(if valid { b"+" } else { b"-" })?;
+            }
+
+            if valid {
+                return Ok(Some(packet));
+            }
+        }
+    }
+
+    fn send(&mut self, data: &[u8]) -> io::Result<()> {
+        let mut packet = vec![b'$'];
+        packet.extend(data);
+        packet.extend(format!("#{:02x}", checksum(data)).bytes());
+
+        loop {
+            self.connection.write_all(&packet)?;
+            self.connection.flush()?;
+
+            if !self.acknowledge {
+                return Ok(());
+            }
+
+            // resent until the client acknowledges it
+            match self.read_byte()? {
+                Some(b'-') => continue,
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    fn reply(&mut self, text: &str) -> io::Result<Session> {
+        self.send(text.as_bytes())?;
+        Ok(Session::Continue)
+    }
+
+    fn handle(&mut self, packet: &[u8]) -> io::Result<Session> {
+        let Some((&kind, body)) = packet.split_first() else {
+            return self.reply("");
+        };
+
+        match kind {
+            b'?' => self.reply(&format!("S{:02x}", SIGTRAP)),
+            b'g' => {
+                let registers = (0..REGISTER_COUNT)
+                    .flat_map(|index| self.register(index))
+                    .collect::<Vec<_>>();
+                self.reply(&hex(&registers))
+            }
+            b'G' => {
+                let written = unhex(body).is_some_and(|bytes| self.write_registers(&bytes));
+                self.reply(if written { "OK" } else { "E01" })
+            }
+            b'p' => match number(body).filter(|&index| index < REGISTER_COUNT) {
+                Some(index) => {
+                    let value = hex(&self.register(index));
+                    self.reply(&value)
+                }
+                None => self.reply("E01"),
+            },
+            b'P' => {
+                let written = body
+                    .iter()
+                    .position(|&c| c == b'=')
+                    .and_then(|equals| {
+                        Some((number(&body[..equals])?, unhex(&body[equals + 1..])?))
+                    })
+                    .is_some_and(|(index, bytes)| self.write_register(index, &bytes));
+                self.reply(if written { "OK" } else { "E01" })
+            }
+            b'm' => match address_and_length(body) {
+                Some((address, length)) => {
+                    let memory = self.read_memory(address, length);
+                    self.reply(&memory)
+                }
+                None => self.reply("E01"),
+            },
+            b'M' => {
+                let written = body
+                    .iter()
+                    .position(|&c| c == b':')
+                    .and_then(|colon| {
+                        Some((
+                            address_and_length(&body[..colon])?,
+                            unhex(&body[colon + 1..])?,
+                        ))
+                    })
+                    .filter(|((_, length), bytes)| *length == bytes.len())
+                    .is_some_and(|((address, _), bytes)| self.write_memory(address, &bytes));
+                self.reply(if written { "OK" } else { "E14" })
+            }
+            b's' | b'c' => {
+                // resuming at an address is not supported
+                if !body.is_empty() {
+                    return self.reply("E01");
+                }
+
+                let reason = match kind {
+                    b's' => self.system.run(Some(1), || false),
+                    _ => self.resume(),
+                };
+
+                let stop = self.stop_reply(&reason);
+                self.reply(&stop)
+            }
+            b'Z' | b'z' => {
+                let reply = match self.set_breakpoint(kind == b'Z', body) {
+                    Some(true) => "OK",
+                    Some(false) => "E01",
+                    None => "",
+                };
+                self.reply(reply)
+            }
+            b'H' => self.reply("OK"),
+            b'D' => {
+                self.reply("OK")?;
+                Ok(Session::Detach)
+            }
+            b'k' => Ok(Session::Detach),
+            b'q' | b'Q' => self.query(packet),
+            _ => self.reply(""),
+        }
+    }
+
+    fn query(&mut self, packet: &[u8]) -> io::Result<Session> {
+        let text = String::from_utf8_lossy(packet);
+
+        if text.starts_with("qSupported") {
+            return self.reply("PacketSize=4000;qXfer:features:read+;swbreak+;QStartNoAckMode+");
+        }
+
+        if text == "QStartNoAckMode" {
+            self.reply("OK")?;
+            self.acknowledge = false;
+            return Ok(Session::Continue);
+        }
+
+        if text == "qAttached" {
+            return self.reply("1");
+        }
+
+        let Some(range) = text.strip_prefix("qXfer:features:read:target.xml:") else {
+            return self.reply("");
+        };
+
+        let Some((offset, length)) = address_and_length(range.as_bytes()) else {
+            return self.reply("E01");
+        };
+
+        // the description is short enough for offsets to fit an address
+        let xml = target_xml::<W>().into_bytes();
+        let start = (offset as usize).min(xml.len());
+        let end = (start + length).min(xml.len());
+
+        let mut reply = vec![if end < xml.len() { b'm' } else { b'l' }];
+        reply.extend(escape(&xml[start..end]));
+        self.send(&reply)?;
+        Ok(Session::Continue)
+    }
+
+    fn register(&self, index: usize) -> Vec<u8> {
+        match index {
+            REGISTER_SP => self.system.get_sp().to_be_bytes().to_vec(),
+            REGISTER_IP => self.system.get_ip().to_be_bytes().to_vec(),
+            REGISTER_FLAGS => vec![self.system.get_flags_register().bits()],
+            index => self.system.get_regs()[index].to_be_bytes(),
+        }
+    }
+
+    fn register_size(index: usize) -> usize {
+        match index {
+            REGISTER_SP | REGISTER_IP => 2,
+            REGISTER_FLAGS => 1,
+            _ => W::BYTES,
+        }
+    }
+
+    fn write_register(&mut self, index: usize, bytes: &[u8]) -> bool {
+        if index >= REGISTER_COUNT || bytes.len() != Self::register_size(index) {
+            return false;
+        }
+
+        let address = || u16::from_be_bytes([bytes[0], bytes[1]]);
+        match index {
+            REGISTER_SP => self.system.set_sp(address()),
+            REGISTER_IP => self.system.set_ip(address()),
+            REGISTER_FLAGS => self
+                .system
+                .set_flags_register(FlagsRegister::from_bits(bytes[0])),
+            index => self.system.set_reg(index, W::from_be_bytes(bytes)),
+        }
+
+        true
+    }
+
+    fn write_registers(&mut self, bytes: &[u8]) -> bool {
+        let total = (0..REGISTER_COUNT).map(Self::register_size).sum::<usize>();
+        if bytes.len() != total {
+            return false;
+        }
+
+        let mut offset = 0;
+        for index in 0..REGISTER_COUNT {
+            let size = Self::register_size(index);
+            self.write_register(index, &bytes[offset..offset + size]);
+            offset += size;
+        }
+
+        true
+    }
+
+    // Hex of the bytes up to the first address that is neither ram nor a device
+    fn read_memory(&self, address: u16, length: usize) -> String {
+        let bytes = (0..length)
+            .map_while(|offset| {
+                let address = address.checked_add(u16::try_from(offset).ok()?)?;
+                self.system.get_mem(address).ok()
+            })
+            .collect::<Vec<_>>();
+
+        match bytes.is_empty() && length > 0 {
+            true => "E14".to_string(),
+            false => hex(&bytes),
+        }
+    }
+
+    fn write_memory(&mut self, address: u16, bytes: &[u8]) -> bool {
+        bytes.iter().enumerate().all(|(offset, &byte)| {
+            address
+                .checked_add(offset as u16)
+                .is_some_and(|address| self.system.set_mem(address, byte).is_ok())
+        })
+    }
+
+    // Some(added or removed) for `Z`/`z` packets, None for unsupported breakpoint types
+    fn set_breakpoint(&mut self, insert: bool, body: &[u8]) -> Option<bool> {
+        let (&kind, rest) = body.split_first()?;
+        let kind = kind.checked_sub(b'0')?;
+
+        let breakpoint = match kind {
+            0 | 1 => |address| BreakpointKind::Address(address),
+            2 => |address| BreakpointKind::Watch(address, WatchMode::Write),
+            3 => |address| BreakpointKind::Watch(address, WatchMode::Read),
+            4 => |address| BreakpointKind::Watch(address, WatchMode::ReadWrite),
+            _ => return None,
+        };
+
+        // `,address,kind`, the kind is the length of the breakpoint
+        let Some((address, _)) = rest.strip_prefix(b",").and_then(address_and_length) else {
+            return Some(false);
+        };
+
+        let breakpoints = self.system.breakpoints_mut();
+        match (insert, self.breakpoints.get(&(kind, address))) {
+            (true, Some(_)) => {}
+            (true, None) => {
+                let id = breakpoints.add(breakpoint(address), None);
+                self.breakpoints.insert((kind, address), id);
+            }
+            (false, Some(&id)) => {
+                _ = breakpoints.delete(id);
+                self.breakpoints.remove(&(kind, address));
+            }
+            (false, None) => return Some(false),
+        }
+
+        Some(true)
+    }
+
+    // Runs until the program stops or the client or the interrupt flag interrupts it
+    fn resume(&mut self) -> StopReason {
+        let interrupt = self.interrupt.clone();
+        let interrupted = || {
+            interrupt
+                .as_ref()
+                .is_some_and(|i| i.load(Ordering::Acquire))
+        };
+
+        loop {
+            match self.system.run(Some(POLL_INTERVAL), interrupted) {
+                StopReason::StepLimit if !self.connection.interrupt_requested() => {}
+                StopReason::StepLimit | StopReason::Interrupted => {
+                    if let Some(interrupt) = &self.interrupt {
+                        interrupt.store(false, Ordering::Release);
+                    }
+
+                    return StopReason::Interrupted;
+                }
+                reason => return reason,
+            }
+        }
+    }
+
+    fn stop_reply(&self, reason: &StopReason) -> String {
+        match reason {
+            StopReason::Halted => "W00".to_string(),
+            StopReason::Breakpoint(_) => format!("T{:02x}swbreak:;", SIGTRAP),
+            StopReason::Watchpoint(id, access) => {
+                let watch = match self.system.breakpoints().get(*id).map(|b| &b.kind) {
+                    Some(BreakpointKind::Watch(_, WatchMode::Read)) => "rwatch",
+                    Some(BreakpointKind::Watch(_, WatchMode::ReadWrite)) => "awatch",
+                    _ => "watch",
+                };
+                format!("T{:02x}{}:{:x};", SIGTRAP, watch, access.address)
+            }
+            StopReason::Interrupted => format!("S{:02x}", SIGINT),
+            StopReason::Fault(fault) => {
+                let signal = match fault {
+                    ExecutionFault::IllegalOpcode | ExecutionFault::InvalidRegister(_) => SIGILL,
+                    ExecutionFault::DivideByZero => SIGFPE,
+                    _ => SIGSEGV,
+                };
+                format!("S{:02x}", signal)
+            }
+            StopReason::StepLimit | StopReason::StartOfHistory => format!("S{:02x}", SIGTRAP),
+        }
+    }
+}
+
+// Waits for a client on the local port and serves it until it detaches
+pub fn listen<W: Word>(
+    system: &mut System<W>,
+    port: u16,
+    interrupt: Arc<AtomicBool>,
+) -> io::Result<()> {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))?;
+    let (stream, _) = listener.accept()?;
+    stream.set_nodelay(true)?;
+
+    let mut stub = GdbStub::new(system, stream);
+    stub.set_interrupt(interrupt);
+    stub.serve()
+}
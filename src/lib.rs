@@ -2,6 +2,10 @@ pub mod types;
 
 pub mod machine;
 
+pub mod batch;
 pub mod cli;
 pub mod compiler;
+pub mod gdb;
 pub mod new_compiler;
+pub mod repl;
+pub mod symbols;
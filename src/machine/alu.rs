@@ -1,17 +1,21 @@
-use crate::machine::flags::{Flags, FlagsRegister};
+use crate::machine::{
+    flags::{Flags, FlagsRegister},
+    word::Word,
+};
 
-pub struct Result {
-    pub value: u8,
+// Operations work on the full word, flags are set according to its most significant bit
+pub struct Result<W: Word = u8> {
+    pub value: W,
     pub flags: FlagsRegister,
 }
 
-pub fn is_signed(byte: u8) -> bool {
-    return (byte & 0b1000_0000) > 0;
+pub fn is_signed<W: Word>(word: W) -> bool {
+    return (word >> (W::SIZE.bits() as usize - 1)) != W::zero();
 }
 
-fn flags_for_operation(a: u8, b: u8, result: (u8, bool)) -> FlagsRegister {
+fn flags_for_operation<W: Word>(a: W, b: W, result: (W, bool)) -> FlagsRegister {
     let mut flags = FlagsRegister::new();
-    if result.0 == 0 {
+    if result.0 == W::zero() {
         flags.set(Flags::Zero);
     }
 
@@ -31,7 +35,14 @@ fn flags_for_operation(a: u8, b: u8, result: (u8, bool)) -> FlagsRegister {
     return flags;
 }
 
-pub fn add(a: u8, b: u8) -> Result {
+// A subtraction overflows like the addition of the complement of `b`: if a and b differ in sign
+// and the result differs from a. Before the signed branches, SUB set overflow by the rule of
+// additions, so e.g. `0x80 - 1` did not overflow.
+fn flags_for_subtraction<W: Word>(a: W, b: W, result: (W, bool)) -> FlagsRegister {
+    flags_for_operation(a, !b, result)
+}
+
+pub fn add<W: Word>(a: W, b: W) -> Result<W> {
     let result = a.overflowing_add(b);
 
     return Result {
@@ -40,16 +51,39 @@ pub fn add(a: u8, b: u8) -> Result {
     };
 }
 
-pub fn sub(a: u8, b: u8) -> Result {
+pub fn sub<W: Word>(a: W, b: W) -> Result<W> {
     let result = a.overflowing_sub(b);
 
     return Result {
         value: result.0,
-        flags: flags_for_operation(a, b, result),
+        flags: flags_for_subtraction(a, b, result),
     };
 }
 
-pub fn and(a: u8, b: u8) -> Result {
+// Adds the carry of a previous addition, the carry is set if either addition overflows
+pub fn adc<W: Word>(a: W, b: W, carry: bool) -> Result<W> {
+    let (sum, first_carry) = a.overflowing_add(b);
+    let (result, second_carry) = sum.overflowing_add(if carry { W::one() } else { W::zero() });
+
+    Result {
+        value: result,
+        flags: flags_for_operation(a, b, (result, first_carry || second_carry)),
+    }
+}
+
+// Subtracts the borrow (carry) of a previous subtraction
+pub fn sbc<W: Word>(a: W, b: W, borrow: bool) -> Result<W> {
+    let (difference, first_borrow) = a.overflowing_sub(b);
+    let (result, second_borrow) =
+        difference.overflowing_sub(if borrow { W::one() } else { W::zero() });
+
+    Result {
+        value: result,
+        flags: flags_for_subtraction(a, b, (result, first_borrow || second_borrow)),
+    }
+}
+
+pub fn and<W: Word>(a: W, b: W) -> Result<W> {
     let result = a & b;
 
     return Result {
@@ -58,7 +92,7 @@ pub fn and(a: u8, b: u8) -> Result {
     };
 }
 
-pub fn or(a: u8, b: u8) -> Result {
+pub fn or<W: Word>(a: W, b: W) -> Result<W> {
     let result = a | b;
 
     return Result {
@@ -67,7 +101,7 @@ pub fn or(a: u8, b: u8) -> Result {
     };
 }
 
-pub fn xor(a: u8, b: u8) -> Result {
+pub fn xor<W: Word>(a: W, b: W) -> Result<W> {
     let result = a ^ b;
 
     return Result {
@@ -76,20 +110,237 @@ pub fn xor(a: u8, b: u8) -> Result {
     };
 }
 
-pub fn shl(a: u8, b: u8) -> Result {
-    let result = a.overflowing_shl(b as u32);
+fn bit<W: Word>(word: W, index: u32) -> bool {
+    (word >> index as usize) & W::one() != W::zero()
+}
 
-    return Result {
-        value: result.0,
-        flags: flags_for_operation(a, b, result),
+// Shifts and rotates set carry to the last bit shifted out, it is cleared by an amount of 0
+pub fn shl<W: Word>(a: W, b: W) -> Result<W> {
+    let (amount, bits) = (b.as_u16() as u32, W::SIZE.bits());
+    let value = if amount < bits {
+        a << amount as usize
+    } else {
+        W::zero()
     };
+
+    let carry = amount != 0 && amount <= bits && bit(a, bits - amount);
+    Result {
+        value,
+        flags: flags_for_value(value, carry, false),
+    }
 }
 
-pub fn shr(a: u8, b: u8) -> Result {
-    let result = a.overflowing_shr(b as u32);
+pub fn shr<W: Word>(a: W, b: W) -> Result<W> {
+    let (amount, bits) = (b.as_u16() as u32, W::SIZE.bits());
+    let value = if amount < bits {
+        a >> amount as usize
+    } else {
+        W::zero()
+    };
 
-    return Result {
-        value: result.0,
-        flags: flags_for_operation(a, b, result),
+    let carry = amount != 0 && amount <= bits && bit(a, amount - 1);
+    Result {
+        value,
+        flags: flags_for_value(value, carry, false),
+    }
+}
+
+// Arithmetic shift right, copies the sign bit into the vacated bits
+pub fn asr<W: Word>(a: W, b: W) -> Result<W> {
+    let (amount, bits) = (b.as_u16() as u32, W::SIZE.bits());
+    let value = if amount < bits {
+        a.signed_shr(amount)
+    } else if is_signed(a) {
+        !W::zero()
+    } else {
+        W::zero()
+    };
+
+    let carry = amount != 0 && bit(a, amount.min(bits) - 1);
+    Result {
+        value,
+        flags: flags_for_value(value, carry, false),
+    }
+}
+
+pub fn rol<W: Word>(a: W, b: W) -> Result<W> {
+    let value = a.rotate_left(b.as_u16() as u32 % W::SIZE.bits());
+    let carry = b != W::zero() && bit(value, 0);
+
+    Result {
+        value,
+        flags: flags_for_value(value, carry, false),
+    }
+}
+
+pub fn ror<W: Word>(a: W, b: W) -> Result<W> {
+    let value = a.rotate_right(b.as_u16() as u32 % W::SIZE.bits());
+    let carry = b != W::zero() && bit(value, W::SIZE.bits() - 1);
+
+    Result {
+        value,
+        flags: flags_for_value(value, carry, false),
+    }
+}
+
+// Rotates through carry, the word and the carry form a value of one bit more than a word
+pub fn rcl<W: Word>(a: W, b: W, carry: bool) -> Result<W> {
+    let bits = W::SIZE.bits();
+    let (mut value, mut carry) = (a, carry);
+    for _ in 0..b.as_u16() as u32 % (bits + 1) {
+        let out = bit(value, bits - 1);
+        value = value << 1 | if carry { W::one() } else { W::zero() };
+        carry = out;
+    }
+
+    Result {
+        value,
+        flags: flags_for_value(value, carry, false),
+    }
+}
+
+pub fn rcr<W: Word>(a: W, b: W, carry: bool) -> Result<W> {
+    let bits = W::SIZE.bits();
+    let (mut value, mut carry) = (a, carry);
+    for _ in 0..b.as_u16() as u32 % (bits + 1) {
+        let out = bit(value, 0);
+        value = value >> 1
+            | if carry {
+                W::one() << (bits as usize - 1)
+            } else {
+                W::zero()
+            };
+        carry = out;
+    }
+
+    Result {
+        value,
+        flags: flags_for_value(value, carry, false),
+    }
+}
+
+// Zero and sign of `value`, carry and overflow as given
+fn flags_for_value<W: Word>(value: W, carry: bool, overflow: bool) -> FlagsRegister {
+    let mut flags = FlagsRegister::new();
+    if value == W::zero() {
+        flags.set(Flags::Zero);
+    }
+
+    if is_signed(value) {
+        flags.set(Flags::Sign);
+    }
+
+    if carry {
+        flags.set(Flags::Carry);
+    }
+
+    if overflow {
+        flags.set(Flags::Overflow);
+    }
+
+    flags
+}
+
+// Whether the high word of a signed product is more than the sign extension of the low word
+fn signed_product_overflows<W: Word>(low: W, high: W) -> bool {
+    let extension = if is_signed(low) {
+        !W::zero()
+    } else {
+        W::zero()
     };
+    high != extension
+}
+
+// Low word of the product, carry and overflow are set if the product does not fit in a word
+pub fn mul<W: Word>(a: W, b: W) -> Result<W> {
+    let (low, high) = a.widening_mul(b);
+    let overflow = high != W::zero();
+
+    Result {
+        value: low,
+        flags: flags_for_value(low, overflow, overflow),
+    }
+}
+
+// High word of the unsigned product, carry and overflow as for `mul`
+pub fn mulh<W: Word>(a: W, b: W) -> Result<W> {
+    let (_, high) = a.widening_mul(b);
+    let overflow = high != W::zero();
+
+    Result {
+        value: high,
+        flags: flags_for_value(high, overflow, overflow),
+    }
+}
+
+// Low word of the signed product, carry and overflow are set if it does not fit in a word
+pub fn imul<W: Word>(a: W, b: W) -> Result<W> {
+    let (low, high) = a.widening_mul_signed(b);
+    let overflow = signed_product_overflows(low, high);
+
+    Result {
+        value: low,
+        flags: flags_for_value(low, overflow, overflow),
+    }
+}
+
+pub fn imulh<W: Word>(a: W, b: W) -> Result<W> {
+    let (low, high) = a.widening_mul_signed(b);
+    let overflow = signed_product_overflows(low, high);
+
+    Result {
+        value: high,
+        flags: flags_for_value(high, overflow, overflow),
+    }
+}
+
+// Division operations return `None` when dividing by zero, carry is always cleared
+pub fn div<W: Word>(a: W, b: W) -> Option<Result<W>> {
+    if b == W::zero() {
+        return None;
+    }
+
+    let value = a / b;
+    Some(Result {
+        value,
+        flags: flags_for_value(value, false, false),
+    })
+}
+
+pub fn rem<W: Word>(a: W, b: W) -> Option<Result<W>> {
+    if b == W::zero() {
+        return None;
+    }
+
+    let value = a % b;
+    Some(Result {
+        value,
+        flags: flags_for_value(value, false, false),
+    })
+}
+
+// Rounds towards zero, the most negative value divided by -1 wraps and sets overflow
+pub fn idiv<W: Word>(a: W, b: W) -> Option<Result<W>> {
+    if b == W::zero() {
+        return None;
+    }
+
+    let (value, overflow) = a.overflowing_div_signed(b);
+    Some(Result {
+        value,
+        flags: flags_for_value(value, false, overflow),
+    })
+}
+
+// The remainder takes the sign of the dividend
+pub fn irem<W: Word>(a: W, b: W) -> Option<Result<W>> {
+    if b == W::zero() {
+        return None;
+    }
+
+    let value = a.wrapping_rem_signed(b);
+    Some(Result {
+        value,
+        flags: flags_for_value(value, false, false),
+    })
 }
@@ -0,0 +1,211 @@
+use std::{any::Any, fmt::Display};
+
+use crate::machine::storage::{FiniteStorage, ReadableStorage, StorageError, WritableStorage, RAM};
+
+// Peripheral that claims `size` consecutive addresses of the data bus. Offsets passed to
+// `read` and `write` are relative to the start of the claimed range.
+pub trait Device: Any {
+    fn name(&self) -> &'static str;
+    fn size(&self) -> u16;
+    fn read(&mut self, offset: u16) -> u8;
+    fn write(&mut self, offset: u16, value: u8);
+
+    // Level of the device's interrupt request line
+    fn interrupt_requested(&self) -> bool {
+        false
+    }
+
+    // Advances the device by the cpu cycles taken by the last instruction
+    fn tick(&mut self, _cycles: u64) {}
+
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+struct Mapping {
+    start: u16,
+    device: Box<dyn Device>,
+}
+
+impl Mapping {
+    fn end(&self) -> u16 {
+        self.start + (self.device.size() - 1)
+    }
+
+    fn contains(&self, address: u16) -> bool {
+        (self.start..=self.end()).contains(&address)
+    }
+}
+
+#[derive(Debug)]
+pub enum BusError {
+    EmptyDevice,
+    OutOfRange,
+    Overlap(&'static str), // name of the device already mapped there
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MemoryRegion {
+    pub start: u16,
+    pub end: u16, // inclusive
+    pub name: &'static str,
+}
+
+// All regions of the data bus, devices take precedence over the ram they overlap
+#[derive(Debug, Clone)]
+pub struct MemoryMap {
+    pub regions: Vec<MemoryRegion>,
+}
+
+impl Display for MemoryMap {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for region in &self.regions {
+            writeln!(
+                f,
+                "{:#06x}-{:#06x} {}",
+                region.start, region.end, region.name
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+// Routes data accesses of instructions to the device claiming the address or to ram otherwise
+pub struct Bus {
+    ram: RAM<u8>,
+    mappings: Vec<Mapping>,
+}
+
+impl Bus {
+    pub fn new(ram: RAM<u8>) -> Self {
+        Self {
+            ram,
+            mappings: vec![],
+        }
+    }
+
+    pub fn ram(&self) -> &RAM<u8> {
+        &self.ram
+    }
+
+    pub fn ram_mut(&mut self) -> &mut RAM<u8> {
+        &mut self.ram
+    }
+
+    pub fn set_ram(&mut self, ram: RAM<u8>) {
+        self.ram = ram;
+    }
+
+    pub fn attach(&mut self, start: u16, device: Box<dyn Device>) -> Result<(), BusError> {
+        if device.size() == 0 {
+            return Err(BusError::EmptyDevice);
+        }
+
+        if start.checked_add(device.size() - 1).is_none() {
+            return Err(BusError::OutOfRange);
+        }
+
+        let mapping = Mapping { start, device };
+        if let Some(other) = self
+            .mappings
+            .iter()
+            .find(|other| other.contains(mapping.start) || mapping.contains(other.start))
+        {
+            return Err(BusError::Overlap(other.device.name()));
+        }
+
+        self.mappings.push(mapping);
+        self.mappings.sort_by_key(|mapping| mapping.start);
+        Ok(())
+    }
+
+    pub fn detach(&mut self, name: &str) -> Option<Box<dyn Device>> {
+        let index = self
+            .mappings
+            .iter()
+            .position(|mapping| mapping.device.name() == name)?;
+
+        Some(self.mappings.remove(index).device)
+    }
+
+    // First attached device of type `T`
+    pub fn device<T: Device>(&self) -> Option<&T> {
+        self.mappings
+            .iter()
+            .find_map(|mapping| mapping.device.as_any().downcast_ref::<T>())
+    }
+
+    pub fn device_mut<T: Device>(&mut self) -> Option<&mut T> {
+        self.mappings
+            .iter_mut()
+            .find_map(|mapping| mapping.device.as_any_mut().downcast_mut::<T>())
+    }
+
+    // Whether a device claims the address
+    pub fn is_mapped(&self, address: u16) -> bool {
+        self.mappings
+            .iter()
+            .any(|mapping| mapping.contains(address))
+    }
+
+    // Whether any device requests an interrupt
+    pub fn interrupt_requested(&self) -> bool {
+        self.mappings
+            .iter()
+            .any(|mapping| mapping.device.interrupt_requested())
+    }
+
+    pub fn tick(&mut self, cycles: u64) {
+        for mapping in &mut self.mappings {
+            mapping.device.tick(cycles);
+        }
+    }
+
+    pub fn read(&mut self, address: u16) -> Result<u8, StorageError> {
+        match self
+            .mappings
+            .iter_mut()
+            .find(|mapping| mapping.contains(address))
+        {
+            Some(mapping) => Ok(mapping.device.read(address - mapping.start)),
+            None => self.ram.get(address as usize),
+        }
+    }
+
+    pub fn write(&mut self, address: u16, value: u8) -> Result<(), StorageError> {
+        match self
+            .mappings
+            .iter_mut()
+            .find(|mapping| mapping.contains(address))
+        {
+            Some(mapping) => {
+                mapping.device.write(address - mapping.start, value);
+                Ok(())
+            }
+            None => self.ram.set(address as usize, value),
+        }
+    }
+
+    pub fn memory_map(&self) -> MemoryMap {
+        let mut regions = self
+            .mappings
+            .iter()
+            .map(|mapping| MemoryRegion {
+                start: mapping.start,
+                end: mapping.end(),
+                name: mapping.device.name(),
+            })
+            .collect::<Vec<_>>();
+
+        if self.ram.size() > 0 {
+            regions.push(MemoryRegion {
+                start: 0,
+                end: (self.ram.size() - 1).min(u16::MAX as usize) as u16,
+                name: "ram",
+            });
+        }
+
+        MemoryMap { regions }
+    }
+}
@@ -2,20 +2,53 @@ use crate::{
     compiler::instruction::Instruction,
     machine::{
         alu as ALU,
+        bus::Bus,
+        debugger::{AccessKind, Breakpoints, MemoryAccess, StopReason},
+        devices::{interrupt::InterruptController, serial::Serial, timer::Timer},
+        fault::{ExecutionFault, FaultPolicy, Step},
         flags::{Flags, FlagsRegister},
+        interrupt::InterruptState,
+        journal::{Journal, JournalEntry},
+        snapshot::Snapshot,
         storage::{ReadableStorage, WritableStorage, RAM},
+        word::Word,
     },
 };
 
-use crate::types::Opcode;
+use crate::types::{Opcode, OpcodeConversionError};
 
 use super::storage::FiniteStorage;
 
-pub struct System {
-    ram: RAM<u8>,
-    regs: [u8; 16],
+// Cycles taken to save the ip and flags and to load the interrupt vector
+pub const INTERRUPT_CYCLES: u64 = 3;
+
+// Bytes addressable with 16-bit addresses
+const ADDRESS_SPACE_SIZE: usize = 0x10000;
+
+// Ram added behind a rom that does not fit, so the stack has room to grow
+pub const STACK_RESERVE: usize = 0x100;
+
+// Machine with registers, alu and data accesses of word type `W`
+pub struct System<W: Word = u8> {
+    bus: Bus,
+    regs: [W; 16],
     ip: u16,
+    sp: u16, // last pushed byte, the stack grows down from the end of ram
     flags: FlagsRegister,
+    breakpoints: Breakpoints,
+    accesses: Vec<MemoryAccess>, // memory accessed by the last executed instruction
+    journal: Journal<W>,
+    interrupts: InterruptState,
+    interrupt_raised: bool, // interrupt requested by software, cleared once it is taken
+    cycles: u64,            // cycles taken by the executed instructions
+    fault_policy: FaultPolicy,
+    rom_size: usize, // the stack must not grow into the loaded rom
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StackError {
+    Overflow,  // push below address 0, into a device or into the loaded rom
+    Underflow, // pop beyond the end of ram
 }
 
 #[derive(Debug)]
@@ -30,43 +63,273 @@ pub enum LoadRamError {
 
 impl System {
     pub fn new(ram_size: usize) -> Self {
+        Self::with_ram_size(ram_size)
+    }
+}
+
+impl<W: Word> System<W> {
+    pub fn with_ram_size(ram_size: usize) -> Self {
+        let mut bus = Bus::new(RAM::new(ram_size));
+        bus.attach(0x0000, Box::new(Serial::new())).unwrap();
+        bus.attach(0x0002, Box::new(InterruptController::new()))
+            .unwrap();
+        bus.attach(0x0004, Box::new(Timer::new())).unwrap();
+
+        let sp = stack_base(&bus) as u16;
         Self {
-            ram: RAM::new(ram_size),
-            regs: [0; 16],
+            bus,
+            regs: [W::zero(); 16],
             ip: 0,
+            sp,
             flags: FlagsRegister::new(),
+            breakpoints: Breakpoints::new(),
+            accesses: vec![],
+            journal: Journal::default(),
+            interrupts: InterruptState::default(),
+            interrupt_raised: false,
+            cycles: 0,
+            fault_policy: FaultPolicy::default(),
+            rom_size: 0,
+        }
+    }
+
+    pub fn cycles(&self) -> u64 {
+        self.cycles
+    }
+
+    // Counts the cycles of an executed instruction, devices run for the same amount
+    fn advance(&mut self, cycles: u64) {
+        self.cycles += cycles;
+        self.bus.tick(cycles);
+    }
+
+    // Direct ram access, not seen by devices, watchpoints or the undo journal
+    pub fn get_mem(&self, address: u16) -> Result<u8, ExecutionFault> {
+        self.bus
+            .ram()
+            .get(address as usize)
+            .map_err(|_| ExecutionFault::OutOfBoundsRead(address))
+    }
+
+    pub fn set_mem(&mut self, address: u16, value: u8) -> Result<(), ExecutionFault> {
+        self.bus
+            .ram_mut()
+            .set(address as usize, value)
+            .map_err(|_| ExecutionFault::OutOfBoundsWrite(address))
+    }
+
+    pub fn fault_policy(&self) -> FaultPolicy {
+        self.fault_policy
+    }
+
+    pub fn set_fault_policy(&mut self, policy: FaultPolicy) {
+        self.fault_policy = policy;
+    }
+
+    pub fn bus(&self) -> &Bus {
+        &self.bus
+    }
+
+    pub fn bus_mut(&mut self) -> &mut Bus {
+        &mut self.bus
+    }
+
+    // Memory access by an instruction through the bus, recorded for watchpoints. Instructions
+    // check the addresses beforehand, see `inaccessible`.
+    fn load(&mut self, address: u16) -> u8 {
+        let value = self.bus.read(address).unwrap_or(0);
+
+        self.accesses.push(MemoryAccess {
+            address,
+            kind: AccessKind::Read,
+            value,
+        });
+
+        value
+    }
+
+    fn store(&mut self, address: u16, value: u8) {
+        // device state can not be undone except for the timer, only ram writes are journaled
+        if !self.bus.is_mapped(address) {
+            if let Ok(old_value) = self.bus.ram().get(address as usize) {
+                self.journal.record_write(address, old_value);
+            }
         }
+
+        self.accesses.push(MemoryAccess {
+            address,
+            kind: AccessKind::Write,
+            value,
+        });
+
+        _ = self.bus.write(address, value);
+    }
+
+    // First address of a word access at `address` that is neither ram nor a device
+    fn inaccessible(&self, address: u16) -> Option<u16> {
+        let count = if self.bus.is_mapped(address) {
+            1
+        } else {
+            W::BYTES as u16
+        };
+        (0..count)
+            .map(|offset| address.wrapping_add(offset))
+            .find(|address| {
+                !self.bus.is_mapped(*address) && *address as usize >= self.bus.ram().size()
+            })
     }
 
-    pub fn get_mem(&self, address: u16) -> u8 {
-        if let Ok(value) = self.ram.get(address as usize) {
-            return value;
+    // Word sized access, devices only take part in byte sized accesses: they see the low byte
+    // of stores and loads are zero extended
+    fn load_word(&mut self, address: u16) -> W {
+        if W::BYTES == 1 || self.bus.is_mapped(address) {
+            return W::from_u8(self.load(address));
         }
 
-        println!(
-            "Error: out of bounds memory store operation [{:#06x}] ip={}",
-            address, self.ip
-        );
+        let bytes = (0..W::BYTES as u16)
+            .map(|offset| self.load(address.wrapping_add(offset)))
+            .collect::<Vec<_>>();
 
-        return 0;
+        W::from_be_bytes(&bytes)
     }
 
-    pub fn set_mem(&mut self, address: u16, value: u8) {
-        if address == 0 {
-            // intercept [0] as serial out
-            print!("{}", value as char);
+    fn store_word(&mut self, address: u16, value: W) {
+        if W::BYTES == 1 || self.bus.is_mapped(address) {
+            self.store(address, value.low_byte());
             return;
         }
 
-        if self.ram.set(address as usize, value).is_err() {
-            println!(
-                "Error: out of bounds memory load operation [{:#06x}] ip={}",
-                address, self.ip
-            );
+        for (offset, byte) in value.to_be_bytes().into_iter().enumerate() {
+            self.store(address.wrapping_add(offset as u16), byte);
         }
     }
 
-    pub fn get_regs(&self) -> [u8; 16] {
+    pub fn last_accesses(&self) -> &[MemoryAccess] {
+        &self.accesses
+    }
+
+    pub fn breakpoints(&self) -> &Breakpoints {
+        &self.breakpoints
+    }
+
+    pub fn breakpoints_mut(&mut self) -> &mut Breakpoints {
+        &mut self.breakpoints
+    }
+
+    // Executes instructions until the system halts, a breakpoint or watchpoint triggers,
+    // `max_steps` instructions were executed or `interrupted` returns true. The instruction at
+    // the current ip is always executed, even if a breakpoint is set on it.
+    pub fn run<F>(&mut self, max_steps: Option<usize>, interrupted: F) -> StopReason
+    where
+        F: Fn() -> bool,
+    {
+        let mut steps = 0;
+
+        loop {
+            if max_steps.is_some_and(|max_steps| steps >= max_steps) {
+                return StopReason::StepLimit;
+            }
+
+            if interrupted() {
+                return StopReason::Interrupted;
+            }
+
+            match self.step() {
+                Ok(Step::Halted) => return StopReason::Halted,
+                Err(fault) => return StopReason::Fault(fault),
+                Ok(_) => {}
+            }
+
+            steps += 1;
+
+            if !self.breakpoints.is_empty() {
+                let mut breakpoints = std::mem::take(&mut self.breakpoints);
+                let reason = breakpoints.check(self, &self.accesses);
+                self.breakpoints = breakpoints;
+
+                if let Some(reason) = reason {
+                    return reason;
+                }
+            }
+        }
+    }
+
+    pub fn journal(&self) -> &Journal<W> {
+        &self.journal
+    }
+
+    pub fn journal_mut(&mut self) -> &mut Journal<W> {
+        &mut self.journal
+    }
+
+    // Undoes the last executed instruction, returns the memory writes that were reverted
+    pub fn step_back(&mut self) -> Option<Vec<MemoryAccess>> {
+        let entry = self.journal.pop()?;
+
+        let mut reverted = vec![];
+        for (address, old_value) in entry.writes.into_iter().rev() {
+            // journaled writes went to ram
+            reverted.push(MemoryAccess {
+                address,
+                kind: AccessKind::Write,
+                value: self.get_mem(address).unwrap_or(0),
+            });
+
+            _ = self.set_mem(address, old_value);
+        }
+
+        self.regs = entry.regs;
+        self.ip = entry.ip;
+        self.sp = entry.sp;
+        self.flags = entry.flags;
+        self.interrupts = entry.interrupts;
+        self.cycles = entry.cycles;
+        if let (Some(timer), Some(device)) = (entry.timer, self.bus.device_mut::<Timer>()) {
+            *device = timer;
+        }
+
+        self.accesses.clear();
+
+        Some(reverted)
+    }
+
+    // Counterpart of `run` that undoes instructions until the start of the recorded history or
+    // until the ip reaches a breakpoint, a watchpoint triggers on an undone write or `max_steps`
+    // instructions were undone.
+    pub fn run_back<F>(&mut self, max_steps: Option<usize>, interrupted: F) -> StopReason
+    where
+        F: Fn() -> bool,
+    {
+        let mut steps = 0;
+
+        loop {
+            if max_steps.is_some_and(|max_steps| steps >= max_steps) {
+                return StopReason::StepLimit;
+            }
+
+            if interrupted() {
+                return StopReason::Interrupted;
+            }
+
+            let Some(reverted) = self.step_back() else {
+                return StopReason::StartOfHistory;
+            };
+
+            steps += 1;
+
+            if !self.breakpoints.is_empty() {
+                let mut breakpoints = std::mem::take(&mut self.breakpoints);
+                let reason = breakpoints.check(self, &reverted);
+                self.breakpoints = breakpoints;
+
+                if let Some(reason) = reason {
+                    return reason;
+                }
+            }
+        }
+    }
+
+    pub fn get_regs(&self) -> [W; 16] {
         self.regs
     }
 
@@ -74,6 +337,81 @@ impl System {
         self.ip
     }
 
+    pub fn get_sp(&self) -> u16 {
+        self.sp
+    }
+
+    // Writes of debuggers, they are not recorded in the journal
+    pub fn set_reg(&mut self, index: usize, value: W) {
+        self.regs[index] = value;
+    }
+
+    pub fn set_ip(&mut self, ip: u16) {
+        self.ip = ip;
+    }
+
+    pub fn set_sp(&mut self, sp: u16) {
+        self.sp = sp;
+    }
+
+    pub fn set_flags_register(&mut self, flags: FlagsRegister) {
+        self.flags = flags;
+    }
+
+    // Address just past the stack, the end of ram
+    pub fn stack_base(&self) -> usize {
+        stack_base(&self.bus)
+    }
+
+    // Address of the last pushed byte. With ram filling the address space the stack starts at
+    // 0x10000, which sp holds as 0, so the stack never takes address 0 there.
+    fn stack_top(&self) -> usize {
+        match self.sp {
+            0 if self.stack_base() == ADDRESS_SPACE_SIZE => ADDRESS_SPACE_SIZE,
+            sp => sp as usize,
+        }
+    }
+
+    // Stack contents from the last pushed byte up to the end of ram
+    pub fn stack(&self) -> &[u8] {
+        let ram = self.bus.ram().as_slice();
+        ram.get(self.stack_top()..self.stack_base()).unwrap_or(&[])
+    }
+
+    fn push(&mut self, bytes: &[u8]) -> Result<(), StackError> {
+        let top = self.stack_top();
+        let sp = match top.checked_sub(bytes.len()) {
+            Some(sp) if sp > 0 || self.stack_base() < ADDRESS_SPACE_SIZE => sp,
+            _ => return Err(StackError::Overflow),
+        };
+
+        if sp < self.rom_size || (sp..top).any(|address| self.bus.is_mapped(address as u16)) {
+            return Err(StackError::Overflow);
+        }
+
+        for (offset, byte) in bytes.iter().enumerate() {
+            self.store((sp + offset) as u16, *byte);
+        }
+
+        self.sp = sp as u16;
+        Ok(())
+    }
+
+    fn pop(&mut self, count: usize) -> Result<Vec<u8>, StackError> {
+        let top = self.stack_top();
+        if top + count > self.stack_base() {
+            return Err(StackError::Underflow);
+        }
+
+        let bytes = (top..top + count)
+            .map(|address| self.load(address as u16))
+            .collect();
+
+        // the end of the address space wraps to 0
+        self.sp = (top + count) as u16;
+        Ok(bytes)
+    }
+
     pub fn get_flags_register(&self) -> &FlagsRegister {
         &self.flags
     }
@@ -82,18 +420,108 @@ impl System {
         self.ip = address as u16;
     }
 
+    pub fn interrupts(&self) -> &InterruptState {
+        &self.interrupts
+    }
+
+    // Requests an interrupt that is taken once interrupts are enabled
+    pub fn raise_interrupt(&mut self) {
+        self.interrupt_raised = true;
+    }
+
+    fn interrupt_vector(&self) -> u16 {
+        self.bus
+            .device::<InterruptController>()
+            .map_or(0, |controller| controller.vector())
+    }
+
+    // Enters the interrupt handler if an interrupt is requested and can be taken
+    fn take_interrupt(&mut self) -> bool {
+        if !self.interrupts.enabled || self.interrupts.in_handler() {
+            return false;
+        }
+
+        if !self.interrupt_raised && !self.bus.interrupt_requested() {
+            return false;
+        }
+
+        self.interrupt_raised = false;
+        self.enter_handler(self.interrupt_vector(), self.ip);
+        true
+    }
+
+    // Records the state before the next instruction, the timer runs with the undone cycles
+    fn begin_journal(&mut self) {
+        self.journal.begin(JournalEntry {
+            regs: self.regs,
+            ip: self.ip,
+            sp: self.sp,
+            flags: self.flags,
+            interrupts: self.interrupts,
+            cycles: self.cycles,
+            timer: self.bus.device::<Timer>().cloned(),
+            writes: vec![],
+        });
+    }
+
+    // Saves the address to return to and the flags, then continues at `vector` with interrupts
+    // disabled
+    fn enter_handler(&mut self, vector: u16, return_ip: u16) {
+        self.begin_journal();
+
+        self.interrupts.saved = Some((return_ip, self.flags));
+        self.interrupts.enabled = false;
+        self.ip = vector;
+        self.advance(INTERRUPT_CYCLES);
+
+        self.journal.commit();
+    }
+
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            word_size: W::SIZE,
+            ram: self.bus.ram().as_slice().to_vec(),
+            regs: self.regs.map(W::as_u16),
+            ip: self.ip,
+            sp: self.sp,
+            flags: self.flags,
+            interrupts: self.interrupts,
+            cycles: self.cycles,
+        }
+    }
+
+    pub fn restore(&mut self, snapshot: &Snapshot) {
+        self.bus.set_ram(RAM::from(snapshot.ram.clone()));
+        self.regs = snapshot.regs.map(W::from_u16);
+        self.ip = snapshot.ip;
+        self.sp = snapshot.sp;
+        self.flags = snapshot.flags;
+        self.interrupts = snapshot.interrupts;
+        self.cycles = snapshot.cycles;
+        self.accesses.clear();
+        self.journal.clear();
+    }
+
     pub fn load_rom(&mut self, rom: Vec<u8>) -> Result<(), LoadRomError> {
         if rom.is_empty() {
             return Err(LoadRomError::EmptyRom());
         }
 
-        let old_ram_size = self.ram.size();
-        self.ram = RAM::from(rom);
+        let old_ram_size = self.bus.ram().size();
+        self.rom_size = rom.len();
+        self.bus.set_ram(RAM::from(rom));
+        self.journal.clear();
+        self.cycles = 0;
 
-        let new_ram_size = self.ram.size();
-        if old_ram_size > new_ram_size {
-            self.ram.resize(old_ram_size);
-        }
+        let new_ram_size = match self.rom_size {
+            rom_size if rom_size > old_ram_size => {
+                (rom_size + STACK_RESERVE).min(ADDRESS_SPACE_SIZE)
+            }
+            _ => old_ram_size,
+        };
+        self.bus.ram_mut().resize(new_ram_size);
+
+        self.sp = self.stack_base() as u16;
 
         return Ok(());
     }
@@ -103,13 +531,17 @@ impl System {
             return Err(LoadRamError::EmptyRam());
         }
 
-        self.ram = RAM::from(ram);
+        self.bus.set_ram(RAM::from(ram));
+        self.rom_size = 0;
+        self.journal.clear();
+        self.cycles = 0;
+        self.sp = self.stack_base() as u16;
         return Ok(());
     }
 
-    fn alu_operation<F>(&mut self, destination_raw_reg: usize, a: u8, b: u8, operation: F)
+    fn alu_operation<F>(&mut self, destination_raw_reg: usize, a: W, b: W, operation: F)
     where
-        F: Fn(u8, u8) -> ALU::Result,
+        F: Fn(W, W) -> ALU::Result<W>,
     {
         let alu_result = operation(a, b);
 
@@ -119,78 +551,266 @@ impl System {
 
     // returns true if halted
     pub fn tick(&mut self) -> bool {
-        let first_byte = self.ram.get(self.ip as usize).unwrap_or(0);
-        let data = self.ram.get(self.ip as usize + 1).unwrap_or(0);
+        matches!(self.step(), Ok(Step::Halted) | Err(_))
+    }
 
-        let opcode_raw = first_byte >> 4;
-        let opcode = Opcode::try_from(opcode_raw);
-        if opcode.is_err() {
-            println!("Info: Illegal Instruction: {}", opcode_raw);
-            return false;
+    // Executes the instruction at ip or enters an interrupt handler. A faulting instruction is
+    // handled according to the fault policy, the fault is returned if the system halts on it.
+    pub fn step(&mut self) -> Result<Step, ExecutionFault> {
+        self.accesses.clear();
+
+        // entering an interrupt handler takes a step of its own
+        if self.take_interrupt() {
+            return Ok(Step::Interrupt);
+        }
+
+        let ip = self.ip;
+        let fault = match self.execute() {
+            Ok(step) => return Ok(step),
+            Err(fault) => fault,
+        };
+
+        self.ip = ip;
+        self.journal.discard();
+        self.accesses.clear();
+
+        // the length of an illegal instruction is unknown, only its first byte is skipped
+        let length = self
+            .decode()
+            .map_or(1, |(opcode, _)| Instruction::get_length(opcode));
+        let next = ip.wrapping_add(length);
+
+        match self.fault_policy {
+            FaultPolicy::Trap(vector) if !self.interrupts.in_handler() => {
+                self.enter_handler(vector, next);
+                Ok(Step::Trapped(fault))
+            }
+            FaultPolicy::Ignore => {
+                self.begin_journal();
+
+                self.ip = next;
+                self.journal.commit();
+                Ok(Step::Ignored(fault))
+            }
+            // handlers do not nest, a fault inside one halts
+            _ => Err(fault),
         }
+    }
+
+    // Instructions are fetched from ram directly, devices only see data accesses
+    fn fetch(&self, offset: u16) -> u8 {
+        self.bus
+            .ram()
+            .get(self.ip.wrapping_add(offset) as usize)
+            .unwrap_or(0)
+    }
+
+    // Opcode of the instruction at ip and the offset of its operands, the operands of extended
+    // instructions follow the extended opcode
+    fn decode(&self) -> Result<(Opcode, u16), OpcodeConversionError> {
+        Opcode::decode(|offset| Some(self.fetch(offset)))
+    }
+
+    // Executes the instruction at ip, a faulting instruction may have advanced the ip and begun
+    // a journal entry but has no other effect
+    fn execute(&mut self) -> Result<Step, ExecutionFault> {
+        let first_byte = self.fetch(0);
+        let Ok((opcode, operands)) = self.decode() else {
+            return Err(ExecutionFault::IllegalOpcode);
+        };
+
+        let data = self.fetch(operands);
+        self.begin_journal();
 
-        let opcode = opcode.unwrap();
         let reg_raw = (first_byte & 0b1111) as usize;
         let reg2_raw = (data >> 4) as usize;
         let reg3_raw = (data & 0b1111) as usize;
 
-        let reg = self.regs.get(reg_raw);
-        let reg2 = self.regs.get(reg2_raw);
-        let reg3 = self.regs.get(reg3_raw);
+        // register fields are nibbles, this only guards against a smaller register file
+        if let Some(register) = [reg_raw, reg2_raw, reg3_raw]
+            .into_iter()
+            .find(|register| *register >= self.regs.len())
+        {
+            return Err(ExecutionFault::InvalidRegister(register));
+        }
+
+        let reg = self.regs[reg_raw];
+        let reg2 = self.regs[reg2_raw];
+        let reg3 = self.regs[reg3_raw];
 
         let imm = data;
         let imm4 = data & 0b1111;
 
-        let offset = (*reg2.unwrap_or(&0) as usize) << 8 | *reg3.unwrap_or(&0) as usize;
+        // addresses are formed by the low bytes of two registers
+        let byte = |reg: W| reg.low_byte();
+        let offset = (byte(reg2) as usize) << 8 | byte(reg3) as usize;
 
         self.ip += Instruction::get_length(opcode);
         match opcode {
             Opcode::HLT => {
                 self.ip -= Instruction::get_length(Opcode::HLT); // Undo goto next instruction
-                println!("Info: halting at ip={}", self.ip);
-                return true;
-            }
-            Opcode::ADD => self.alu_operation(reg_raw, *reg2.unwrap(), *reg3.unwrap(), ALU::add),
-            Opcode::XOR => self.alu_operation(reg_raw, *reg2.unwrap(), *reg3.unwrap(), ALU::xor),
-            Opcode::SUB => self.alu_operation(reg_raw, *reg2.unwrap(), *reg3.unwrap(), ALU::sub),
-            Opcode::SHL => self.alu_operation(reg_raw, *reg2.unwrap(), imm4, ALU::shl),
-            Opcode::SHR => self.alu_operation(reg_raw, *reg2.unwrap(), imm4, ALU::shr),
-            Opcode::AND => self.alu_operation(reg_raw, *reg2.unwrap(), *reg3.unwrap(), ALU::and),
-            Opcode::OR => self.alu_operation(reg_raw, *reg2.unwrap(), *reg3.unwrap(), ALU::or),
+                self.journal.discard();
+                return Ok(Step::Halted);
+            }
+            Opcode::ADD => self.alu_operation(reg_raw, reg2, reg3, ALU::add),
+            Opcode::XOR => self.alu_operation(reg_raw, reg2, reg3, ALU::xor),
+            Opcode::SUB => self.alu_operation(reg_raw, reg2, reg3, ALU::sub),
+            Opcode::SHL => self.alu_operation(reg_raw, reg2, W::from_u8(imm4), ALU::shl),
+            Opcode::SHR => self.alu_operation(reg_raw, reg2, W::from_u8(imm4), ALU::shr),
+            Opcode::ROL => self.alu_operation(reg_raw, reg2, W::from_u8(imm4), ALU::rol),
+            Opcode::ROR => self.alu_operation(reg_raw, reg2, W::from_u8(imm4), ALU::ror),
+            Opcode::ASR => self.alu_operation(reg_raw, reg2, W::from_u8(imm4), ALU::asr),
+            Opcode::RCL | Opcode::RCR | Opcode::RCLV | Opcode::RCRV => {
+                let carry = self.flags.is_set(Flags::Carry);
+                let amount = match opcode {
+                    Opcode::RCL | Opcode::RCR => W::from_u8(imm4),
+                    _ => reg3,
+                };
+
+                let rotate = match opcode {
+                    Opcode::RCL | Opcode::RCLV => ALU::rcl,
+                    _ => ALU::rcr,
+                };
+
+                self.alu_operation(reg_raw, reg2, amount, |a, b| rotate(a, b, carry))
+            }
+            Opcode::SHLV => self.alu_operation(reg_raw, reg2, reg3, ALU::shl),
+            Opcode::SHRV => self.alu_operation(reg_raw, reg2, reg3, ALU::shr),
+            Opcode::ASRV => self.alu_operation(reg_raw, reg2, reg3, ALU::asr),
+            Opcode::ROLV => self.alu_operation(reg_raw, reg2, reg3, ALU::rol),
+            Opcode::RORV => self.alu_operation(reg_raw, reg2, reg3, ALU::ror),
+            Opcode::AND => self.alu_operation(reg_raw, reg2, reg3, ALU::and),
+            Opcode::OR => self.alu_operation(reg_raw, reg2, reg3, ALU::or),
+            Opcode::MUL => self.alu_operation(reg_raw, reg2, reg3, ALU::mul),
+            Opcode::MULH => self.alu_operation(reg_raw, reg2, reg3, ALU::mulh),
+            Opcode::IMUL => self.alu_operation(reg_raw, reg2, reg3, ALU::imul),
+            Opcode::IMULH => self.alu_operation(reg_raw, reg2, reg3, ALU::imulh),
+            Opcode::DIV | Opcode::MOD | Opcode::IDIV | Opcode::IMOD => {
+                let operation = match opcode {
+                    Opcode::DIV => ALU::div,
+                    Opcode::MOD => ALU::rem,
+                    Opcode::IDIV => ALU::idiv,
+                    _ => ALU::irem,
+                };
+
+                let Some(result) = operation(reg2, reg3) else {
+                    return Err(ExecutionFault::DivideByZero);
+                };
+
+                self.flags = result.flags;
+                self.regs[reg_raw] = result.value;
+            }
             Opcode::LDI => {
-                self.regs[reg_raw] = imm;
+                self.regs[reg_raw] = W::from_u8(imm);
             }
             Opcode::SB => {
-                self.set_mem(offset as u16, *reg.unwrap());
+                if let Some(address) = self.inaccessible(offset as u16) {
+                    return Err(ExecutionFault::OutOfBoundsWrite(address));
+                }
+
+                self.store_word(offset as u16, reg);
             }
             Opcode::LB => {
-                self.regs[reg_raw] = self.get_mem(offset as u16);
+                if let Some(address) = self.inaccessible(offset as u16) {
+                    return Err(ExecutionFault::OutOfBoundsRead(address));
+                }
+
+                self.regs[reg_raw] = self.load_word(offset as u16);
             }
-            Opcode::JNZ => {
-                let zf_set = self.flags.is_set(Flags::Zero);
-                if !zf_set {
-                    self.ip = ((*reg.unwrap() as u16) << 8) | *reg2.unwrap() as u16;
+            Opcode::JNZ
+            | Opcode::JZ
+            | Opcode::JNC
+            | Opcode::JS
+            | Opcode::JNS
+            | Opcode::JO
+            | Opcode::JNO
+            | Opcode::JL
+            | Opcode::JGE
+            | Opcode::JLE
+            | Opcode::JG
+            | Opcode::JBE
+            | Opcode::JA
+            | Opcode::JMP => {
+                let condition = opcode.branch_condition().unwrap();
+                if self.flags.satisfies(condition) {
+                    self.ip = (byte(reg) as u16) << 8 | byte(reg2) as u16;
                 }
             }
             Opcode::JAL => {
                 let new_ip = offset as u16;
 
-                self.regs[reg_raw] = (self.ip >> 8) as u8;
-                self.regs[reg2_raw] = self.ip as u8;
+                self.regs[reg_raw] = W::from_u8((self.ip >> 8) as u8);
+                self.regs[reg2_raw] = W::from_u8(self.ip as u8);
 
                 self.ip = new_ip;
             }
             Opcode::JC => {
                 let cf_set = self.flags.is_set(Flags::Carry);
                 if cf_set {
-                    self.ip = ((*reg.unwrap() as u16) << 8) | *reg2.unwrap() as u16;
+                    self.ip = (byte(reg) as u16) << 8 | byte(reg2) as u16;
                 }
             }
+            Opcode::ADC => {
+                let carry = self.flags.is_set(Flags::Carry);
+                self.alu_operation(reg_raw, reg2, reg3, |a, b| ALU::adc(a, b, carry))
+            }
+            Opcode::SBC => {
+                let borrow = self.flags.is_set(Flags::Carry);
+                self.alu_operation(reg_raw, reg2, reg3, |a, b| ALU::sbc(a, b, borrow))
+            }
+            Opcode::CMP => {
+                // only the flags of the subtraction are kept
+                self.flags = ALU::sub(reg, reg2).flags;
+            }
             Opcode::NOT => {
-                self.regs[reg_raw] = !*reg2.unwrap();
+                self.regs[reg_raw] = !reg2;
             }
+            Opcode::EI => {
+                self.interrupts.enabled = true;
+            }
+            Opcode::DI => {
+                self.interrupts.enabled = false;
+            }
+            Opcode::RETI => {
+                // outside of a handler there is nothing to return to
+                if let Some((ip, flags)) = self.interrupts.saved.take() {
+                    self.ip = ip;
+                    self.flags = flags;
+                    self.interrupts.enabled = true;
+                }
+            }
+            Opcode::PUSH => {
+                let bytes = reg.to_be_bytes();
+                if let Err(error) = self.push(&bytes) {
+                    return Err(ExecutionFault::Stack(error));
+                }
+            }
+            Opcode::POP => match self.pop(W::BYTES) {
+                Ok(bytes) => self.regs[reg_raw] = W::from_be_bytes(&bytes),
+                Err(error) => return Err(ExecutionFault::Stack(error)),
+            },
+            Opcode::CALL => {
+                // the return address is pushed with the most significant byte first
+                let target = (byte(reg) as u16) << 8 | byte(reg2) as u16;
+                if let Err(error) = self.push(&self.ip.to_be_bytes()) {
+                    return Err(ExecutionFault::Stack(error));
+                }
+
+                self.ip = target;
+            }
+            Opcode::RET => match self.pop(2) {
+                Ok(bytes) => self.ip = u16::from_be_bytes([bytes[0], bytes[1]]),
+                Err(error) => return Err(ExecutionFault::Stack(error)),
+            },
         };
 
-        false
+        self.advance(opcode.cycles());
+        self.journal.commit();
+        Ok(Step::Executed)
     }
 }
+
+// The stack starts at the end of ram, ram beyond the 16-bit address space is not reachable
+fn stack_base(bus: &Bus) -> usize {
+    bus.ram().size().min(ADDRESS_SPACE_SIZE)
+}
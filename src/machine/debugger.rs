@@ -0,0 +1,348 @@
+use std::fmt::Display;
+
+use crate::machine::{
+    computer::System,
+    fault::ExecutionFault,
+    word::{Word, WordSize},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AccessKind {
+    Read,
+    Write,
+}
+
+// Memory access performed by an instruction, instruction fetches are not included
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MemoryAccess {
+    pub address: u16,
+    pub kind: AccessKind,
+    pub value: u8,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WatchMode {
+    Read,
+    Write,
+    ReadWrite,
+}
+
+impl WatchMode {
+    pub fn matches(&self, kind: AccessKind) -> bool {
+        match self {
+            WatchMode::Read => kind == AccessKind::Read,
+            WatchMode::Write => kind == AccessKind::Write,
+            WatchMode::ReadWrite => true,
+        }
+    }
+}
+
+impl TryFrom<&str> for WatchMode {
+    type Error = ConditionParseError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "r" => Ok(WatchMode::Read),
+            "w" => Ok(WatchMode::Write),
+            "rw" => Ok(WatchMode::ReadWrite),
+            _ => Err(ConditionParseError::InvalidWatchMode),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ConditionParseError {
+    InvalidOperand,
+    InvalidComparison,
+    InvalidWatchMode,
+    TrailingInput,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConditionOperand {
+    Register(u8),
+    Memory(u16),
+    Constant(u16),
+}
+
+impl ConditionOperand {
+    pub fn value<W: Word>(&self, system: &System<W>) -> u16 {
+        match self {
+            ConditionOperand::Register(index) => system.get_regs()[*index as usize].as_u16(),
+            ConditionOperand::Memory(address) => system.get_mem(*address).unwrap_or(0) as u16,
+            ConditionOperand::Constant(value) => *value,
+        }
+    }
+
+    fn parse(value: &str, word_size: WordSize) -> Result<Self, ConditionParseError> {
+        let number = |value: &str| {
+            match value.strip_prefix("0x") {
+                Some(hexadecimal) => u16::from_str_radix(hexadecimal, 16),
+                None => value.parse::<u16>(),
+            }
+            .map_err(|_| ConditionParseError::InvalidOperand)
+        };
+
+        if let Some(register) = value.strip_prefix('r') {
+            return match register.parse::<u8>() {
+                Ok(index) if index < 16 => Ok(ConditionOperand::Register(index)),
+                _ => Err(ConditionParseError::InvalidOperand),
+            };
+        }
+
+        if let Some(address) = value.strip_prefix('[').and_then(|v| v.strip_suffix(']')) {
+            return Ok(ConditionOperand::Memory(number(address)?));
+        }
+
+        match number(value)? {
+            value if value <= word_size.max_value() => Ok(ConditionOperand::Constant(value)),
+            _ => Err(ConditionParseError::InvalidOperand),
+        }
+    }
+}
+
+impl Display for ConditionOperand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConditionOperand::Register(index) => write!(f, "r{}", index),
+            ConditionOperand::Memory(address) => write!(f, "[{:#06x}]", address),
+            ConditionOperand::Constant(value) => write!(f, "{:#04x}", value),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Comparison {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+impl Comparison {
+    const SYMBOLS: [(&'static str, Comparison); 6] = [
+        ("==", Comparison::Equal),
+        ("!=", Comparison::NotEqual),
+        ("<=", Comparison::LessOrEqual),
+        (">=", Comparison::GreaterOrEqual),
+        ("<", Comparison::Less),
+        (">", Comparison::Greater),
+    ];
+
+    fn symbol(&self) -> &'static str {
+        Self::SYMBOLS
+            .iter()
+            .find(|(_, comparison)| comparison == self)
+            .map(|(symbol, _)| *symbol)
+            .unwrap()
+    }
+}
+
+// Unsigned comparison of a register, memory byte or constant, e.g. `r3 == 0x10`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Condition {
+    pub left: ConditionOperand,
+    pub comparison: Comparison,
+    pub right: ConditionOperand,
+}
+
+impl Condition {
+    pub fn parse(condition: &str) -> Result<Self, ConditionParseError> {
+        Self::parse_sized(condition, WordSize::Bits8)
+    }
+
+    // Constants have to fit in a word of the given size
+    pub fn parse_sized(condition: &str, word_size: WordSize) -> Result<Self, ConditionParseError> {
+        let condition = condition.split_whitespace().collect::<String>();
+
+        let Some((index, (symbol, comparison))) = Comparison::SYMBOLS
+            .iter()
+            .filter_map(|entry| condition.find(entry.0).map(|index| (index, entry)))
+            .min_by_key(|(index, (symbol, _))| (*index, usize::MAX - symbol.len()))
+        else {
+            return Err(ConditionParseError::InvalidComparison);
+        };
+
+        let left = ConditionOperand::parse(&condition[..index], word_size)?;
+        let right = ConditionOperand::parse(&condition[index + symbol.len()..], word_size)?;
+
+        Ok(Condition {
+            left,
+            comparison: *comparison,
+            right,
+        })
+    }
+
+    pub fn evaluate<W: Word>(&self, system: &System<W>) -> bool {
+        let left = self.left.value(system);
+        let right = self.right.value(system);
+
+        match self.comparison {
+            Comparison::Equal => left == right,
+            Comparison::NotEqual => left != right,
+            Comparison::Less => left < right,
+            Comparison::LessOrEqual => left <= right,
+            Comparison::Greater => left > right,
+            Comparison::GreaterOrEqual => left >= right,
+        }
+    }
+}
+
+impl Display for Condition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {} {}",
+            self.left,
+            self.comparison.symbol(),
+            self.right
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BreakpointKind {
+    Address(u16),          // stop before executing the instruction at the address
+    Watch(u16, WatchMode), // stop after an instruction accessed the address
+}
+
+#[derive(Debug, Clone)]
+pub struct Breakpoint {
+    pub id: usize,
+    pub kind: BreakpointKind,
+    pub condition: Option<Condition>,
+    pub enabled: bool,
+    pub hits: usize,
+}
+
+impl Display for Breakpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.kind {
+            BreakpointKind::Address(address) => write!(f, "breakpoint at {:#06x}", address)?,
+            BreakpointKind::Watch(address, mode) => {
+                write!(f, "watchpoint ({:?}) on [{:#06x}]", mode, address)?
+            }
+        }
+
+        if let Some(condition) = &self.condition {
+            write!(f, " if {}", condition)?;
+        }
+
+        Ok(())
+    }
+}
+
+// Why execution stopped
+#[derive(Debug, Clone, PartialEq)]
+pub enum StopReason {
+    Halted,
+    Breakpoint(usize),               // id of the breakpoint
+    Watchpoint(usize, MemoryAccess), // id of the watchpoint and the triggering access
+    StepLimit,
+    Interrupted,
+    StartOfHistory,        // nothing left to undo when running backwards
+    Fault(ExecutionFault), // halted in front of the faulting instruction
+}
+
+#[derive(Debug)]
+pub enum BreakpointError {
+    NoSuchBreakpoint(usize),
+}
+
+#[derive(Default)]
+pub struct Breakpoints {
+    list: Vec<Breakpoint>,
+    next_id: usize,
+}
+
+impl Breakpoints {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, kind: BreakpointKind, condition: Option<Condition>) -> usize {
+        self.next_id += 1;
+        self.list.push(Breakpoint {
+            id: self.next_id,
+            kind,
+            condition,
+            enabled: true,
+            hits: 0,
+        });
+
+        self.next_id
+    }
+
+    pub fn delete(&mut self, id: usize) -> Result<(), BreakpointError> {
+        let length = self.list.len();
+        self.list.retain(|breakpoint| breakpoint.id != id);
+
+        if self.list.len() == length {
+            return Err(BreakpointError::NoSuchBreakpoint(id));
+        }
+
+        Ok(())
+    }
+
+    pub fn set_enabled(&mut self, id: usize, enabled: bool) -> Result<(), BreakpointError> {
+        match self.list.iter_mut().find(|breakpoint| breakpoint.id == id) {
+            Some(breakpoint) => {
+                breakpoint.enabled = enabled;
+                Ok(())
+            }
+            None => Err(BreakpointError::NoSuchBreakpoint(id)),
+        }
+    }
+
+    pub fn get(&self, id: usize) -> Option<&Breakpoint> {
+        self.list.iter().find(|breakpoint| breakpoint.id == id)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Breakpoint> {
+        self.list.iter()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.list.is_empty()
+    }
+
+    // Checks all enabled breakpoints against the state after an instruction was executed
+    pub fn check<W: Word>(
+        &mut self,
+        system: &System<W>,
+        accesses: &[MemoryAccess],
+    ) -> Option<StopReason> {
+        for breakpoint in self.list.iter_mut().filter(|breakpoint| breakpoint.enabled) {
+            let reason = match breakpoint.kind {
+                BreakpointKind::Address(address) if address == system.get_ip() => {
+                    StopReason::Breakpoint(breakpoint.id)
+                }
+                BreakpointKind::Watch(address, mode) => {
+                    let access = accesses
+                        .iter()
+                        .find(|access| access.address == address && mode.matches(access.kind));
+
+                    match access {
+                        Some(access) => StopReason::Watchpoint(breakpoint.id, *access),
+                        None => continue,
+                    }
+                }
+                _ => continue,
+            };
+
+            if breakpoint
+                .condition
+                .is_some_and(|condition| !condition.evaluate(system))
+            {
+                continue;
+            }
+
+            breakpoint.hits += 1;
+            return Some(reason);
+        }
+
+        None
+    }
+}
@@ -0,0 +1,60 @@
+use std::any::Any;
+
+use crate::machine::bus::Device;
+
+// Holds the address interrupt handlers start at, memory mapped as high and low byte
+#[derive(Default)]
+pub struct InterruptController {
+    vector: u16,
+}
+
+impl InterruptController {
+    pub const NAME: &'static str = "interrupt";
+
+    pub const VECTOR_HIGH: u16 = 0;
+    pub const VECTOR_LOW: u16 = 1;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn vector(&self) -> u16 {
+        self.vector
+    }
+
+    pub fn set_vector(&mut self, vector: u16) {
+        self.vector = vector;
+    }
+}
+
+impl Device for InterruptController {
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    fn size(&self) -> u16 {
+        2
+    }
+
+    fn read(&mut self, offset: u16) -> u8 {
+        match offset {
+            Self::VECTOR_HIGH => (self.vector >> 8) as u8,
+            _ => self.vector as u8,
+        }
+    }
+
+    fn write(&mut self, offset: u16, value: u8) {
+        self.vector = match offset {
+            Self::VECTOR_HIGH => (self.vector & 0x00ff) | (value as u16) << 8,
+            _ => (self.vector & 0xff00) | value as u16,
+        };
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
@@ -0,0 +1,3 @@
+pub mod interrupt;
+pub mod serial;
+pub mod timer;
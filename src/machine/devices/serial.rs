@@ -0,0 +1,140 @@
+use std::{any::Any, collections::VecDeque};
+
+use crate::machine::bus::Device;
+
+// Supplies received bytes once the receive queue ran empty, e.g. read from stdin
+pub trait InputSource {
+    // Returns the next bytes or None once the source is exhausted. `output` holds the output
+    // that was not taken yet, an interactive source can show (and take) it before blocking.
+    fn receive(&mut self, output: &mut Vec<u8>) -> Option<Vec<u8>>;
+
+    // Like `receive` without blocking, returns no bytes while nothing arrived. The serial port
+    // polls after every instruction to raise the receive interrupt.
+    fn poll(&mut self, output: &mut Vec<u8>) -> Option<Vec<u8>> {
+        self.receive(output)
+    }
+}
+
+// Serial port occupying two addresses:
+//   +0 data: writing sends a byte to the output buffer, reading takes the next received byte
+//   +1 status: bit 0 is set while received data is available, bit 1 (writable) enables the
+//      receive interrupt which is requested as long as data is available
+#[derive(Default)]
+pub struct Serial {
+    output: Vec<u8>,
+    input: VecDeque<u8>,
+    source: Option<Box<dyn InputSource>>,
+    interrupt_enabled: bool,
+}
+
+impl Serial {
+    pub const NAME: &'static str = "serial";
+
+    pub const DATA: u16 = 0;
+    pub const STATUS: u16 = 1;
+
+    pub const STATUS_DATA_AVAILABLE: u8 = 0b01;
+    pub const STATUS_INTERRUPT_ENABLE: u8 = 0b10;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn output(&self) -> &[u8] {
+        &self.output
+    }
+
+    // Output written since the last call
+    pub fn take_output(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.output)
+    }
+
+    // Queues bytes to be received by the program
+    pub fn push_input(&mut self, bytes: &[u8]) {
+        self.input.extend(bytes);
+    }
+
+    pub fn pending_input(&self) -> usize {
+        self.input.len()
+    }
+
+    pub fn set_input_source(&mut self, source: Option<Box<dyn InputSource>>) {
+        self.source = source;
+    }
+
+    // Asks the input source for more data if nothing is queued, an exhausted source is dropped
+    fn fill_input(&mut self, blocking: bool) {
+        if !self.input.is_empty() {
+            return;
+        }
+
+        let Some(source) = &mut self.source else {
+            return;
+        };
+
+        let bytes = match blocking {
+            true => source.receive(&mut self.output),
+            false => source.poll(&mut self.output),
+        };
+
+        match bytes {
+            Some(bytes) => self.input.extend(bytes),
+            None => self.source = None,
+        }
+    }
+}
+
+impl Device for Serial {
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    fn size(&self) -> u16 {
+        2
+    }
+
+    fn read(&mut self, offset: u16) -> u8 {
+        match offset {
+            Self::DATA => {
+                self.fill_input(true);
+                self.input.pop_front().unwrap_or(0)
+            }
+            // polled programs see no data instead of waiting for the source
+            _ => {
+                let mut status = 0;
+                if !self.input.is_empty() {
+                    status |= Self::STATUS_DATA_AVAILABLE;
+                }
+
+                if self.interrupt_enabled {
+                    status |= Self::STATUS_INTERRUPT_ENABLE;
+                }
+
+                status
+            }
+        }
+    }
+
+    fn write(&mut self, offset: u16, value: u8) {
+        match offset {
+            Self::DATA => self.output.push(value),
+            _ => self.interrupt_enabled = value & Self::STATUS_INTERRUPT_ENABLE > 0,
+        }
+    }
+
+    fn interrupt_requested(&self) -> bool {
+        self.interrupt_enabled && !self.input.is_empty()
+    }
+
+    fn tick(&mut self, _cycles: u64) {
+        self.fill_input(false);
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
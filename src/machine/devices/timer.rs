@@ -0,0 +1,132 @@
+use std::any::Any;
+
+use crate::machine::bus::Device;
+
+// Counter driven by cpu cycles, occupying four addresses:
+//   +0 counter: counts up once every `prescaler + 1` cycles while enabled
+//   +1 reload: loaded into the counter when it overflows from 0xff
+//   +2 prescaler
+//   +3 control/status: bit 0 enables counting, bit 1 enables the overflow interrupt, bit 7 is
+//      set on overflow and cleared by writing it as 1
+// An overflow therefore occurs every `(0x100 - reload) * (prescaler + 1)` cycles.
+#[derive(Debug, Clone, Default)]
+pub struct Timer {
+    counter: u8,
+    reload: u8,
+    prescaler: u8,
+    enabled: bool,
+    interrupt_enabled: bool,
+    overflow: bool,
+    divider: u64, // cycles counted towards the next increment
+}
+
+impl Timer {
+    pub const NAME: &'static str = "timer";
+
+    pub const COUNTER: u16 = 0;
+    pub const RELOAD: u16 = 1;
+    pub const PRESCALER: u16 = 2;
+    pub const CONTROL: u16 = 3;
+
+    pub const CONTROL_ENABLE: u8 = 0b0000_0001;
+    pub const CONTROL_INTERRUPT_ENABLE: u8 = 0b0000_0010;
+    pub const STATUS_OVERFLOW: u8 = 0b1000_0000;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn counter(&self) -> u8 {
+        self.counter
+    }
+
+    pub fn overflowed(&self) -> bool {
+        self.overflow
+    }
+
+    fn increment(&mut self) {
+        if self.counter == u8::MAX {
+            self.counter = self.reload;
+            self.overflow = true;
+        } else {
+            self.counter += 1;
+        }
+    }
+}
+
+impl Device for Timer {
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    fn size(&self) -> u16 {
+        4
+    }
+
+    fn read(&mut self, offset: u16) -> u8 {
+        match offset {
+            Self::COUNTER => self.counter,
+            Self::RELOAD => self.reload,
+            Self::PRESCALER => self.prescaler,
+            _ => {
+                let mut control = 0;
+                if self.enabled {
+                    control |= Self::CONTROL_ENABLE;
+                }
+
+                if self.interrupt_enabled {
+                    control |= Self::CONTROL_INTERRUPT_ENABLE;
+                }
+
+                if self.overflow {
+                    control |= Self::STATUS_OVERFLOW;
+                }
+
+                control
+            }
+        }
+    }
+
+    fn write(&mut self, offset: u16, value: u8) {
+        match offset {
+            Self::COUNTER => self.counter = value,
+            Self::RELOAD => self.reload = value,
+            Self::PRESCALER => {
+                self.prescaler = value;
+                self.divider = 0;
+            }
+            _ => {
+                self.enabled = value & Self::CONTROL_ENABLE > 0;
+                self.interrupt_enabled = value & Self::CONTROL_INTERRUPT_ENABLE > 0;
+                if value & Self::STATUS_OVERFLOW > 0 {
+                    self.overflow = false;
+                }
+            }
+        }
+    }
+
+    fn interrupt_requested(&self) -> bool {
+        self.interrupt_enabled && self.overflow
+    }
+
+    fn tick(&mut self, cycles: u64) {
+        if !self.enabled {
+            return;
+        }
+
+        let period = self.prescaler as u64 + 1;
+        self.divider += cycles;
+        while self.divider >= period {
+            self.divider -= period;
+            self.increment();
+        }
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
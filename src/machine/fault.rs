@@ -0,0 +1,61 @@
+use std::fmt::Display;
+
+use crate::machine::computer::StackError;
+
+// Reason an instruction could not be executed. Faults are detected before the instruction has
+// any effect, so the system can stop in front of it.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExecutionFault {
+    IllegalOpcode,
+    InvalidRegister(usize),
+    OutOfBoundsRead(u16),  // address that is neither ram nor a device
+    OutOfBoundsWrite(u16), // address that is neither ram nor a device
+    Stack(StackError),
+    DivideByZero,
+}
+
+impl Display for ExecutionFault {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::IllegalOpcode => write!(f, "illegal opcode"),
+            Self::InvalidRegister(register) => write!(f, "invalid register r{}", register),
+            Self::OutOfBoundsRead(address) => {
+                write!(f, "out of bounds memory load [{:#06x}]", address)
+            }
+            Self::OutOfBoundsWrite(address) => {
+                write!(f, "out of bounds memory store [{:#06x}]", address)
+            }
+            Self::Stack(error) => write!(f, "stack {:?}", error),
+            Self::DivideByZero => write!(f, "divide by zero"),
+        }
+    }
+}
+
+// What the system does when an instruction faults
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum FaultPolicy {
+    #[default]
+    Halt, // stop in front of the faulting instruction
+    Trap(u16), // enter the handler at the address like an interrupt, RETI skips the instruction
+    Ignore,    // skip the faulting instruction
+}
+
+impl Display for FaultPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Halt => write!(f, "halt"),
+            Self::Trap(vector) => write!(f, "trap {:#06x}", vector),
+            Self::Ignore => write!(f, "ignore"),
+        }
+    }
+}
+
+// Outcome of a step that did not halt on a fault
+#[derive(Debug, Clone, PartialEq)]
+pub enum Step {
+    Executed,
+    Interrupt, // entered the interrupt handler instead of executing an instruction
+    Halted,
+    Trapped(ExecutionFault), // entered the fault handler
+    Ignored(ExecutionFault), // skipped the faulting instruction
+}
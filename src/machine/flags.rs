@@ -1,5 +1,7 @@
 use std::fmt::{Display, Write};
 
+use crate::types::BranchCondition;
+
 #[repr(u8)]
 #[derive(Debug, Clone)]
 pub enum Flags {
@@ -20,6 +22,7 @@ impl Display for Flags {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct FlagsRegister {
     flags: [bool; 4],
 }
@@ -53,4 +56,49 @@ impl FlagsRegister {
 
         return result;
     }
+
+    // Packed as one bit per flag, Zero being the least significant bit
+    pub fn bits(&self) -> u8 {
+        let mut bits = 0;
+        for (index, set) in self.flags.iter().enumerate() {
+            if *set {
+                bits |= 1 << index;
+            }
+        }
+
+        bits
+    }
+
+    // Signed comparisons after CMP use sign and overflow, unsigned ones the carry (borrow)
+    pub fn satisfies(&self, condition: BranchCondition) -> bool {
+        let zero = self.is_set(Flags::Zero);
+        let carry = self.is_set(Flags::Carry);
+        let less = self.is_set(Flags::Sign) != self.is_set(Flags::Overflow);
+
+        match condition {
+            BranchCondition::NotZero => !zero,
+            BranchCondition::Zero => zero,
+            BranchCondition::NoCarry => !carry,
+            BranchCondition::Sign => self.is_set(Flags::Sign),
+            BranchCondition::NoSign => !self.is_set(Flags::Sign),
+            BranchCondition::Overflow => self.is_set(Flags::Overflow),
+            BranchCondition::NoOverflow => !self.is_set(Flags::Overflow),
+            BranchCondition::Less => less,
+            BranchCondition::GreaterOrEqual => !less,
+            BranchCondition::LessOrEqual => less || zero,
+            BranchCondition::Greater => !less && !zero,
+            BranchCondition::BelowOrEqual => carry || zero,
+            BranchCondition::Above => !carry && !zero,
+            BranchCondition::Always => true,
+        }
+    }
+
+    pub fn from_bits(bits: u8) -> Self {
+        let mut flags = [false; 4];
+        for (index, flag) in flags.iter_mut().enumerate() {
+            *flag = bits & (1 << index) > 0;
+        }
+
+        Self { flags }
+    }
 }
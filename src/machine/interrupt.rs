@@ -0,0 +1,30 @@
+use std::fmt::Display;
+
+use crate::machine::flags::FlagsRegister;
+
+// Interrupt state of the cpu. Interrupts are only taken while enabled and no handler is
+// running, entering a handler saves the ip and flags of the interrupted program and disables
+// interrupts until RETI.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct InterruptState {
+    pub enabled: bool,
+    pub saved: Option<(u16, FlagsRegister)>, // ip and flags to return to while a handler runs
+}
+
+impl InterruptState {
+    pub fn in_handler(&self) -> bool {
+        self.saved.is_some()
+    }
+}
+
+impl Display for InterruptState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(if self.enabled { "enabled" } else { "disabled" })?;
+
+        if let Some((ip, _)) = self.saved {
+            write!(f, ", in handler returning to {:#06x}", ip)?;
+        }
+
+        Ok(())
+    }
+}
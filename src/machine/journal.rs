@@ -0,0 +1,104 @@
+use std::collections::VecDeque;
+
+use crate::machine::{
+    devices::timer::Timer, flags::FlagsRegister, interrupt::InterruptState, word::Word,
+};
+
+// State before an instruction was executed, together with the old value of every memory byte
+// it wrote. Applying the entry undoes the instruction.
+#[derive(Debug, Clone)]
+pub struct JournalEntry<W: Word = u8> {
+    pub regs: [W; 16],
+    pub ip: u16,
+    pub sp: u16,
+    pub flags: FlagsRegister,
+    pub interrupts: InterruptState,
+    pub cycles: u64,
+    pub timer: Option<Timer>, // counts the cycles, None if it is not attached
+    pub writes: Vec<(u16, u8)>, // address and old value, in the order they were written
+}
+
+// Undo history of executed instructions, the oldest entries are dropped beyond `limit`
+pub struct Journal<W: Word = u8> {
+    entries: VecDeque<JournalEntry<W>>,
+    pending: Option<JournalEntry<W>>,
+    limit: usize,
+}
+
+impl<W: Word> Journal<W> {
+    pub const DEFAULT_LIMIT: usize = 10_000;
+
+    pub fn new(limit: usize) -> Self {
+        Self {
+            entries: VecDeque::new(),
+            pending: None,
+            limit,
+        }
+    }
+
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    // A limit of zero disables recording
+    pub fn set_limit(&mut self, limit: usize) {
+        self.limit = limit;
+        while self.entries.len() > limit {
+            self.entries.pop_front();
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.pending = None;
+    }
+
+    // Starts the entry of the next instruction with the state before it, without writes
+    pub(crate) fn begin(&mut self, entry: JournalEntry<W>) {
+        if self.limit == 0 {
+            return;
+        }
+
+        self.pending = Some(entry);
+    }
+
+    pub(crate) fn record_write(&mut self, address: u16, old_value: u8) {
+        if let Some(entry) = &mut self.pending {
+            entry.writes.push((address, old_value));
+        }
+    }
+
+    pub(crate) fn commit(&mut self) {
+        let Some(entry) = self.pending.take() else {
+            return;
+        };
+
+        if self.entries.len() == self.limit {
+            self.entries.pop_front();
+        }
+
+        self.entries.push_back(entry);
+    }
+
+    pub(crate) fn discard(&mut self) {
+        self.pending = None;
+    }
+
+    pub(crate) fn pop(&mut self) -> Option<JournalEntry<W>> {
+        self.entries.pop_back()
+    }
+}
+
+impl<W: Word> Default for Journal<W> {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_LIMIT)
+    }
+}
@@ -1,4 +1,12 @@
 pub mod alu;
+pub mod bus;
 pub mod computer;
+pub mod debugger;
+pub mod devices;
+pub mod fault;
 pub mod flags;
+pub mod interrupt;
+pub mod journal;
+pub mod snapshot;
 pub mod storage;
+pub mod word;
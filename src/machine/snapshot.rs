@@ -0,0 +1,274 @@
+use std::fmt::Display;
+
+use crate::machine::{
+    flags::{Flags, FlagsRegister},
+    interrupt::InterruptState,
+    word::WordSize,
+};
+
+// Save-state file layout, all multi-byte values are big-endian:
+//   magic "MRTS", version (1 byte), word size in bits (1 byte), ip (2 bytes), flags (1 byte),
+//   interrupts: enabled/in handler bits (1 byte), saved ip (2 bytes), saved flags (1 byte),
+//   stack pointer (2 bytes), cycles (8 bytes),
+//   16 registers (1 word each), ram size (4 bytes), ram contents
+const MAGIC: &[u8; 4] = b"MRTS";
+pub const SNAPSHOT_VERSION: u8 = 1;
+
+const INTERRUPTS_ENABLED: u8 = 0b01;
+const INTERRUPTS_IN_HANDLER: u8 = 0b10;
+
+// Copy of the cpu state and ram. Breakpoints and the state of the devices are not part of it:
+// the timer, the serial queues and the interrupt vector keep their values on restore.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snapshot {
+    pub word_size: WordSize,
+    pub ram: Vec<u8>,
+    pub regs: [u16; 16], // zero extended words
+    pub ip: u16,
+    pub sp: u16,
+    pub flags: FlagsRegister,
+    pub interrupts: InterruptState,
+    pub cycles: u64,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum SnapshotError {
+    InvalidMagic,
+    UnsupportedVersion(u8),
+    UnsupportedWordSize(u8),
+    Truncated,
+    TrailingData,
+}
+
+// Reads consecutive fields of a save-state
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, count: usize) -> Result<&'a [u8], SnapshotError> {
+        if self.data.len() < count {
+            return Err(SnapshotError::Truncated);
+        }
+
+        let (taken, rest) = self.data.split_at(count);
+        self.data = rest;
+        Ok(taken)
+    }
+
+    fn byte(&mut self) -> Result<u8, SnapshotError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn word(&mut self) -> Result<u16, SnapshotError> {
+        let bytes = self.take(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+}
+
+impl Snapshot {
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut data = vec![];
+
+        data.extend_from_slice(MAGIC);
+        data.push(SNAPSHOT_VERSION);
+        data.push(self.word_size.bits() as u8);
+        data.extend_from_slice(&self.ip.to_be_bytes());
+        data.push(self.flags.bits());
+
+        let (saved_ip, saved_flags) = self.interrupts.saved.unwrap_or((0, FlagsRegister::new()));
+
+        let mut interrupts = 0;
+        if self.interrupts.enabled {
+            interrupts |= INTERRUPTS_ENABLED;
+        }
+
+        if self.interrupts.in_handler() {
+            interrupts |= INTERRUPTS_IN_HANDLER;
+        }
+
+        data.push(interrupts);
+        data.extend_from_slice(&saved_ip.to_be_bytes());
+        data.push(saved_flags.bits());
+        data.extend_from_slice(&self.sp.to_be_bytes());
+        data.extend_from_slice(&self.cycles.to_be_bytes());
+
+        for reg in self.regs {
+            match self.word_size {
+                WordSize::Bits8 => data.push(reg as u8),
+                WordSize::Bits16 => data.extend_from_slice(&reg.to_be_bytes()),
+            }
+        }
+
+        data.extend_from_slice(&(self.ram.len() as u32).to_be_bytes());
+        data.extend_from_slice(&self.ram);
+
+        data
+    }
+
+    pub fn deserialize(data: &[u8]) -> Result<Self, SnapshotError> {
+        let mut reader = Reader { data };
+
+        if reader.take(MAGIC.len()).ok() != Some(MAGIC.as_slice()) {
+            return Err(SnapshotError::InvalidMagic);
+        }
+
+        let version = reader.byte()?;
+        if version != SNAPSHOT_VERSION {
+            return Err(SnapshotError::UnsupportedVersion(version));
+        }
+
+        let bits = reader.byte()?;
+        let word_size =
+            WordSize::from_bits(bits as u32).ok_or(SnapshotError::UnsupportedWordSize(bits))?;
+
+        let ip = reader.word()?;
+        let flags = FlagsRegister::from_bits(reader.byte()?);
+
+        let mut interrupts = InterruptState::default();
+        let bits = reader.byte()?;
+        let saved_ip = reader.word()?;
+        let saved_flags = FlagsRegister::from_bits(reader.byte()?);
+
+        interrupts.enabled = bits & INTERRUPTS_ENABLED > 0;
+        if bits & INTERRUPTS_IN_HANDLER > 0 {
+            interrupts.saved = Some((saved_ip, saved_flags));
+        }
+
+        let sp = reader.word()?;
+        let cycles = u64::from_be_bytes(reader.take(8)?.try_into().unwrap());
+
+        let mut regs = [0; 16];
+        for reg in &mut regs {
+            *reg = match word_size {
+                WordSize::Bits8 => reader.byte()? as u16,
+                WordSize::Bits16 => reader.word()?,
+            };
+        }
+
+        let ram_size = reader.take(4)?;
+        let ram_size = u32::from_be_bytes(ram_size.try_into().unwrap()) as usize;
+        let ram = reader.take(ram_size)?.to_vec();
+
+        if !reader.data.is_empty() {
+            return Err(SnapshotError::TrailingData);
+        }
+
+        Ok(Snapshot {
+            word_size,
+            ram,
+            regs,
+            ip,
+            sp,
+            flags,
+            interrupts,
+            cycles,
+        })
+    }
+
+    // Changes needed to get from this snapshot to `other`
+    pub fn diff(&self, other: &Snapshot) -> SnapshotDiff {
+        let registers = (0..16)
+            .filter(|index| self.regs[*index] != other.regs[*index])
+            .map(|index| (index as u8, self.regs[index], other.regs[index]))
+            .collect();
+
+        let flags = [Flags::Zero, Flags::Carry, Flags::Sign, Flags::Overflow]
+            .into_iter()
+            .filter_map(|flag| {
+                let before = self.flags.is_set(flag.clone());
+                let after = other.flags.is_set(flag.clone());
+                (before != after).then_some((flag, after))
+            })
+            .collect();
+
+        // bytes beyond the end of the smaller ram count as zero
+        let size = self.ram.len().max(other.ram.len());
+        let byte = |ram: &[u8], address: usize| ram.get(address).copied().unwrap_or(0);
+        let memory = (0..size)
+            .filter(|address| byte(&self.ram, *address) != byte(&other.ram, *address))
+            .map(|address| (address, byte(&self.ram, address), byte(&other.ram, address)))
+            .collect();
+
+        SnapshotDiff {
+            word_size: (self.word_size != other.word_size)
+                .then_some((self.word_size, other.word_size)),
+            ip: (self.ip != other.ip).then_some((self.ip, other.ip)),
+            sp: (self.sp != other.sp).then_some((self.sp, other.sp)),
+            registers,
+            flags,
+            memory,
+            ram_size: (self.ram.len() != other.ram.len())
+                .then_some((self.ram.len(), other.ram.len())),
+            interrupts: (self.interrupts != other.interrupts)
+                .then_some((self.interrupts, other.interrupts)),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct SnapshotDiff {
+    pub word_size: Option<(WordSize, WordSize)>,
+    pub ip: Option<(u16, u16)>,
+    pub sp: Option<(u16, u16)>,
+    pub registers: Vec<(u8, u16, u16)>, // register index, old and new value
+    pub flags: Vec<(Flags, bool)>,      // flag and whether it is set now
+    pub memory: Vec<(usize, u8, u8)>,   // address, old and new value
+    pub ram_size: Option<(usize, usize)>,
+    pub interrupts: Option<(InterruptState, InterruptState)>,
+}
+
+impl SnapshotDiff {
+    pub fn is_empty(&self) -> bool {
+        self.word_size.is_none()
+            && self.ip.is_none()
+            && self.sp.is_none()
+            && self.registers.is_empty()
+            && self.flags.is_empty()
+            && self.memory.is_empty()
+            && self.ram_size.is_none()
+            && self.interrupts.is_none()
+    }
+}
+
+impl Display for SnapshotDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "no changes");
+        }
+
+        if let Some((old, new)) = self.word_size {
+            writeln!(f, "word size: {} -> {}", old, new)?;
+        }
+
+        if let Some((old, new)) = self.ip {
+            writeln!(f, "ip: {:#06x} -> {:#06x}", old, new)?;
+        }
+
+        if let Some((old, new)) = self.sp {
+            writeln!(f, "sp: {:#06x} -> {:#06x}", old, new)?;
+        }
+
+        for (index, old, new) in &self.registers {
+            writeln!(f, "r{}: {:#04x} -> {:#04x}", index, old, new)?;
+        }
+
+        for (flag, set) in &self.flags {
+            writeln!(f, "{}: {}", flag, if *set { "set" } else { "cleared" })?;
+        }
+
+        if let Some((old, new)) = self.interrupts {
+            writeln!(f, "interrupts: {} -> {}", old, new)?;
+        }
+
+        if let Some((old, new)) = self.ram_size {
+            writeln!(f, "ram size: {} -> {}", old, new)?;
+        }
+
+        for (address, old, new) in &self.memory {
+            writeln!(f, "[{:#06x}]: {:#04x} -> {:#04x}", address, old, new)?;
+        }
+
+        Ok(())
+    }
+}
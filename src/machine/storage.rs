@@ -97,6 +97,10 @@ where
     pub fn resize(&mut self, new_ram_size: usize) {
         self.data.resize(new_ram_size, T::default());
     }
+
+    pub fn as_slice(&self) -> &[T] {
+        &self.data
+    }
 }
 
 impl<T> FiniteStorage for RAM<T>
@@ -0,0 +1,138 @@
+use std::fmt::{Debug, Display, LowerHex};
+
+use num_traits::PrimInt;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WordSize {
+    Bits8,
+    Bits16,
+}
+
+impl WordSize {
+    pub fn bits(&self) -> u32 {
+        match self {
+            WordSize::Bits8 => 8,
+            WordSize::Bits16 => 16,
+        }
+    }
+
+    pub fn from_bits(bits: u32) -> Option<Self> {
+        match bits {
+            8 => Some(WordSize::Bits8),
+            16 => Some(WordSize::Bits16),
+            _ => None,
+        }
+    }
+
+    pub fn max_value(&self) -> u16 {
+        match self {
+            WordSize::Bits8 => u8::MAX as u16,
+            WordSize::Bits16 => u16::MAX,
+        }
+    }
+}
+
+impl Display for WordSize {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}-bit", self.bits())
+    }
+}
+
+// Width of the registers, the alu and data accesses. Memory stays byte addressed, words are
+// stored with the most significant byte first.
+pub trait Word: PrimInt + Default + Debug + Display + LowerHex + 'static {
+    const SIZE: WordSize;
+    const BYTES: usize;
+
+    fn from_u8(byte: u8) -> Self;
+    fn from_u16(value: u16) -> Self; // truncates
+    fn as_u16(self) -> u16;
+    fn low_byte(self) -> u8;
+
+    fn overflowing_add(self, other: Self) -> (Self, bool);
+    fn overflowing_sub(self, other: Self) -> (Self, bool);
+    fn overflowing_shl(self, amount: u32) -> (Self, bool);
+    fn overflowing_shr(self, amount: u32) -> (Self, bool);
+
+    // low and high word of the double width product
+    fn widening_mul(self, other: Self) -> (Self, Self);
+    fn widening_mul_signed(self, other: Self) -> (Self, Self);
+
+    // two's complement division, overflowing only for the most negative value divided by -1
+    fn overflowing_div_signed(self, other: Self) -> (Self, bool);
+    fn wrapping_rem_signed(self, other: Self) -> Self;
+
+    fn to_be_bytes(self) -> Vec<u8>;
+    fn from_be_bytes(bytes: &[u8]) -> Self; // `BYTES` bytes
+}
+
+macro_rules! word {
+    ($type:ty, $signed:ty, $wide:ty, $wide_signed:ty, $size:expr) => {
+        impl Word for $type {
+            const SIZE: WordSize = $size;
+            const BYTES: usize = std::mem::size_of::<$type>();
+
+            fn from_u8(byte: u8) -> Self {
+                byte as $type
+            }
+
+            fn from_u16(value: u16) -> Self {
+                value as $type
+            }
+
+            fn as_u16(self) -> u16 {
+                self as u16
+            }
+
+            fn low_byte(self) -> u8 {
+                self as u8
+            }
+
+            fn overflowing_add(self, other: Self) -> (Self, bool) {
+                <$type>::overflowing_add(self, other)
+            }
+
+            fn overflowing_sub(self, other: Self) -> (Self, bool) {
+                <$type>::overflowing_sub(self, other)
+            }
+
+            fn overflowing_shl(self, amount: u32) -> (Self, bool) {
+                <$type>::overflowing_shl(self, amount)
+            }
+
+            fn overflowing_shr(self, amount: u32) -> (Self, bool) {
+                <$type>::overflowing_shr(self, amount)
+            }
+
+            fn widening_mul(self, other: Self) -> (Self, Self) {
+                let product = self as $wide * other as $wide;
+                (product as $type, (product >> <$type>::BITS) as $type)
+            }
+
+            fn widening_mul_signed(self, other: Self) -> (Self, Self) {
+                let product = self as $signed as $wide_signed * other as $signed as $wide_signed;
+                (product as $type, (product >> <$type>::BITS) as $type)
+            }
+
+            fn overflowing_div_signed(self, other: Self) -> (Self, bool) {
+                let (quotient, overflow) = (self as $signed).overflowing_div(other as $signed);
+                (quotient as $type, overflow)
+            }
+
+            fn wrapping_rem_signed(self, other: Self) -> Self {
+                (self as $signed).wrapping_rem(other as $signed) as $type
+            }
+
+            fn to_be_bytes(self) -> Vec<u8> {
+                <$type>::to_be_bytes(self).to_vec()
+            }
+
+            fn from_be_bytes(bytes: &[u8]) -> Self {
+                <$type>::from_be_bytes(bytes.try_into().unwrap())
+            }
+        }
+    };
+}
+
+word!(u8, i8, u16, i16, WordSize::Bits8);
+word!(u16, i16, u32, i32, WordSize::Bits16);
@@ -1,19 +1,54 @@
-use mrt_cpu::cli::Cli;
+use mrt_cpu::{
+    batch::{self, Status},
+    cli::{
+        self,
+        command::{Outcome, Registry},
+        Cli, CliError,
+    },
+    machine::word::{Word, WordSize},
+    repl::{self, LineEditor, Prompt},
+};
 
 use std::{
-    io::{self, stdout, BufRead, Write},
+    collections::VecDeque,
+    io::{stdout, Write},
+    path::PathBuf,
+    process::ExitCode,
     sync::{
         atomic::{AtomicBool, Ordering},
         Arc,
     },
 };
 
-fn main() {
-    println!(
-        "MRT-CPU CLI Utility
+fn main() -> ExitCode {
+    // a subcommand runs without the interactive prompt
+    let args = std::env::args().collect::<Vec<_>>();
+    if let Some(status) = batch::run(&args[1..]) {
+        return status.into();
+    }
+
+    let script = script_argument();
+    let mut input = Input {
+        pending: VecDeque::new(),
+        editor: script.is_none().then(LineEditor::new),
+        repeat: None,
+        failure: None,
+    };
+
+    match script {
+        Some(path) => match cli::read_script(&path) {
+            Ok(commands) => input.pending.extend(commands),
+            Err(_) => {
+                println!("Error: unable to read script {}", path.display());
+                return Status::Usage.into();
+            }
+        },
+        None => println!(
+            "MRT-CPU CLI Utility
     Enter `help' for a list of commands
     "
-    );
+        ),
+    }
 
     let interrupt = Arc::new(AtomicBool::new(false));
 
@@ -28,74 +63,142 @@ fn main() {
         .expect("Error: failed to set interrupt handler");
     }
 
-    let mut cli = Cli::new(interrupt.clone());
+    // switching the word size starts over with a new machine
+    let mut word_size = word_size_argument();
+    while let Some(next) = match word_size {
+        WordSize::Bits8 => repl(Cli::<u8>::new(interrupt.clone()), &mut input),
+        WordSize::Bits16 => repl(Cli::<u16>::new(interrupt.clone()), &mut input),
+    } {
+        word_size = next;
+    }
 
-    loop {
-        print!("% ");
-
-        let _ = io::stdout().flush();
-        let stdin = io::stdin();
-        let mut handle = stdin.lock();
-
-        let mut line = String::new();
-        handle
-            .read_line(&mut line)
-            .expect("Failed to gather input from stdin");
-
-        let len = line.trim_end_matches(&['\r', '\n'][..]).len();
-        line.truncate(len);
-        let command = line.split(' ').collect::<Vec<&str>>();
-
-        // do something with input
-        let result = match command[0] {
-            "help" => {
-                println!(
-                    "Help: main, alias [required] <optional> - description
-    exit, quit - exit application
-    load_rom, lr [rom_file] - load a rom
-    ram_size [ram_size] - set ram size
-    step, s <step_count> - step N amount of instructions
-    continue, c - continue running until Ctrl+C
-    compile, com [file] <out> <--new> - compile assembly file and output to `out'. --new as last (3rd) parameter uses the new compiler
-    regs - print system registers
-    goto [address] - set ip to address
-    disassemble, dis <count|from> <to> - disassemble N instruction at ip or from range
-    write, w [address] [byte] <count> - write byte N times at address in memory
-    read, r [address] <count> - read N bytes from address in memory
-                "
-                );
-                Ok(())
-            }
-            "exit" | "quit" => return,
+    match input.failure {
+        Some(status) => status.into(),
+        None => ExitCode::SUCCESS,
+    }
+}
 
-            "load_rom" | "lr" => cli.load_rom(command),
+// `--script file` executes the commands of the file instead of reading them from stdin
+fn script_argument() -> Option<PathBuf> {
+    let args = std::env::args().collect::<Vec<_>>();
+    let index = args.iter().position(|arg| arg == "--script")?;
 
-            "ram_size" => cli.ram_size(command),
+    match args.get(index + 1) {
+        Some(path) => Some(PathBuf::from(path)),
+        None => {
+            println!("Error: missing script file");
+            std::process::exit(Status::Usage as i32);
+        }
+    }
+}
 
-            "step" | "s" => cli.step(command),
+// Commands of scripts waiting to be executed before reading from stdin
+struct Input {
+    pending: VecDeque<String>,
+    editor: Option<LineEditor>, // None with --script, exits once the commands are executed
+    repeat: Option<String>,     // executed again on an empty line, like `step` in gdb
+    failure: Option<Status>,    // of a failed assertion or command of a --script
+}
 
-            "continue" | "c" => cli.continue_exec(),
+impl Input {
+    // Stops executing scripts, a --script also exits with the status. At the prompt the
+    // failure is only reported, later commands may pass.
+    fn fail(&mut self, status: Status) {
+        if self.editor.is_none() {
+            self.failure = Some(status);
+        }
 
-            "compile" | "com" => cli.compile(command),
+        self.pending.clear();
+    }
 
-            "regs" => cli.print_regs(),
+    // Any command of a --script that fails stops it, the prompt carries on
+    fn command_failed(&mut self) {
+        if self.editor.is_none() {
+            self.fail(Status::Usage);
+        }
+    }
 
-            "goto" => cli.goto(command),
+    // None once the input ended, labels are completed in arguments
+    fn next_line(&mut self, labels: Vec<String>) -> Option<String> {
+        if let Some(line) = self.pending.pop_front() {
+            return Some(line);
+        }
 
-            "disassemble" | "dis" => cli.disassemble(command),
+        match self.editor.as_mut()?.read_line("% ", labels) {
+            Prompt::Line(line) => match &self.repeat {
+                Some(repeat) if line.trim().is_empty() => Some(repeat.clone()),
+                _ => Some(line),
+            },
+            // only an empty line repeats the last command
+            Prompt::Interrupted => {
+                self.repeat = None;
+                Some(String::new())
+            }
+            Prompt::Ended => None,
+        }
+    }
+}
 
-            "read" | "r" => cli.read_memory(command),
+// `--word-size 16` selects the word size of the machine, 8-bit by default
+fn word_size_argument() -> WordSize {
+    let args = std::env::args().collect::<Vec<_>>();
+    let Some(index) = args.iter().position(|arg| arg == "--word-size") else {
+        return WordSize::Bits8;
+    };
+
+    let word_size = args
+        .get(index + 1)
+        .and_then(|bits| bits.parse().ok())
+        .and_then(WordSize::from_bits);
+
+    match word_size {
+        Some(word_size) => word_size,
+        None => {
+            println!("Error: word size is 8 or 16");
+            std::process::exit(1);
+        }
+    }
+}
 
-            "write" | "w" => cli.write_memory(command),
+// Executes commands until exit, returns the word size of the machine to switch to
+fn repl<W: Word>(mut cli: Cli<W>, input: &mut Input) -> Option<WordSize> {
+    let registry = Registry::<W>::new();
+    if let Some(editor) = &mut input.editor {
+        editor.set_commands(registry.signatures());
+    }
 
-            _ => {
-                println!("Unrecognized command");
-                Ok(())
+    loop {
+        let line = input.next_line(cli.labels())?;
+        let arguments = match repl::split_arguments(&line) {
+            Ok(arguments) => arguments,
+            Err(error) => {
+                println!("{:?}", error);
+                input.command_failed();
+                continue;
             }
         };
 
-        if let Err(error) = result {
-            println!("{:?}", error);
+        let command = arguments.iter().map(String::as_str).collect::<Vec<_>>();
+        let Some(&name) = command.first() else {
+            continue;
+        };
+
+        input.repeat = matches!(name, "step" | "s").then(|| line.clone());
+
+        match registry.execute(&mut cli, &command) {
+            Ok(Outcome::Exit) => return None,
+            Ok(Outcome::WordSize(word_size)) => return Some(word_size),
+            Ok(Outcome::Source(commands)) => {
+                for command in commands.into_iter().rev() {
+                    input.pending.push_front(command);
+                }
+            }
+            Ok(_) => {}
+            Err(CliError::AssertionFailed) => input.fail(Status::AssertionFailed),
+            Err(error) => {
+                println!("{:?}", error);
+                input.command_failed();
+            }
         }
     }
 }
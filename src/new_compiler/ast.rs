@@ -0,0 +1,38 @@
+use crate::types::{Directive, LabelSelector, Opcode, PseudoInstruction, Register};
+
+use super::error::Position;
+
+#[derive(Debug, Clone)]
+pub struct Program {
+    pub statements: Vec<Statement>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Statement {
+    pub position: Position,
+    pub kind: StatementKind,
+}
+
+#[derive(Debug, Clone)]
+pub enum StatementKind {
+    Label(String), // `name:`
+    Instruction(Opcode, Vec<Operand>),
+    Pseudo(PseudoInstruction, Vec<Operand>),
+    Directive(Directive, Vec<Operand>),
+}
+
+#[derive(Debug, Clone)]
+pub struct Operand {
+    pub position: Position,
+    pub kind: OperandKind,
+}
+
+#[derive(Debug, Clone)]
+pub enum OperandKind {
+    Register(Register),
+    RegisterPair(Register, Register), // `rh:rl`
+    Number(u16),
+    String(Vec<u8>),              // escape sequences already applied
+    Label(String, LabelSelector), // `name`, `hi(name)` or `lo(name)`
+    Memory(Box<Operand>),         // `[address]`
+}
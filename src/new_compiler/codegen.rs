@@ -0,0 +1,513 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    compiler::instruction::Instruction,
+    machine::word::WordSize,
+    symbols::SymbolTable,
+    types::{
+        Directive, InstructionType, LabelSelector, Opcode, PseudoInstruction, Register,
+        ADDRESS_REGISTERS,
+    },
+};
+
+use super::{
+    ast::{Operand, OperandKind, Program, Statement, StatementKind},
+    error::{CodeGenerationError, CodeGenerationWarning, Position},
+};
+
+// Maximum size of the generated binary, the address space is 16-bit
+const ADDRESS_SPACE: usize = 0x10000;
+
+// Output of a single statement, pseudo-instructions and instructions given an address list the
+// real instructions they expanded to
+#[derive(Debug, Clone)]
+pub struct ListingLine {
+    pub position: Position,
+    pub address: u16,
+    pub bytes: Vec<u8>,
+    pub instructions: Vec<Instruction>,
+    pub expanded: bool, // written as a pseudo-instruction or with an address
+}
+
+// Lays out all statements of a program. The first pass only collects the address of every
+// label, the second pass (resolve = true) uses those to generate the final binary.
+struct Generator<'a> {
+    labels: &'a mut HashMap<String, u16>,
+    resolve: bool,
+    binary: Vec<u8>,
+    word_size: WordSize, // selected by `.wordsize`
+    listing: Vec<ListingLine>,
+}
+
+impl Generator<'_> {
+    fn address(&self) -> u16 {
+        self.binary.len() as u16
+    }
+
+    fn label_address(&self, position: &Position, name: &str) -> Result<u16, CodeGenerationError> {
+        match self.labels.get(name) {
+            Some(address) => Ok(*address),
+            None if self.resolve => Err(CodeGenerationError::UnknownLabel(
+                position.clone(),
+                name.to_string(),
+            )),
+            // not yet defined, the address does not influence the size of the output
+            None => Ok(0),
+        }
+    }
+
+    fn register(operand: &Operand) -> Result<Register, CodeGenerationError> {
+        match operand.kind {
+            OperandKind::Register(register) => Ok(register),
+            _ => Err(CodeGenerationError::ExpectedRegister(
+                operand.position.clone(),
+            )),
+        }
+    }
+
+    // Value of a number or (part of a) label address that has to be at most `maximum`
+    fn value(&self, operand: &Operand, maximum: u16) -> Result<u16, CodeGenerationError> {
+        let position = &operand.position;
+        let value = match &operand.kind {
+            OperandKind::Number(value) => *value,
+            OperandKind::Label(name, selector) => {
+                let address = self.label_address(position, name)?;
+                match selector {
+                    LabelSelector::High => address >> 8,
+                    LabelSelector::Low => address & 0xff,
+                    LabelSelector::Address if address > maximum => {
+                        return Err(CodeGenerationError::LabelOutOfRange(
+                            position.clone(),
+                            name.clone(),
+                            address,
+                        ))
+                    }
+                    LabelSelector::Address => address,
+                }
+            }
+            _ => return Err(CodeGenerationError::ExpectedValue(position.clone())),
+        };
+
+        if value > maximum {
+            return Err(CodeGenerationError::ValueOutOfRange(
+                position.clone(),
+                value,
+                maximum,
+            ));
+        }
+
+        Ok(value)
+    }
+
+    fn byte(&self, operand: &Operand) -> Result<u8, CodeGenerationError> {
+        Ok(self.value(operand, u8::MAX as u16)? as u8)
+    }
+
+    fn instruction(
+        &self,
+        position: &Position,
+        opcode: Opcode,
+        operands: &[Operand],
+    ) -> Result<Instruction, CodeGenerationError> {
+        let itype = Instruction::get_type(opcode);
+        let expected = match itype {
+            InstructionType::NoParam => 0,
+            InstructionType::SingleReg => 1,
+            InstructionType::RegImm | InstructionType::DoubleReg => 2,
+            InstructionType::DoubleRegImm4 | InstructionType::TripleReg => 3,
+        };
+
+        if operands.len() != expected {
+            return Err(CodeGenerationError::OperandCount(
+                position.clone(),
+                expected,
+                operands.len(),
+            ));
+        }
+
+        let reg = |index: usize| Self::register(&operands[index]);
+
+        Ok(match itype {
+            InstructionType::NoParam => Instruction::NoParam(opcode),
+            InstructionType::SingleReg => Instruction::SingleReg(opcode, reg(0)?),
+            InstructionType::RegImm => {
+                Instruction::RegImm(opcode, reg(0)?, self.byte(&operands[1])?)
+            }
+            InstructionType::DoubleReg => Instruction::DoubleReg(opcode, reg(0)?, reg(1)?),
+            InstructionType::DoubleRegImm4 => Instruction::DoubleRegImm4(
+                opcode,
+                reg(0)?,
+                reg(1)?,
+                self.value(&operands[2], 0b1111)? as u8,
+            ),
+            InstructionType::TripleReg => Instruction::TripleReg(opcode, reg(0)?, reg(1)?, reg(2)?),
+        })
+    }
+
+    // Instructions a statement expands to. Branches, CALL, LB and SB given an address instead of
+    // a register pair load it into the address registers first.
+    fn instructions(
+        &self,
+        position: &Position,
+        opcode: Opcode,
+        operands: &[Operand],
+    ) -> Result<Vec<Instruction>, CodeGenerationError> {
+        let (register, address) = match operands {
+            [address] if opcode.is_jump() && !matches!(address.kind, OperandKind::Register(_)) => {
+                (None, address)
+            }
+            [register, Operand {
+                kind: OperandKind::Memory(address),
+                ..
+            }] if matches!(opcode, Opcode::LB | Opcode::SB) => {
+                (Some(Self::register(register)?), address.as_ref())
+            }
+            _ => return Ok(vec![self.instruction(position, opcode, operands)?]),
+        };
+
+        let (high, low) = ADDRESS_REGISTERS;
+        if let Some(register) = register
+            .filter(|register| opcode == Opcode::SB && (*register == high || *register == low))
+        {
+            return Err(CodeGenerationError::ClobberedRegister(
+                operands[0].position.clone(),
+                register,
+            ));
+        }
+
+        let address = self.value(address, u16::MAX)?;
+        Ok(vec![
+            Instruction::RegImm(Opcode::LDI, high, (address >> 8) as u8),
+            Instruction::RegImm(Opcode::LDI, low, address as u8),
+            match register {
+                Some(register) => Instruction::TripleReg(opcode, register, high, low),
+                None => Instruction::DoubleReg(opcode, high, low),
+            },
+        ])
+    }
+
+    fn pseudo(
+        &self,
+        position: &Position,
+        pseudo: PseudoInstruction,
+        operands: &[Operand],
+    ) -> Result<Vec<Instruction>, CodeGenerationError> {
+        if operands.len() != 2 {
+            return Err(CodeGenerationError::OperandCount(
+                position.clone(),
+                2,
+                operands.len(),
+            ));
+        }
+
+        Ok(match pseudo {
+            PseudoInstruction::Ldi16 => {
+                let OperandKind::RegisterPair(high, low) = operands[0].kind else {
+                    return Err(CodeGenerationError::ExpectedRegisterPair(
+                        operands[0].position.clone(),
+                    ));
+                };
+
+                let value = self.value(&operands[1], u16::MAX)?;
+                vec![
+                    Instruction::RegImm(Opcode::LDI, high, (value >> 8) as u8),
+                    Instruction::RegImm(Opcode::LDI, low, value as u8),
+                ]
+            }
+            PseudoInstruction::Mov => {
+                let source = Self::register(&operands[1])?;
+                vec![Instruction::TripleReg(
+                    Opcode::OR,
+                    Self::register(&operands[0])?,
+                    source,
+                    source,
+                )]
+            }
+        })
+    }
+
+    fn directive(
+        &mut self,
+        position: &Position,
+        directive: Directive,
+        operands: &[Operand],
+    ) -> Result<(), CodeGenerationError> {
+        let invalid =
+            || CodeGenerationError::InvalidDirectiveArguments(position.clone(), directive);
+
+        // numeric arguments that determine the layout may not depend on labels
+        let number = |operand: Option<&Operand>| match operand {
+            Some(Operand {
+                kind: OperandKind::Number(value),
+                ..
+            }) => Ok(*value),
+            _ => Err(invalid()),
+        };
+
+        match directive {
+            Directive::Org => {
+                let address = number(operands.first())?;
+                if operands.len() != 1 {
+                    return Err(invalid());
+                }
+
+                if (address as usize) < self.binary.len() {
+                    return Err(CodeGenerationError::InvalidOrigin(
+                        position.clone(),
+                        address,
+                    ));
+                }
+
+                self.binary.resize(address as usize, 0);
+            }
+            Directive::Db => {
+                if operands.is_empty() {
+                    return Err(invalid());
+                }
+
+                for operand in operands {
+                    match &operand.kind {
+                        OperandKind::String(bytes) => self.binary.extend_from_slice(bytes),
+                        _ => {
+                            let byte = self.byte(operand)?;
+                            self.binary.push(byte);
+                        }
+                    }
+                }
+            }
+            Directive::Dw => {
+                if operands.is_empty() {
+                    return Err(invalid());
+                }
+
+                for operand in operands {
+                    let word = self.value(operand, u16::MAX)?;
+                    self.binary.extend_from_slice(&word.to_be_bytes());
+                }
+            }
+            Directive::Ascii | Directive::Asciiz => {
+                if operands.is_empty() {
+                    return Err(invalid());
+                }
+
+                for operand in operands {
+                    let OperandKind::String(bytes) = &operand.kind else {
+                        return Err(invalid());
+                    };
+
+                    self.binary.extend_from_slice(bytes);
+                    if directive == Directive::Asciiz {
+                        self.binary.push(0);
+                    }
+                }
+            }
+            Directive::Fill => {
+                let count = number(operands.first())?;
+                let value = match operands.get(1) {
+                    Some(operand) => self.byte(operand)?,
+                    None => 0,
+                };
+
+                if operands.len() > 2 {
+                    return Err(invalid());
+                }
+
+                self.binary
+                    .resize(self.binary.len() + count as usize, value);
+            }
+            Directive::Align => {
+                let alignment = number(operands.first())?;
+                if operands.len() != 1 || alignment == 0 {
+                    return Err(invalid());
+                }
+
+                let aligned = self.binary.len().next_multiple_of(alignment as usize);
+                self.binary.resize(aligned, 0);
+            }
+            Directive::WordSize => {
+                let bits = number(operands.first())?;
+                if operands.len() != 1 {
+                    return Err(invalid());
+                }
+
+                self.word_size = WordSize::from_bits(bits as u32).ok_or_else(invalid)?;
+            }
+            Directive::Word => {
+                if operands.is_empty() {
+                    return Err(invalid());
+                }
+
+                for operand in operands {
+                    let word = self.value(operand, self.word_size.max_value())?;
+                    match self.word_size {
+                        WordSize::Bits8 => self.binary.push(word as u8),
+                        WordSize::Bits16 => self.binary.extend_from_slice(&word.to_be_bytes()),
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn statement(&mut self, statement: &Statement) -> Result<(), CodeGenerationError> {
+        let position = &statement.position;
+        let address = self.binary.len();
+        let mut instructions = vec![];
+        let mut expanded = false;
+
+        match &statement.kind {
+            StatementKind::Label(name) => {
+                if !self.resolve && self.labels.insert(name.clone(), self.address()).is_some() {
+                    return Err(CodeGenerationError::DuplicateLabel(
+                        position.clone(),
+                        name.clone(),
+                    ));
+                }
+            }
+            StatementKind::Instruction(opcode, operands) => {
+                instructions = self.instructions(position, *opcode, operands)?;
+                expanded = instructions.len() > 1;
+            }
+            StatementKind::Pseudo(pseudo, operands) => {
+                instructions = self.pseudo(position, *pseudo, operands)?;
+                expanded = true;
+            }
+            StatementKind::Directive(directive, operands) => {
+                self.directive(position, *directive, operands)?
+            }
+        }
+
+        for instruction in &instructions {
+            self.binary.extend(instruction.serialize());
+        }
+
+        if self.binary.len() > ADDRESS_SPACE {
+            return Err(CodeGenerationError::ProgramTooLarge(
+                position.clone(),
+                self.binary.len(),
+            ));
+        }
+
+        if self.resolve {
+            self.listing.push(ListingLine {
+                position: position.clone(),
+                address: address as u16,
+                bytes: self.binary[address.min(self.binary.len())..].to_vec(),
+                instructions,
+                expanded,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+fn pass<'a>(
+    program: &Program,
+    labels: &'a mut HashMap<String, u16>,
+    resolve: bool,
+) -> (Generator<'a>, Vec<CodeGenerationError>) {
+    let mut generator = Generator {
+        labels,
+        resolve,
+        binary: vec![],
+        word_size: WordSize::Bits8,
+        listing: vec![],
+    };
+
+    let mut errors = vec![];
+    for statement in &program.statements {
+        if let Err(error) = generator.statement(statement) {
+            let too_large = matches!(error, CodeGenerationError::ProgramTooLarge(..));
+            errors.push(error);
+
+            if too_large {
+                break;
+            }
+        }
+    }
+
+    (generator, errors)
+}
+
+pub fn generate(program: &Program) -> Result<Vec<u8>, CodeGenerationError> {
+    let (binary, mut errors) = generate_recovering(program);
+    if errors.is_empty() {
+        Ok(binary)
+    } else {
+        Err(errors.remove(0))
+    }
+}
+
+// Generates the program, a statement containing an error is skipped so all errors are collected
+pub fn generate_recovering(program: &Program) -> (Vec<u8>, Vec<CodeGenerationError>) {
+    let mut labels = HashMap::new();
+
+    // only duplicate labels are unique to the first pass, the layout is the same in both passes
+    let (_, first_pass_errors) = pass(program, &mut labels, false);
+    let mut errors = first_pass_errors
+        .into_iter()
+        .filter(|error| matches!(error, CodeGenerationError::DuplicateLabel(..)))
+        .collect::<Vec<_>>();
+
+    let (generator, second_pass_errors) = pass(program, &mut labels, true);
+    errors.extend(second_pass_errors);
+    errors.sort_by_key(|error| error.position().get_line_info());
+
+    (generator.binary, errors)
+}
+
+// Output of every statement of the program, for a listing next to the source
+pub fn listing(program: &Program) -> Result<Vec<ListingLine>, CodeGenerationError> {
+    let mut labels = HashMap::new();
+    _ = pass(program, &mut labels, false);
+
+    let (generator, mut errors) = pass(program, &mut labels, true);
+    if errors.is_empty() {
+        Ok(generator.listing)
+    } else {
+        Err(errors.remove(0))
+    }
+}
+
+// Label addresses of the program, only meaningful if it generates without errors
+pub fn symbols(program: &Program) -> SymbolTable {
+    let mut labels = HashMap::new();
+    _ = pass(program, &mut labels, false);
+
+    SymbolTable::from(labels)
+}
+
+// Reports labels that are defined but never referenced
+pub fn warnings(program: &Program) -> Vec<CodeGenerationWarning> {
+    let used = program
+        .statements
+        .iter()
+        .flat_map(|statement| match &statement.kind {
+            StatementKind::Instruction(_, operands)
+            | StatementKind::Pseudo(_, operands)
+            | StatementKind::Directive(_, operands) => operands.as_slice(),
+            StatementKind::Label(_) => &[],
+        })
+        .map(|operand| match &operand.kind {
+            OperandKind::Memory(address) => address.as_ref(),
+            _ => operand,
+        })
+        .filter_map(|operand| match &operand.kind {
+            OperandKind::Label(name, _) => Some(name.as_str()),
+            _ => None,
+        })
+        .collect::<HashSet<_>>();
+
+    program
+        .statements
+        .iter()
+        .filter_map(|statement| match &statement.kind {
+            StatementKind::Label(name) if !used.contains(name.as_str()) => Some(
+                CodeGenerationWarning::UnusedLabel(statement.position.clone(), name.clone()),
+            ),
+            _ => None,
+        })
+        .collect()
+}
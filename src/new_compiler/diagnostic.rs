@@ -0,0 +1,376 @@
+use std::fmt::{Display, Write};
+
+use super::error::{
+    CodeGenerationError, CodeGenerationWarning, CompilationError, ParseError, Position,
+    TokenTypeConversionError, TokenizationError,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        })
+    }
+}
+
+// A single error or warning, optionally pointing at a position in the source
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub code: &'static str,
+    pub message: String,
+    pub position: Option<Position>,
+    pub help: Option<String>,
+}
+
+impl Diagnostic {
+    pub fn error(code: &'static str, message: String, position: Option<&Position>) -> Self {
+        Diagnostic {
+            severity: Severity::Error,
+            code,
+            message,
+            position: position.cloned(),
+            help: None,
+        }
+    }
+
+    pub fn warning(code: &'static str, message: String, position: Option<&Position>) -> Self {
+        Diagnostic {
+            severity: Severity::Warning,
+            ..Diagnostic::error(code, message, position)
+        }
+    }
+
+    pub fn with_help(mut self, help: &str) -> Self {
+        self.help = Some(help.to_string());
+        self
+    }
+
+    // Writes the diagnostic, including the offending source line if it is available
+    fn render(&self, f: &mut impl Write, source_lines: &[Vec<char>]) -> std::fmt::Result {
+        writeln!(f, "{}[{}]: {}", self.severity, self.code, self.message)?;
+
+        let Some(position) = &self.position else {
+            if let Some(help) = &self.help {
+                writeln!(f, "  = help: {}", help)?;
+            }
+
+            return Ok(());
+        };
+
+        let (line_number, column) = position.get_line_info();
+        let line_label = (line_number + 1).to_string();
+        let gutter = " ".repeat(line_label.len());
+
+        writeln!(
+            f,
+            "{}--> {}:{}:{}",
+            gutter,
+            position.path().display(),
+            line_number + 1,
+            column
+        )?;
+
+        if let Some(line) = source_lines.get(line_number) {
+            let start = column.saturating_sub(1).min(line.len());
+
+            // keep tabs so the underline lines up with the source line
+            let indent = line[..start]
+                .iter()
+                .map(|c| if *c == '\t' { '\t' } else { ' ' })
+                .collect::<String>();
+
+            writeln!(f, "{} |", gutter)?;
+            writeln!(f, "{} | {}", line_label, String::from_iter(line))?;
+            writeln!(
+                f,
+                "{} | {}{}",
+                gutter,
+                indent,
+                "^".repeat(span_length(line, start))
+            )?;
+        }
+
+        if let Some(help) = &self.help {
+            writeln!(f, "{} = help: {}", gutter, help)?;
+        }
+
+        Ok(())
+    }
+}
+
+// Length of the token starting at `start`, used to underline the offending source
+fn span_length(line: &[char], start: usize) -> usize {
+    let rest = &line[start..];
+
+    let length = match rest.first() {
+        Some('"') => match rest.iter().skip(1).position(|c| *c == '"') {
+            Some(end) => end + 2,
+            None => rest.len(),
+        },
+        Some(c) if c.is_ascii_alphanumeric() || *c == '.' => {
+            let word = rest
+                .iter()
+                .take_while(|c| c.is_ascii_alphanumeric() || **c == '.')
+                .count();
+
+            // include the label of a selector such as `hi(label)`
+            match (rest.get(word), rest.iter().position(|c| *c == ')')) {
+                (Some('('), Some(end)) => end + 1,
+                _ => word,
+            }
+        }
+        _ => 1,
+    };
+
+    length.max(1)
+}
+
+// All diagnostics of a single compilation together with the source they refer to
+#[derive(Debug, Clone)]
+pub struct Diagnostics {
+    source_lines: Vec<Vec<char>>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Diagnostics {
+    pub fn new(source: &[u8]) -> Self {
+        let source_lines = source
+            .split(|byte| *byte == b'\n')
+            .map(|line| {
+                let line = line.strip_suffix(b"\r").unwrap_or(line);
+                line.iter().map(|byte| *byte as char).collect()
+            })
+            .collect();
+
+        Diagnostics {
+            source_lines,
+            diagnostics: vec![],
+        }
+    }
+
+    pub fn push(&mut self, diagnostic: Diagnostic) {
+        self.diagnostics.push(diagnostic);
+    }
+
+    pub fn extend<T>(&mut self, diagnostics: impl IntoIterator<Item = T>)
+    where
+        Diagnostic: From<T>,
+    {
+        self.diagnostics
+            .extend(diagnostics.into_iter().map(Diagnostic::from));
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.diagnostics.iter()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.diagnostics.is_empty()
+    }
+
+    pub fn count(&self, severity: Severity) -> usize {
+        self.iter()
+            .filter(|diagnostic| diagnostic.severity == severity)
+            .count()
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.count(Severity::Error) > 0
+    }
+}
+
+impl Display for Diagnostics {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for diagnostic in &self.diagnostics {
+            diagnostic.render(f, &self.source_lines)?;
+            writeln!(f)?;
+        }
+
+        write!(
+            f,
+            "{} error(s), {} warning(s) generated",
+            self.count(Severity::Error),
+            self.count(Severity::Warning)
+        )
+    }
+}
+
+impl From<TokenizationError> for Diagnostic {
+    fn from(error: TokenizationError) -> Self {
+        match error {
+            TokenizationError::TokenTypeConversion(position, conversion_error) => {
+                match conversion_error {
+                    TokenTypeConversionError::UnknownCharacter(c) => Diagnostic::error(
+                        "E0101",
+                        format!("unknown character `{}`", c.escape_default()),
+                        Some(&position),
+                    )
+                    .with_help("labels and instructions may only contain letters and digits"),
+                    TokenTypeConversionError::IncompatibleTypes(received, current) => {
+                        Diagnostic::error(
+                            "E0102",
+                            format!("unexpected {:?} in {:?}", received, current),
+                            Some(&position),
+                        )
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl From<ParseError> for Diagnostic {
+    fn from(error: ParseError) -> Self {
+        let position = Some(error.position());
+
+        match &error {
+            ParseError::UnexpectedToken(_, text) => {
+                Diagnostic::error("E0201", format!("unexpected `{}`", text), position)
+                    .with_help("expected an operand or the end of the line")
+            }
+            ParseError::UnexpectedEndOfLine(_) => {
+                Diagnostic::error("E0202", "unexpected end of line".to_string(), position)
+            }
+            ParseError::UnknownInstruction(_, name) => {
+                Diagnostic::error("E0203", format!("unknown instruction `{}`", name), position)
+                    .with_help("label definitions end with a `:`")
+            }
+            ParseError::UnknownDirective(_, name) => {
+                Diagnostic::error("E0204", format!("unknown directive `{}`", name), position)
+                    .with_help("supported are .org, .db, .dw, .ascii, .asciiz, .fill and .align")
+            }
+            ParseError::UnknownSelector(_, name) => {
+                Diagnostic::error("E0205", format!("unknown selector `{}`", name), position)
+                    .with_help("use `hi(label)` or `lo(label)` to select a byte of the address")
+            }
+            ParseError::InvalidNumber(_, text) => {
+                Diagnostic::error("E0206", format!("invalid number `{}`", text), position)
+                    .with_help("numbers are 16-bit at most, hexadecimal numbers start with `0x`")
+            }
+            ParseError::InvalidEscapeSequence(_, c) => Diagnostic::error(
+                "E0207",
+                format!("invalid escape sequence `\\{}`", c),
+                position,
+            )
+            .with_help("supported are \\n, \\r, \\t, \\0, \\\\ and \\\""),
+            ParseError::UnterminatedString(_) => {
+                Diagnostic::error("E0208", "unterminated string".to_string(), position)
+                    .with_help("strings end with a `\"` on the same line")
+            }
+        }
+    }
+}
+
+impl From<CodeGenerationError> for Diagnostic {
+    fn from(error: CodeGenerationError) -> Self {
+        let position = Some(error.position());
+
+        match &error {
+            CodeGenerationError::OperandCount(_, expected, received) => Diagnostic::error(
+                "E0301",
+                format!("expected {} operand(s), found {}", expected, received),
+                position,
+            ),
+            CodeGenerationError::ExpectedRegister(_) => {
+                Diagnostic::error("E0302", "expected a register".to_string(), position)
+                    .with_help("registers are named r0 up to r15")
+            }
+            CodeGenerationError::ExpectedValue(_) => {
+                Diagnostic::error("E0303", "expected a number or label".to_string(), position)
+            }
+            CodeGenerationError::ValueOutOfRange(_, value, maximum) => Diagnostic::error(
+                "E0304",
+                format!("value {} does not fit, the maximum is {}", value, maximum),
+                position,
+            ),
+            CodeGenerationError::UnknownLabel(_, name) => {
+                Diagnostic::error("E0305", format!("unknown label `{}`", name), position)
+                    .with_help("define the label with `name:`")
+            }
+            CodeGenerationError::DuplicateLabel(_, name) => Diagnostic::error(
+                "E0306",
+                format!("label `{}` is already defined", name),
+                position,
+            ),
+            CodeGenerationError::LabelOutOfRange(_, name, address) => Diagnostic::error(
+                "E0307",
+                format!("address {:#06x} of label `{}` does not fit", address, name),
+                position,
+            )
+            .with_help("use `hi(label)` and `lo(label)` to load the address in two parts"),
+            CodeGenerationError::InvalidDirectiveArguments(_, directive) => Diagnostic::error(
+                "E0308",
+                format!("invalid arguments for {:?} directive", directive),
+                position,
+            )
+            .with_help("arguments that determine the layout have to be numbers"),
+            CodeGenerationError::InvalidOrigin(_, address) => Diagnostic::error(
+                "E0309",
+                format!("origin {:#06x} lies before the current address", address),
+                position,
+            )
+            .with_help("the output can only be moved forward"),
+            CodeGenerationError::ProgramTooLarge(_, size) => Diagnostic::error(
+                "E0310",
+                format!("program of {} bytes exceeds the address space", size),
+                position,
+            ),
+            CodeGenerationError::ExpectedRegisterPair(_) => {
+                Diagnostic::error("E0311", "expected a register pair".to_string(), position)
+                    .with_help("register pairs are written as `rh:rl`, e.g. `r4:r5`")
+            }
+            CodeGenerationError::ClobberedRegister(_, register) => Diagnostic::error(
+                "E0312",
+                format!(
+                    "{:?} is overwritten with the address before it is used",
+                    register
+                ),
+                position,
+            )
+            .with_help("r14 and r15 hold the address of instructions given a label or `[address]`"),
+        }
+    }
+}
+
+impl From<CodeGenerationWarning> for Diagnostic {
+    fn from(warning: CodeGenerationWarning) -> Self {
+        match warning {
+            CodeGenerationWarning::UnusedLabel(position, name) => Diagnostic::warning(
+                "W0301",
+                format!("label `{}` is never used", name),
+                Some(&position),
+            ),
+        }
+    }
+}
+
+impl Display for CompilationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let diagnostic = match self {
+            CompilationError::Rejected(diagnostics) => return write!(f, "{}", diagnostics),
+            CompilationError::UnableToReadFromInputFile(path) => Diagnostic::error(
+                "E0001",
+                format!("unable to read from `{}`", path.to_string_lossy()),
+                None,
+            ),
+            CompilationError::UnableToWriteToOutputFile(path) => Diagnostic::error(
+                "E0002",
+                format!("unable to write to `{}`", path.to_string_lossy()),
+                None,
+            ),
+            CompilationError::Tokenizer(error) => Diagnostic::from(error.clone()),
+            CompilationError::Parser(error) => Diagnostic::from(error.clone()),
+            CompilationError::CodeGeneration(error) => Diagnostic::from(error.clone()),
+        };
+
+        diagnostic.render(f, &[])
+    }
+}
@@ -1,6 +1,9 @@
 use std::{ffi::OsString, path::Path, rc::Rc};
 
-use crate::new_compiler::token::TokenType;
+use crate::{
+    new_compiler::{diagnostic::Diagnostics, token::TokenType},
+    types::{Directive, Register},
+};
 
 // Used to describe the accurate position of the compiler in case of an error
 #[derive(Debug, Clone, PartialEq)]
@@ -25,6 +28,10 @@ impl Position {
         return (self.line_number, self.line_offset);
     }
 
+    pub fn path(&self) -> &Path {
+        Path::new(self.path.as_os_str())
+    }
+
     pub fn next_line(&mut self) {
         self.line_number += 1;
         self.line_offset = 0;
@@ -42,15 +49,85 @@ pub enum CompilationError {
     UnableToReadFromInputFile(OsString),
     UnableToWriteToOutputFile(OsString),
     Tokenizer(TokenizationError),
+    Parser(ParseError),
+    CodeGeneration(CodeGenerationError),
+    Rejected(Diagnostics), // one or more errors, see the diagnostics
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum TokenizationError {
     TokenTypeConversion(Position, TokenTypeConversionError),
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum TokenTypeConversionError {
     IncompatibleTypes(TokenType, TokenType), // received type, current type
     UnknownCharacter(char),
 }
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParseError {
+    UnexpectedToken(Position, String),
+    UnexpectedEndOfLine(Position),
+    UnknownInstruction(Position, String),
+    UnknownDirective(Position, String),
+    UnknownSelector(Position, String),
+    InvalidNumber(Position, String),
+    InvalidEscapeSequence(Position, char),
+    UnterminatedString(Position),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CodeGenerationError {
+    OperandCount(Position, usize, usize), // expected count, received count
+    ExpectedRegister(Position),
+    ExpectedValue(Position),
+    ValueOutOfRange(Position, u16, u16), // value, maximum
+    UnknownLabel(Position, String),
+    DuplicateLabel(Position, String),
+    LabelOutOfRange(Position, String, u16),
+    InvalidDirectiveArguments(Position, Directive),
+    InvalidOrigin(Position, u16),
+    ProgramTooLarge(Position, usize),
+    ExpectedRegisterPair(Position),
+    ClobberedRegister(Position, Register), // overwritten by the expansion before it is used
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CodeGenerationWarning {
+    UnusedLabel(Position, String),
+}
+
+impl ParseError {
+    pub fn position(&self) -> &Position {
+        match self {
+            Self::UnexpectedToken(position, _)
+            | Self::UnexpectedEndOfLine(position)
+            | Self::UnknownInstruction(position, _)
+            | Self::UnknownDirective(position, _)
+            | Self::UnknownSelector(position, _)
+            | Self::InvalidNumber(position, _)
+            | Self::InvalidEscapeSequence(position, _)
+            | Self::UnterminatedString(position) => position,
+        }
+    }
+}
+
+impl CodeGenerationError {
+    pub fn position(&self) -> &Position {
+        match self {
+            Self::OperandCount(position, ..)
+            | Self::ExpectedRegister(position)
+            | Self::ExpectedValue(position)
+            | Self::ValueOutOfRange(position, ..)
+            | Self::UnknownLabel(position, _)
+            | Self::DuplicateLabel(position, _)
+            | Self::LabelOutOfRange(position, ..)
+            | Self::InvalidDirectiveArguments(position, _)
+            | Self::InvalidOrigin(position, _)
+            | Self::ProgramTooLarge(position, _)
+            | Self::ExpectedRegisterPair(position)
+            | Self::ClobberedRegister(position, _) => position,
+        }
+    }
+}
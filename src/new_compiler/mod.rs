@@ -1,30 +1,149 @@
+pub mod ast;
+pub mod codegen;
+pub mod diagnostic;
 pub mod error;
+pub mod parser;
 pub mod token;
 
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
+use crate::symbols::SymbolTable;
+use diagnostic::Diagnostics;
 use error::CompilationError;
 
 pub fn compile(input_stream: &[u8], file_path: &Path) -> Result<Box<Vec<u8>>, CompilationError> {
-    let output_stream = Box::<Vec<u8>>::default();
-
     // lexical analysis
     let tokens = token::tokenize(input_stream, file_path);
     if let Err(error) = tokens {
         return Err(CompilationError::Tokenizer(error));
     }
 
-    let tokens = *tokens.unwrap();
-    for token in tokens {
-        println!("Collected token: {:?}", token);
+    // parsing
+    let program = parser::parse(*tokens.unwrap());
+    if let Err(error) = program {
+        return Err(CompilationError::Parser(error));
+    }
+
+    // code generation
+    let output = codegen::generate(&program.unwrap());
+    if let Err(error) = output {
+        return Err(CompilationError::CodeGeneration(error));
+    }
+
+    Ok(Box::new(output.unwrap()))
+}
+
+// Compiles the input into a listing of the address and bytes of every source line. Expanded
+// instructions are followed by the real instructions they expanded to.
+pub fn listing(input_stream: &[u8], file_path: &Path) -> Result<String, CompilationError> {
+    let tokens = token::tokenize(input_stream, file_path).map_err(CompilationError::Tokenizer)?;
+    let program = parser::parse(*tokens).map_err(CompilationError::Parser)?;
+    let lines = codegen::listing(&program).map_err(CompilationError::CodeGeneration)?;
+
+    let source = String::from_utf8_lossy(input_stream);
+    let source = source.lines().collect::<Vec<_>>();
+    let mut output = String::new();
+    let mut row = |address: usize, bytes: &[u8], text: &str| {
+        let bytes = bytes
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect::<Vec<_>>()
+            .join(" ");
+        output.push_str(format!("{:04x}  {:<11}  {}", address, bytes, text).trim_end());
+        output.push('\n');
+    };
+
+    // several statements can share a line, e.g. `loop: ADD r0 r0 r1`, the source is shown once
+    let mut previous_line = None;
+    let mut lines = lines.into_iter().peekable();
+    while let Some(line) = lines.next() {
+        let (line_number, _) = line.position.get_line_info();
+        let shared = lines
+            .peek()
+            .is_some_and(|next| next.position.get_line_info().0 == line_number);
+        if shared && line.bytes.is_empty() && !line.expanded {
+            continue;
+        }
+
+        let text = if previous_line.replace(line_number) == Some(line_number) {
+            ""
+        } else {
+            source.get(line_number).map_or("", |text| text.trim())
+        };
+
+        let address = line.address as usize;
+        if line.expanded {
+            row(address, &[], text);
+
+            let mut address = address;
+            for instruction in &line.instructions {
+                let bytes = instruction.serialize();
+                row(address, &bytes, &format!("    {}", instruction));
+                address += bytes.len();
+            }
+
+            continue;
+        }
+
+        // long directives continue on the following rows
+        let mut chunks = line.bytes.chunks(4);
+        row(address, chunks.next().unwrap_or(&[]), text);
+        for (index, chunk) in chunks.enumerate() {
+            row(address + (index + 1) * 4, chunk, "");
+        }
+    }
+
+    Ok(output)
+}
+
+// Compiles the input while collecting all errors and warnings. The output is only available
+// if no errors were found.
+pub fn compile_with_diagnostics(
+    input_stream: &[u8],
+    file_path: &Path,
+) -> (Option<Vec<u8>>, Diagnostics) {
+    let (output, diagnostics) = compile_program(input_stream, file_path);
+    (output.map(|(output, _)| output), diagnostics)
+}
+
+fn compile_program(
+    input_stream: &[u8],
+    file_path: &Path,
+) -> (Option<(Vec<u8>, SymbolTable)>, Diagnostics) {
+    let mut diagnostics = Diagnostics::new(input_stream);
+
+    // lexical analysis
+    let (tokens, errors) = token::tokenize_recovering(input_stream, file_path);
+    if !errors.is_empty() {
+        diagnostics.extend(errors);
+        return (None, diagnostics);
     }
 
     // parsing
+    let (program, errors) = parser::Parser::new(tokens).parse_recovering();
+    if !errors.is_empty() {
+        diagnostics.extend(errors);
+        return (None, diagnostics);
+    }
+
+    // code generation
+    let (output, errors) = codegen::generate_recovering(&program);
+    diagnostics.extend(errors);
+    diagnostics.extend(codegen::warnings(&program));
 
-    return Ok(output_stream);
+    if diagnostics.has_errors() {
+        return (None, diagnostics);
+    }
+
+    (Some((output, codegen::symbols(&program))), diagnostics)
 }
 
-pub fn compile_file(input_path: &Path, output_path: &Path) -> Result<(), CompilationError> {
+// Compiles a file and writes the symbol table next to it, on success the returned diagnostics
+// only contain warnings
+pub fn compile_file(
+    input_path: &Path,
+    output_path: &Path,
+) -> Result<Diagnostics, CompilationError> {
     let input_bytes = std::fs::read(input_path);
     if input_bytes.is_err() {
         return Err(CompilationError::UnableToReadFromInputFile(
@@ -33,7 +152,10 @@ pub fn compile_file(input_path: &Path, output_path: &Path) -> Result<(), Compila
     }
 
     let bytes = input_bytes.unwrap();
-    let output_bytes = *compile(&bytes, input_path)?;
+    let (output, diagnostics) = compile_program(&bytes, input_path);
+    let Some((output_bytes, symbols)) = output else {
+        return Err(CompilationError::Rejected(diagnostics));
+    };
 
     let output_result = std::fs::write(output_path, output_bytes);
     if output_result.is_err() {
@@ -42,5 +164,36 @@ pub fn compile_file(input_path: &Path, output_path: &Path) -> Result<(), Compila
         ));
     }
 
-    return Ok(());
+    let symbols_path = SymbolTable::path_for_rom(output_path);
+    if symbols.write(&symbols_path).is_err() {
+        return Err(CompilationError::UnableToWriteToOutputFile(
+            symbols_path.into_os_string(),
+        ));
+    }
+
+    Ok(diagnostics)
+}
+
+// Writes the listing of a file, see `listing`
+pub fn listing_file(input_path: &Path, listing_path: &Path) -> Result<(), CompilationError> {
+    let Ok(bytes) = std::fs::read(input_path) else {
+        return Err(CompilationError::UnableToReadFromInputFile(
+            input_path.as_os_str().to_os_string(),
+        ));
+    };
+
+    let output = listing(&bytes, input_path)?;
+    if std::fs::write(listing_path, output).is_err() {
+        return Err(CompilationError::UnableToWriteToOutputFile(
+            listing_path.as_os_str().to_os_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+pub fn listing_path_for_rom(rom_path: &Path) -> PathBuf {
+    let mut path = rom_path.as_os_str().to_os_string();
+    path.push(".lst");
+    PathBuf::from(path)
 }
@@ -0,0 +1,258 @@
+use crate::types::{Directive, LabelSelector, Opcode, PseudoInstruction, Register};
+
+use super::{
+    ast::{Operand, OperandKind, Program, Statement, StatementKind},
+    error::{ParseError, Position},
+    token::{Token, TokenType},
+};
+
+pub struct Parser {
+    tokens: Vec<Token>,
+    index: usize,
+}
+
+impl Parser {
+    pub fn new(tokens: Vec<Token>) -> Self {
+        // Only new lines are significant, other whitespace and comments merely separate tokens
+        let tokens = tokens
+            .into_iter()
+            .filter(|token| match token.ttype() {
+                TokenType::Whitespace => token.text().contains('\n'),
+                TokenType::Comment | TokenType::Unknown => false,
+                _ => true,
+            })
+            .collect();
+
+        Parser { tokens, index: 0 }
+    }
+
+    pub fn parse(self) -> Result<Program, ParseError> {
+        let (program, mut errors) = self.parse_recovering();
+        if errors.is_empty() {
+            Ok(program)
+        } else {
+            Err(errors.remove(0))
+        }
+    }
+
+    // Parses the whole program, a line containing an error is skipped so parsing can continue
+    pub fn parse_recovering(mut self) -> (Program, Vec<ParseError>) {
+        let mut statements = vec![];
+        let mut errors = vec![];
+
+        while let Some(token) = self.peek() {
+            if Self::is_end_of_line(token) {
+                self.index += 1;
+                continue;
+            }
+
+            if let Err(error) = self.parse_line(&mut statements) {
+                errors.push(error);
+
+                while self
+                    .peek()
+                    .is_some_and(|token| !Self::is_end_of_line(token))
+                {
+                    self.index += 1;
+                }
+            }
+        }
+
+        (Program { statements }, errors)
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.index)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.index).cloned();
+        self.index += 1;
+        token
+    }
+
+    fn is_end_of_line(token: &Token) -> bool {
+        token.ttype() == TokenType::Whitespace
+    }
+
+    fn is_punctuation(token: Option<&Token>, punctuation: &str) -> bool {
+        token.is_some_and(|token| {
+            token.ttype() == TokenType::Punctuation && token.text() == punctuation
+        })
+    }
+
+    fn unexpected(token: &Token) -> ParseError {
+        if Self::is_end_of_line(token) {
+            ParseError::UnexpectedEndOfLine(token.position().clone())
+        } else {
+            ParseError::UnexpectedToken(token.position().clone(), token.text())
+        }
+    }
+
+    // Parses a label definition, an instruction or a directive
+    fn parse_line(&mut self, statements: &mut Vec<Statement>) -> Result<(), ParseError> {
+        let token = self.next().unwrap();
+        let position = token.position().clone();
+        let text = token.text();
+
+        match token.ttype() {
+            TokenType::Symbol if Self::is_punctuation(self.peek(), ":") => {
+                self.index += 1;
+                statements.push(Statement {
+                    position,
+                    kind: StatementKind::Label(text),
+                });
+
+                return Ok(());
+            }
+            TokenType::Symbol => {
+                let kind = match Opcode::try_from(text.as_str()) {
+                    Ok(opcode) => StatementKind::Instruction(opcode, self.parse_operands()?),
+                    Err(_) => match PseudoInstruction::try_from(text.as_str()) {
+                        Ok(pseudo) => StatementKind::Pseudo(pseudo, self.parse_operands()?),
+                        Err(_) => return Err(ParseError::UnknownInstruction(position, text)),
+                    },
+                };
+
+                statements.push(Statement { position, kind });
+            }
+            TokenType::Directive => {
+                let Ok(directive) = Directive::try_from(text.as_str()) else {
+                    return Err(ParseError::UnknownDirective(position, text));
+                };
+
+                let operands = self.parse_operands()?;
+                statements.push(Statement {
+                    position,
+                    kind: StatementKind::Directive(directive, operands),
+                });
+            }
+            _ => return Err(Self::unexpected(&token)),
+        }
+
+        match self.peek() {
+            Some(token) if !Self::is_end_of_line(token) => Err(Self::unexpected(token)),
+            _ => Ok(()),
+        }
+    }
+
+    // Operands are separated by whitespace and/or a comma and end at the end of the line
+    fn parse_operands(&mut self) -> Result<Vec<Operand>, ParseError> {
+        let mut operands = vec![];
+
+        while self
+            .peek()
+            .is_some_and(|token| !Self::is_end_of_line(token))
+        {
+            if !operands.is_empty() && Self::is_punctuation(self.peek(), ",") {
+                self.index += 1;
+            }
+
+            operands.push(self.parse_operand()?);
+        }
+
+        Ok(operands)
+    }
+
+    fn parse_operand(&mut self) -> Result<Operand, ParseError> {
+        let Some(token) = self.next() else {
+            let position = self.tokens.last().unwrap().position().clone();
+            return Err(ParseError::UnexpectedEndOfLine(position));
+        };
+
+        let position = token.position().clone();
+        let text = token.text();
+
+        let kind = match token.ttype() {
+            TokenType::Symbol if Self::is_punctuation(self.peek(), "(") => {
+                self.index += 1;
+                let selector = match text.to_lowercase().as_str() {
+                    "hi" => LabelSelector::High,
+                    "lo" => LabelSelector::Low,
+                    _ => return Err(ParseError::UnknownSelector(position, text)),
+                };
+
+                let label = match self.next() {
+                    Some(token) if token.ttype() == TokenType::Symbol => token.text(),
+                    Some(token) => return Err(Self::unexpected(&token)),
+                    None => return Err(ParseError::UnexpectedEndOfLine(position)),
+                };
+
+                match self.next() {
+                    Some(token) if Self::is_punctuation(Some(&token), ")") => {}
+                    Some(token) => return Err(Self::unexpected(&token)),
+                    None => return Err(ParseError::UnexpectedEndOfLine(position)),
+                }
+
+                OperandKind::Label(label, selector)
+            }
+            TokenType::Symbol => match Register::try_from(text.as_str()) {
+                Ok(high) if Self::is_punctuation(self.peek(), ":") => {
+                    self.index += 1;
+                    match self.next() {
+                        Some(token) => match Register::try_from(token.text().as_str()) {
+                            Ok(low) => OperandKind::RegisterPair(high, low),
+                            Err(_) => return Err(Self::unexpected(&token)),
+                        },
+                        None => return Err(ParseError::UnexpectedEndOfLine(position)),
+                    }
+                }
+                Ok(register) => OperandKind::Register(register),
+                Err(_) => OperandKind::Label(text, LabelSelector::Address),
+            },
+            TokenType::Punctuation if text == "[" => {
+                let address = self.parse_operand()?;
+
+                match self.next() {
+                    Some(token) if Self::is_punctuation(Some(&token), "]") => {}
+                    Some(token) => return Err(Self::unexpected(&token)),
+                    None => return Err(ParseError::UnexpectedEndOfLine(position)),
+                }
+
+                OperandKind::Memory(Box::new(address))
+            }
+            TokenType::Number => OperandKind::Number(Self::parse_number(&position, &text)?),
+            TokenType::String => OperandKind::String(Self::parse_string(&position, &text)?),
+            _ => return Err(Self::unexpected(&token)),
+        };
+
+        Ok(Operand { position, kind })
+    }
+
+    fn parse_number(position: &Position, text: &str) -> Result<u16, ParseError> {
+        let result = match text.strip_prefix("0x").or(text.strip_prefix("0X")) {
+            Some(hexadecimal) => u16::from_str_radix(hexadecimal, 16),
+            None => text.parse::<u16>(),
+        };
+
+        result.map_err(|_| ParseError::InvalidNumber(position.clone(), text.to_string()))
+    }
+
+    fn parse_string(position: &Position, text: &str) -> Result<Vec<u8>, ParseError> {
+        let mut chars = text.chars().skip(1).peekable();
+        let mut bytes = vec![];
+
+        while let Some(c) = chars.next() {
+            match c {
+                '"' if chars.peek().is_none() => return Ok(bytes),
+                '\\' => bytes.push(match chars.next() {
+                    Some('n') => b'\n',
+                    Some('r') => b'\r',
+                    Some('t') => b'\t',
+                    Some('0') => 0,
+                    Some('\\') => b'\\',
+                    Some('"') => b'"',
+                    Some(c) => return Err(ParseError::InvalidEscapeSequence(position.clone(), c)),
+                    None => break,
+                }),
+                c => bytes.push(c as u8),
+            }
+        }
+
+        Err(ParseError::UnterminatedString(position.clone()))
+    }
+}
+
+pub fn parse(tokens: Vec<Token>) -> Result<Program, ParseError> {
+    Parser::new(tokens).parse()
+}
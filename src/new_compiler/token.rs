@@ -4,7 +4,7 @@ use crate::new_compiler::error::TokenizationError;
 
 use super::error::{Position, TokenTypeConversionError};
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Token {
     position: Position,
     data: Vec<char>,
@@ -18,6 +18,9 @@ pub enum TokenType {
     Number,
     Whitespace,
     Comment,
+    Directive,   // `.name`
+    String,      // `"text"`, including the quotes
+    Punctuation, // single character, one of `:`, `,`, `(`, `)`, `[` and `]`
 }
 
 impl TokenType {
@@ -31,13 +34,24 @@ impl TokenType {
             });
         }
 
+        // Strings end at the first unescaped quote after the opening quote
+        if token.ttype == TokenType::String && !token.is_complete() {
+            return Ok(TokenType::String);
+        }
+
         // Check for hexadecimal notation
-        if token.ttype == TokenType::Number && token.data == ['0'] && c == 'x' || c == 'X' {
+        if token.ttype == TokenType::Number
+            && (token.data == ['0'] && (c == 'x' || c == 'X')
+                || token.is_hexadecimal() && c.is_ascii_hexdigit())
+        {
             return Ok(TokenType::Number);
         }
 
         let ttype = match c {
-            'a'..='z' | 'A'..='Z' => TokenType::Symbol,
+            'a'..='z' | 'A'..='Z' | '_' if token.ttype == TokenType::Directive => {
+                TokenType::Directive
+            }
+            'a'..='z' | 'A'..='Z' | '_' => TokenType::Symbol,
             '0'..='9' => {
                 if token.ttype == TokenType::Symbol {
                     TokenType::Symbol
@@ -45,6 +59,9 @@ impl TokenType {
                     TokenType::Number
                 }
             }
+            '.' => TokenType::Directive,
+            '"' => TokenType::String,
+            ':' | ',' | '(' | ')' | '[' | ']' => TokenType::Punctuation,
             ' ' | '\t' | '\n' | '\r' => TokenType::Whitespace,
             '#' => TokenType::Comment,
             _ => TokenType::Unknown,
@@ -80,7 +97,7 @@ impl Token {
             self.ttype = ttype;
         }
 
-        if self.ttype != ttype {
+        if self.ttype != ttype || self.is_complete() {
             return Err(TokenTypeConversionError::IncompatibleTypes(
                 ttype, self.ttype,
             ));
@@ -93,13 +110,53 @@ impl Token {
     pub fn ttype(&self) -> TokenType {
         return self.ttype;
     }
+
+    pub fn position(&self) -> &Position {
+        &self.position
+    }
+
+    pub fn text(&self) -> String {
+        String::from_iter(&self.data)
+    }
+
+    // Whether the token can not take any more characters, regardless of their type
+    fn is_complete(&self) -> bool {
+        match self.ttype {
+            TokenType::Punctuation => !self.data.is_empty(),
+            TokenType::String => {
+                let escapes = self.data.iter().rev().skip(1);
+                let escaped = escapes.take_while(|c| **c == '\\').count() % 2 == 1;
+
+                self.data.len() > 1 && self.data.last() == Some(&'"') && !escaped
+            }
+            _ => false,
+        }
+    }
+
+    fn is_hexadecimal(&self) -> bool {
+        self.data.len() > 1 && self.data[0] == '0' && matches!(self.data[1], 'x' | 'X')
+    }
 }
 
 pub fn tokenize(
     input_stream: &[u8],
     file_path: &Path,
 ) -> Result<Box<Vec<Token>>, TokenizationError> {
+    let (tokens, mut errors) = tokenize_recovering(input_stream, file_path);
+    if !errors.is_empty() {
+        return Err(errors.remove(0));
+    }
+
+    return Ok(Box::new(tokens));
+}
+
+// Tokenizes the whole input, unknown characters are reported and skipped
+pub fn tokenize_recovering(
+    input_stream: &[u8],
+    file_path: &Path,
+) -> (Vec<Token>, Vec<TokenizationError>) {
     let mut tokens = vec![];
+    let mut errors = vec![];
 
     let chars = (*input_stream)
         .iter()
@@ -109,23 +166,32 @@ pub fn tokenize(
     let mut pos = Position::new(file_path);
     let mut current_token = Token::new(pos.clone());
     for c in chars {
-        if c == '\n' {
+        // position of the character itself, a new line belongs to the end of the line
+        let char_pos = if c == '\n' {
+            let mut char_pos = pos.clone();
+            char_pos.next_char();
             pos.next_line();
+            char_pos
         } else {
             pos.next_char();
+            pos.clone()
+        };
+
+        if current_token.data.is_empty() {
+            current_token.position = char_pos.clone();
         }
 
         if let Err(error) = current_token.take(c) {
             if matches!(error, TokenTypeConversionError::IncompatibleTypes(..)) {
                 tokens.push(current_token);
-                current_token = Token::new(pos.clone());
+                current_token = Token::new(char_pos);
                 _ = current_token.take(c);
             } else {
-                return Err(TokenizationError::TokenTypeConversion(pos, error));
+                errors.push(TokenizationError::TokenTypeConversion(char_pos, error));
             }
         }
     }
 
     tokens.push(current_token);
-    return Ok(Box::new(tokens));
+    (tokens, errors)
 }
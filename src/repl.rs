@@ -0,0 +1,237 @@
+use std::path::PathBuf;
+
+use rustyline::{
+    completion::{Completer, FilenameCompleter, Pair},
+    error::ReadlineError,
+    highlight::Highlighter,
+    hint::Hinter,
+    history::DefaultHistory,
+    validate::Validator,
+    Context, Editor, Helper,
+};
+
+use crate::cli::command::{Parameter, ParameterKind};
+
+// Line editing of the interactive prompt with a history that is kept across sessions
+
+const HISTORY_FILE: &str = ".mrt_cpu_history";
+
+#[derive(Debug, PartialEq)]
+pub enum ArgumentError {
+    UnterminatedQuote,
+}
+
+// Splits a line at whitespace, quotes keep whitespace in an argument and a backslash escapes the
+// next character outside of single quotes, e.g. `input "a  b"`
+pub fn split_arguments(line: &str) -> Result<Vec<String>, ArgumentError> {
+    let mut arguments = vec![];
+    let mut argument: Option<String> = None;
+    let mut quote = None;
+
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(open), c) if c == open => quote = None,
+            (Some('"') | None, '\\') => {
+                if let Some(escaped) = chars.next() {
+                    argument.get_or_insert_with(String::new).push(escaped);
+                }
+            }
+            (None, '"' | '\'') => {
+                quote = Some(c);
+                argument.get_or_insert_with(String::new);
+            }
+            (None, c) if c.is_whitespace() => arguments.extend(argument.take()),
+            (_, c) => argument.get_or_insert_with(String::new).push(c),
+        }
+    }
+
+    if quote.is_some() {
+        return Err(ArgumentError::UnterminatedQuote);
+    }
+
+    arguments.extend(argument);
+    Ok(arguments)
+}
+
+// Completes command names, and arguments by the kind of their parameter: file paths, the labels
+// of the loaded rom, choices and flags
+pub struct CommandCompleter {
+    files: FilenameCompleter,
+    commands: Vec<(String, Vec<Parameter>)>, // names and aliases
+    labels: Vec<String>,
+}
+
+impl CommandCompleter {
+    pub fn new() -> Self {
+        Self {
+            files: FilenameCompleter::new(),
+            commands: vec![],
+            labels: vec![],
+        }
+    }
+
+    pub fn set_commands(&mut self, commands: Vec<(String, Vec<Parameter>)>) {
+        self.commands = commands;
+    }
+
+    pub fn set_labels(&mut self, labels: Vec<String>) {
+        self.labels = labels;
+    }
+
+    // Start of the completed word and the candidates for it
+    pub fn completions(&self, line: &str, pos: usize) -> (usize, Vec<Pair>) {
+        let line = &line[..pos];
+        let start = line.rfind(char::is_whitespace).map_or(0, |index| index + 1);
+        let word = &line[start..];
+
+        let candidates = |names: &mut dyn Iterator<Item = &str>| {
+            names
+                .filter(|name| name.starts_with(word))
+                .map(|name| Pair {
+                    display: name.to_string(),
+                    replacement: name.to_string(),
+                })
+                .collect()
+        };
+
+        let mut arguments = line[..start].split_whitespace();
+        let Some(name) = arguments.next() else {
+            let mut names = self.commands.iter().map(|(name, _)| name.as_str());
+            return (start, candidates(&mut names));
+        };
+
+        let Some((_, parameters)) = self.commands.iter().find(|(command, _)| command == name)
+        else {
+            return (start, vec![]);
+        };
+
+        let (flags, parameters): (Vec<&Parameter>, Vec<&Parameter>) = parameters
+            .iter()
+            .partition(|parameter| parameter.kind == ParameterKind::Flag);
+
+        if word.starts_with('-') {
+            let mut names = flags.iter().map(|flag| flag.name);
+            return (start, candidates(&mut names));
+        }
+
+        // the remaining arguments belong to a trailing text parameter
+        let index = arguments
+            .filter(|argument| !argument.starts_with("--"))
+            .count();
+        let parameter = parameters.get(index).or(parameters
+            .last()
+            .filter(|parameter| parameter.kind == ParameterKind::Text));
+
+        match parameter.map(|parameter| parameter.kind) {
+            Some(ParameterKind::File) => self
+                .files
+                .complete_path(line, pos)
+                .unwrap_or((start, vec![])),
+            Some(ParameterKind::Address) => (
+                start,
+                candidates(&mut self.labels.iter().map(String::as_str)),
+            ),
+            Some(ParameterKind::Choice(choices)) => {
+                (start, candidates(&mut choices.iter().copied()))
+            }
+            _ => (start, vec![]),
+        }
+    }
+}
+
+impl Default for CommandCompleter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Completer for CommandCompleter {
+    type Candidate = Pair;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<Pair>)> {
+        Ok(self.completions(line, pos))
+    }
+}
+
+impl Hinter for CommandCompleter {
+    type Hint = String;
+}
+
+impl Highlighter for CommandCompleter {}
+
+impl Validator for CommandCompleter {}
+
+impl Helper for CommandCompleter {}
+
+#[derive(Debug, PartialEq)]
+pub enum Prompt {
+    Line(String),
+    Interrupted, // Ctrl-C discarded the line
+    Ended,
+}
+
+pub struct LineEditor {
+    editor: Editor<CommandCompleter, DefaultHistory>,
+    history_path: Option<PathBuf>, // in the home directory
+}
+
+impl LineEditor {
+    pub fn new() -> Self {
+        let mut editor = Editor::new().expect("Failed to set up line editing");
+        editor.set_helper(Some(CommandCompleter::new()));
+
+        // there is no history yet in the first session
+        let history_path =
+            std::env::var_os("HOME").map(|home| PathBuf::from(home).join(HISTORY_FILE));
+        if let Some(path) = &history_path {
+            _ = editor.load_history(path);
+        }
+
+        Self {
+            editor,
+            history_path,
+        }
+    }
+
+    // Commands of the registry to complete
+    pub fn set_commands(&mut self, commands: Vec<(String, Vec<Parameter>)>) {
+        if let Some(completer) = self.editor.helper_mut() {
+            completer.set_commands(commands);
+        }
+    }
+
+    // Reads a line with the labels to complete
+    pub fn read_line(&mut self, prompt: &str, labels: Vec<String>) -> Prompt {
+        if let Some(completer) = self.editor.helper_mut() {
+            completer.set_labels(labels);
+        }
+
+        match self.editor.readline(prompt) {
+            Ok(line) => {
+                if !line.trim().is_empty() {
+                    _ = self.editor.add_history_entry(line.as_str());
+                    if let Some(path) = &self.history_path {
+                        _ = self.editor.save_history(path);
+                    }
+                }
+
+                Prompt::Line(line)
+            }
+            Err(ReadlineError::Interrupted) => Prompt::Interrupted,
+            Err(ReadlineError::Eof) => Prompt::Ended,
+            Err(error) => panic!("Failed to gather input from stdin: {}", error),
+        }
+    }
+}
+
+impl Default for LineEditor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,113 @@
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    path::{Path, PathBuf},
+};
+
+// Label addresses of a compiled program, stored next to the rom as `<rom>.sym`
+#[derive(Debug, Clone, Default)]
+pub struct SymbolTable {
+    symbols: HashMap<String, u16>,
+}
+
+#[derive(Debug)]
+pub enum SymbolTableError {
+    FailedToRead,
+    FailedToWrite,
+    InvalidLine(usize),
+}
+
+impl SymbolTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, name: &str, address: u16) {
+        self.symbols.insert(name.to_string(), address);
+    }
+
+    pub fn get(&self, name: &str) -> Option<u16> {
+        self.symbols.get(name).copied()
+    }
+
+    // First label (alphabetically) defined at the address
+    pub fn name_of(&self, address: u16) -> Option<&str> {
+        self.symbols
+            .iter()
+            .filter(|(_, symbol_address)| **symbol_address == address)
+            .map(|(name, _)| name.as_str())
+            .min()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.symbols.is_empty()
+    }
+
+    // All symbols ordered by address
+    pub fn sorted(&self) -> Vec<(&str, u16)> {
+        let mut symbols = self
+            .symbols
+            .iter()
+            .map(|(name, address)| (name.as_str(), *address))
+            .collect::<Vec<_>>();
+
+        symbols.sort_by_key(|(name, address)| (*address, *name));
+        symbols
+    }
+
+    pub fn path_for_rom(rom_path: &Path) -> PathBuf {
+        let mut path = rom_path.as_os_str().to_os_string();
+        path.push(".sym");
+        PathBuf::from(path)
+    }
+
+    pub fn parse(data: &str) -> Result<Self, SymbolTableError> {
+        let mut table = SymbolTable::new();
+
+        for (index, line) in data.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let mut parts = line.split_whitespace();
+            let (Some(address), Some(name), None) = (parts.next(), parts.next(), parts.next())
+            else {
+                return Err(SymbolTableError::InvalidLine(index + 1));
+            };
+
+            let address = u16::from_str_radix(address.trim_start_matches("0x"), 16)
+                .map_err(|_| SymbolTableError::InvalidLine(index + 1))?;
+
+            table.insert(name, address);
+        }
+
+        Ok(table)
+    }
+
+    pub fn read(path: &Path) -> Result<Self, SymbolTableError> {
+        match std::fs::read_to_string(path) {
+            Ok(data) => Self::parse(&data),
+            Err(_) => Err(SymbolTableError::FailedToRead),
+        }
+    }
+
+    pub fn write(&self, path: &Path) -> Result<(), SymbolTableError> {
+        std::fs::write(path, self.to_string()).map_err(|_| SymbolTableError::FailedToWrite)
+    }
+}
+
+impl Display for SymbolTable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (name, address) in self.sorted() {
+            writeln!(f, "{:#06x} {}", address, name)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl From<HashMap<String, u16>> for SymbolTable {
+    fn from(symbols: HashMap<String, u16>) -> Self {
+        Self { symbols }
+    }
+}
@@ -1,5 +1,5 @@
 #[repr(u8)]
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum Opcode {
     HLT,
     LDI,
@@ -16,11 +16,328 @@ pub enum Opcode {
     NOT,
     AND,
     OR,
+
+    // extended opcodes, encoded behind the `Opcode::EXTENSION` prefix
+    EI,
+    DI,
+    RETI,
+    ADC,
+    SBC,
+    CMP,
+
+    // conditional branches, encoded as `JNZ` with the condition in the low nibble of the
+    // second byte
+    JZ,
+    JNC,
+    JS,
+    JNS,
+    JO,
+    JNO,
+    JL,
+    JGE,
+    JLE,
+    JG,
+    JBE,
+    JA,
+    JMP,
+
+    PUSH,
+    POP,
+    CALL,
+    RET,
+
+    MUL,
+    MULH,
+    IMUL,
+    IMULH,
+    DIV,
+    MOD,
+    IDIV,
+    IMOD,
+
+    ROL,
+    ROR,
+    RCL,
+    RCR,
+    ASR,
+    // shifts and rotates by the amount in a register
+    SHLV,
+    SHRV,
+    ASRV,
+    ROLV,
+    RORV,
+    RCLV,
+    RCRV,
+}
+
+// Condition of the branch instructions, `NotZero` (0) keeps `JNZ` encoded as before
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BranchCondition {
+    NotZero,        // JNZ
+    Zero,           // JZ
+    NoCarry,        // JNC (JAE), unsigned greater or equal after CMP
+    Sign,           // JS
+    NoSign,         // JNS
+    Overflow,       // JO
+    NoOverflow,     // JNO
+    Less,           // JL, signed
+    GreaterOrEqual, // JGE, signed
+    LessOrEqual,    // JLE, signed
+    Greater,        // JG, signed
+    BelowOrEqual,   // JBE, unsigned
+    Above,          // JA, unsigned
+    Always,         // JMP
+}
+
+impl TryFrom<u8> for BranchCondition {
+    type Error = OpcodeConversionError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        let result = match value {
+            0 => BranchCondition::NotZero,
+            1 => BranchCondition::Zero,
+            2 => BranchCondition::NoCarry,
+            3 => BranchCondition::Sign,
+            4 => BranchCondition::NoSign,
+            5 => BranchCondition::Overflow,
+            6 => BranchCondition::NoOverflow,
+            7 => BranchCondition::Less,
+            8 => BranchCondition::GreaterOrEqual,
+            9 => BranchCondition::LessOrEqual,
+            10 => BranchCondition::Greater,
+            11 => BranchCondition::BelowOrEqual,
+            12 => BranchCondition::Above,
+            13 => BranchCondition::Always,
+            _ => return Err(OpcodeConversionError::NoSuchOpcode),
+        };
+
+        Ok(result)
+    }
+}
+
+// Secondary opcode table of the extension prefix, indexed by the extended opcode. New
+// instructions are appended so existing programs keep their encoding.
+const EXTENDED_OPCODES: &[Opcode] = &[
+    Opcode::EI,
+    Opcode::DI,
+    Opcode::RETI,
+    Opcode::ADC,
+    Opcode::SBC,
+    Opcode::CMP,
+    Opcode::PUSH,
+    Opcode::POP,
+    Opcode::CALL,
+    Opcode::RET,
+    Opcode::MUL,
+    Opcode::MULH,
+    Opcode::IMUL,
+    Opcode::IMULH,
+    Opcode::DIV,
+    Opcode::MOD,
+    Opcode::IDIV,
+    Opcode::IMOD,
+    Opcode::ROL,
+    Opcode::ROR,
+    Opcode::RCL,
+    Opcode::RCR,
+    Opcode::ASR,
+    Opcode::SHLV,
+    Opcode::SHRV,
+    Opcode::ASRV,
+    Opcode::ROLV,
+    Opcode::RORV,
+    Opcode::RCLV,
+    Opcode::RCRV,
+];
+
+impl Opcode {
+    // Primary opcode of extended instructions, the following bytes select the extended opcode
+    pub const EXTENSION: u8 = 15;
+
+    // Extended opcode byte that continues with the next page of 255 extended opcodes
+    pub const EXTENSION_ESCAPE: u8 = 0xff;
+
+    pub fn extension(&self) -> Option<u16> {
+        EXTENDED_OPCODES
+            .iter()
+            .position(|opcode| opcode == self)
+            .map(|index| index as u16)
+    }
+
+    pub fn is_extended(&self) -> bool {
+        self.extension().is_some()
+    }
+
+    pub fn from_extension(value: u16) -> Result<Self, OpcodeConversionError> {
+        EXTENDED_OPCODES
+            .get(value as usize)
+            .copied()
+            .ok_or(OpcodeConversionError::NoSuchOpcode)
+    }
+
+    // Bytes following the prefix: one escape byte per skipped page, then the index in the page
+    pub fn encode_extension(extension: u16) -> Vec<u8> {
+        let page_size = Self::EXTENSION_ESCAPE as u16;
+        let mut bytes = vec![Self::EXTENSION_ESCAPE; (extension / page_size) as usize];
+        bytes.push((extension % page_size) as u8);
+        bytes
+    }
+
+    pub fn extension_bytes(&self) -> Option<Vec<u8>> {
+        self.extension().map(Self::encode_extension)
+    }
+
+    // Decodes the opcode of the instruction whose bytes `byte` returns, together with the offset
+    // of the byte holding the remaining operands
+    pub fn decode<F>(byte: F) -> Result<(Opcode, u16), OpcodeConversionError>
+    where
+        F: Fn(u16) -> Option<u8>,
+    {
+        let byte = |offset: u16| byte(offset).ok_or(OpcodeConversionError::Truncated);
+        let opcode_raw = byte(0)? >> 4;
+
+        if opcode_raw == Self::EXTENSION {
+            let mut extension: u16 = 0;
+            let mut offset = 1;
+            loop {
+                let value = byte(offset)?;
+                offset += 1;
+                // a run of escape bytes too long for any opcode
+                extension = extension
+                    .checked_add(value as u16)
+                    .ok_or(OpcodeConversionError::NoSuchOpcode)?;
+
+                if value != Self::EXTENSION_ESCAPE {
+                    return Ok((Self::from_extension(extension)?, offset));
+                }
+            }
+        }
+
+        // the low nibble of the second byte holds the branch condition
+        if opcode_raw == Opcode::JNZ as u8 {
+            let condition = BranchCondition::try_from(byte(1)? & 0b1111)?;
+            return Ok((Self::from_branch_condition(condition), 1));
+        }
+
+        Ok((Self::try_from(opcode_raw)?, 1))
+    }
+
+    // Branches and CALL, which jump to the address in a register pair
+    pub fn is_jump(&self) -> bool {
+        self.branch_condition().is_some() || matches!(self, Opcode::JC | Opcode::CALL)
+    }
+
+    // The carry branch keeps its own primary opcode `JC`
+    pub fn branch_condition(&self) -> Option<BranchCondition> {
+        match self {
+            Opcode::JNZ => Some(BranchCondition::NotZero),
+            Opcode::JZ => Some(BranchCondition::Zero),
+            Opcode::JNC => Some(BranchCondition::NoCarry),
+            Opcode::JS => Some(BranchCondition::Sign),
+            Opcode::JNS => Some(BranchCondition::NoSign),
+            Opcode::JO => Some(BranchCondition::Overflow),
+            Opcode::JNO => Some(BranchCondition::NoOverflow),
+            Opcode::JL => Some(BranchCondition::Less),
+            Opcode::JGE => Some(BranchCondition::GreaterOrEqual),
+            Opcode::JLE => Some(BranchCondition::LessOrEqual),
+            Opcode::JG => Some(BranchCondition::Greater),
+            Opcode::JBE => Some(BranchCondition::BelowOrEqual),
+            Opcode::JA => Some(BranchCondition::Above),
+            Opcode::JMP => Some(BranchCondition::Always),
+            _ => None,
+        }
+    }
+
+    pub fn from_branch_condition(condition: BranchCondition) -> Self {
+        match condition {
+            BranchCondition::NotZero => Opcode::JNZ,
+            BranchCondition::Zero => Opcode::JZ,
+            BranchCondition::NoCarry => Opcode::JNC,
+            BranchCondition::Sign => Opcode::JS,
+            BranchCondition::NoSign => Opcode::JNS,
+            BranchCondition::Overflow => Opcode::JO,
+            BranchCondition::NoOverflow => Opcode::JNO,
+            BranchCondition::Less => Opcode::JL,
+            BranchCondition::GreaterOrEqual => Opcode::JGE,
+            BranchCondition::LessOrEqual => Opcode::JLE,
+            BranchCondition::Greater => Opcode::JG,
+            BranchCondition::BelowOrEqual => Opcode::JBE,
+            BranchCondition::Above => Opcode::JA,
+            BranchCondition::Always => Opcode::JMP,
+        }
+    }
+
+    // Cycles taken to execute the instruction: one per fetched byte, one per data access and
+    // one for loading the ip of jumps, whether taken or not
+    pub fn cycles(&self) -> u64 {
+        match self {
+            Opcode::HLT => 1,
+            Opcode::LDI => 2,
+            Opcode::ADD => 2,
+            Opcode::SB => 3,
+            Opcode::LB => 3,
+            Opcode::JNZ => 3,
+            Opcode::JAL => 3,
+            Opcode::XOR => 2,
+            Opcode::SUB => 2,
+            Opcode::SHL => 2,
+            Opcode::SHR => 2,
+            Opcode::JC => 3,
+            Opcode::NOT => 2,
+            Opcode::AND => 2,
+            Opcode::OR => 2,
+            Opcode::EI => 2,
+            Opcode::DI => 2,
+            Opcode::RETI => 3,
+            Opcode::ADC => 3,
+            Opcode::SBC => 3,
+            Opcode::CMP => 3,
+            Opcode::JZ
+            | Opcode::JNC
+            | Opcode::JS
+            | Opcode::JNS
+            | Opcode::JO
+            | Opcode::JNO
+            | Opcode::JL
+            | Opcode::JGE
+            | Opcode::JLE
+            | Opcode::JG
+            | Opcode::JBE
+            | Opcode::JA
+            | Opcode::JMP => 3,
+            Opcode::PUSH => 3,
+            Opcode::POP => 3,
+            Opcode::CALL => 5,
+            Opcode::RET => 4,
+            Opcode::MUL
+            | Opcode::MULH
+            | Opcode::IMUL
+            | Opcode::IMULH
+            | Opcode::DIV
+            | Opcode::MOD
+            | Opcode::IDIV
+            | Opcode::IMOD => 3,
+            Opcode::ROL
+            | Opcode::ROR
+            | Opcode::RCL
+            | Opcode::RCR
+            | Opcode::ASR
+            | Opcode::SHLV
+            | Opcode::SHRV
+            | Opcode::ASRV
+            | Opcode::ROLV
+            | Opcode::RORV
+            | Opcode::RCLV
+            | Opcode::RCRV => 3,
+        }
+    }
 }
 
 #[derive(Debug)]
 pub enum OpcodeConversionError {
     NoSuchOpcode,
+    Truncated, // the instruction ends before its opcode
 }
 
 impl TryFrom<&str> for Opcode {
@@ -43,6 +360,50 @@ impl TryFrom<&str> for Opcode {
             "NOT" => Opcode::NOT,
             "AND" => Opcode::AND,
             "OR" => Opcode::OR,
+            "EI" => Opcode::EI,
+            "DI" => Opcode::DI,
+            "RETI" => Opcode::RETI,
+            "ADC" => Opcode::ADC,
+            "SBC" => Opcode::SBC,
+            "CMP" => Opcode::CMP,
+            "JZ" => Opcode::JZ,
+            "JNC" | "JAE" => Opcode::JNC,
+            "JB" => Opcode::JC,
+            "JS" => Opcode::JS,
+            "JNS" => Opcode::JNS,
+            "JO" => Opcode::JO,
+            "JNO" => Opcode::JNO,
+            "JL" => Opcode::JL,
+            "JGE" => Opcode::JGE,
+            "JLE" => Opcode::JLE,
+            "JG" => Opcode::JG,
+            "JBE" => Opcode::JBE,
+            "JA" => Opcode::JA,
+            "JMP" => Opcode::JMP,
+            "PUSH" => Opcode::PUSH,
+            "POP" => Opcode::POP,
+            "CALL" => Opcode::CALL,
+            "RET" => Opcode::RET,
+            "MUL" => Opcode::MUL,
+            "MULH" => Opcode::MULH,
+            "IMUL" => Opcode::IMUL,
+            "IMULH" => Opcode::IMULH,
+            "DIV" => Opcode::DIV,
+            "MOD" => Opcode::MOD,
+            "IDIV" => Opcode::IDIV,
+            "IMOD" => Opcode::IMOD,
+            "ROL" => Opcode::ROL,
+            "ROR" => Opcode::ROR,
+            "RCL" => Opcode::RCL,
+            "RCR" => Opcode::RCR,
+            "ASR" => Opcode::ASR,
+            "SHLV" => Opcode::SHLV,
+            "SHRV" => Opcode::SHRV,
+            "ASRV" => Opcode::ASRV,
+            "ROLV" => Opcode::ROLV,
+            "RORV" => Opcode::RORV,
+            "RCLV" => Opcode::RCLV,
+            "RCRV" => Opcode::RCRV,
             _ => return Err(OpcodeConversionError::NoSuchOpcode),
         };
 
@@ -77,8 +438,86 @@ impl TryFrom<u8> for Opcode {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Directive {
+    Org,      // .org address - continue output at address
+    Db,       // .db bytes.. - raw bytes and/or strings
+    Dw,       // .dw words.. - 16-bit words, most significant byte first
+    Ascii,    // .ascii "string"
+    Asciiz,   // .asciiz "string" - zero terminated
+    Fill,     // .fill count <byte> - repeat byte (default 0) count times
+    Align,    // .align n - pad with zeros up to the next multiple of n
+    WordSize, // .wordsize bits - word size (8 or 16) of the target machine, 8 by default
+    Word,     // .word words.. - words of the target word size, most significant byte first
+}
+
+#[derive(Debug)]
+pub enum DirectiveConversionError {
+    NoSuchDirective,
+}
+
+impl TryFrom<&str> for Directive {
+    type Error = DirectiveConversionError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let result = match value.to_lowercase().as_str() {
+            ".org" => Directive::Org,
+            ".db" => Directive::Db,
+            ".dw" => Directive::Dw,
+            ".ascii" => Directive::Ascii,
+            ".asciiz" => Directive::Asciiz,
+            ".fill" => Directive::Fill,
+            ".align" => Directive::Align,
+            ".wordsize" => Directive::WordSize,
+            ".word" => Directive::Word,
+            _ => return Err(DirectiveConversionError::NoSuchDirective),
+        };
+
+        Ok(result)
+    }
+}
+
+// Assembler mnemonics without an encoding of their own, they expand into real instructions
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PseudoInstruction {
+    Ldi16, // LDI16 rh:rl value - LDI rh hi(value), LDI rl lo(value)
+    Mov,   // MOV rd rs - OR rd rs rs
+}
+
+// Registers the assembler loads addresses into when an instruction is given an address instead
+// of a register pair, e.g. `CALL label` or `LB rd [label]`
+pub const ADDRESS_REGISTERS: (Register, Register) = (Register::R14, Register::R15);
+
+#[derive(Debug)]
+pub enum PseudoInstructionConversionError {
+    NoSuchPseudoInstruction,
+}
+
+impl TryFrom<&str> for PseudoInstruction {
+    type Error = PseudoInstructionConversionError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let result = match value.to_uppercase().as_str() {
+            "LDI16" => PseudoInstruction::Ldi16,
+            "MOV" => PseudoInstruction::Mov,
+            _ => return Err(PseudoInstructionConversionError::NoSuchPseudoInstruction),
+        };
+
+        Ok(result)
+    }
+}
+
+// Which part of the 16-bit label address is used as operand byte
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LabelSelector {
+    Address, // `name`, the address has to fit in a single byte
+    High,    // `hi(name)`
+    Low,     // `lo(name)`
+}
+
+// Encoded by its number
 #[repr(u8)]
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq)]
 pub enum Register {
     R0,
     R1,
@@ -91,6 +530,7 @@ pub enum Register {
     R8,
     R9,
     R10,
+    R11,
     R12,
     R13,
     R14,
@@ -120,13 +560,13 @@ impl TryFrom<&str> for Register {
             return fail();
         }
 
-        let digit = chars.next();
-
-        let mut num = (digit.unwrap() as u8) - ('0' as u8);
-
-        if let Some(digit_2) = chars.next() {
-            num *= 10;
-            num += (digit_2 as u8) - ('0' as u8);
+        let mut num = 0u8;
+        for c in chars {
+            // symbols such as label names may start with an 'r' as well
+            match c.to_digit(10) {
+                Some(digit) => num = num * 10 + digit as u8,
+                None => return fail(),
+            }
         }
 
         Register::try_from(num)
@@ -153,6 +593,7 @@ impl TryFrom<u8> for Register {
             Register::R8,
             Register::R9,
             Register::R10,
+            Register::R11,
             Register::R12,
             Register::R13,
             Register::R14,
@@ -166,6 +607,7 @@ impl TryFrom<u8> for Register {
 #[derive(Debug)]
 pub enum InstructionType {
     NoParam,
+    SingleReg,
     RegImm,
     DoubleReg,
     DoubleRegImm4,
@@ -0,0 +1,37 @@
+// Helpers shared by the integration tests, each of which uses only some of them
+#![allow(dead_code)]
+
+use std::path::Path;
+
+use mrt_cpu::{
+    machine::{computer::System, word::Word},
+    new_compiler,
+};
+
+// Assembles the source with the new compiler into an 8-bit machine with `ram_size` bytes of ram
+pub fn system(source: &str, ram_size: usize) -> System {
+    word_system(source, ram_size)
+}
+
+// Like `system` for a machine of any word size
+pub fn word_system<W: Word>(source: &str, ram_size: usize) -> System<W> {
+    let rom = new_compiler::compile(source.as_bytes(), Path::new("test.asm")).unwrap();
+
+    let mut sys = System::with_ram_size(ram_size);
+    sys.load_rom(*rom).unwrap();
+    sys
+}
+
+// Counts r0 up by one, storing every value at [0x80], until it wraps around to zero
+pub const COUNTER: &str = "
+    LDI r1 1
+    LDI r2 0
+    LDI r3 0x80
+    LDI r4 hi(loop)
+    LDI r5 lo(loop)
+    loop:
+    ADD r0 r0 r1
+    SB r0 r2 r3
+    JNZ r4 r5
+    HLT
+";
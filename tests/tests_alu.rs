@@ -17,37 +17,246 @@ mod tests {
 
     #[test]
     fn alu_flags_zero() {
-        let result = ALU::sub(1, 1);
+        let result = ALU::sub(1u8, 1);
         assert!(result.flags.is_set(Flags::Zero));
 
-        let result = ALU::sub(1, 0);
+        let result = ALU::sub(1u8, 0);
         assert!(!result.flags.is_set(Flags::Zero));
     }
 
     #[test]
     fn alu_flags_carry() {
-        let result = ALU::add(255, 255);
+        let result = ALU::add(255u8, 255);
         assert!(result.flags.is_set(Flags::Carry));
 
-        let result = ALU::add(1, 1);
+        let result = ALU::add(1u8, 1);
         assert!(!result.flags.is_set(Flags::Carry));
     }
 
     #[test]
     fn alu_flags_sign() {
-        let result = ALU::sub(0, 1);
+        let result = ALU::sub(0u8, 1);
         assert!(result.flags.is_set(Flags::Sign));
 
-        let result = ALU::sub(0, 0);
+        let result = ALU::sub(0u8, 0);
         assert!(!result.flags.is_set(Flags::Sign));
     }
 
     #[test]
     fn alu_flags_overflow() {
-        let result = ALU::add(127, 1);
+        let result = ALU::add(127u8, 1);
         assert!(result.flags.is_set(Flags::Overflow));
 
-        let result = ALU::sub(0, 0);
+        let result = ALU::sub(0u8, 0);
         assert!(!result.flags.is_set(Flags::Overflow));
     }
+
+    #[test]
+    fn alu_sub_flags_overflow() {
+        // -128 - 1
+        let result = ALU::sub(0x80u8, 1);
+        assert!(result.flags.is_set(Flags::Overflow));
+
+        // 127 - -1
+        let result = ALU::sub(0x7fu8, 0xff);
+        assert!(result.flags.is_set(Flags::Overflow));
+
+        // -1 - -1
+        let result = ALU::sub(0xffu8, 0xff);
+        assert!(!result.flags.is_set(Flags::Overflow));
+
+        // -128 - -128 = 0, no signed overflow; the old rule of additions flagged it
+        let result = ALU::sub(0x80u8, 0x80);
+        assert!(!result.flags.is_set(Flags::Overflow));
+
+        let result = ALU::sbc(0x80u8, 0, true);
+        assert!(result.flags.is_set(Flags::Overflow));
+    }
+
+    #[test]
+    fn alu_adc_adds_carry() {
+        let result = ALU::adc(1u8, 1, false);
+        assert_eq!(result.value, 2);
+
+        let result = ALU::adc(1u8, 1, true);
+        assert_eq!(result.value, 3);
+        assert!(!result.flags.is_set(Flags::Carry));
+    }
+
+    #[test]
+    fn alu_adc_flags_carry() {
+        let result = ALU::adc(255u8, 0, true);
+        assert_eq!(result.value, 0);
+        assert!(result.flags.is_set(Flags::Carry));
+        assert!(result.flags.is_set(Flags::Zero));
+
+        let result = ALU::adc(255u8, 255, true);
+        assert_eq!(result.value, 255);
+        assert!(result.flags.is_set(Flags::Carry));
+
+        let result = ALU::adc(254u8, 0, true);
+        assert!(!result.flags.is_set(Flags::Carry));
+    }
+
+    #[test]
+    fn alu_adc_flags_overflow() {
+        let result = ALU::adc(127u8, 0, true);
+        assert!(result.flags.is_set(Flags::Overflow));
+        assert!(result.flags.is_set(Flags::Sign));
+    }
+
+    #[test]
+    fn alu_sbc_subtracts_borrow() {
+        let result = ALU::sbc(5u8, 2, false);
+        assert_eq!(result.value, 3);
+
+        let result = ALU::sbc(5u8, 2, true);
+        assert_eq!(result.value, 2);
+        assert!(!result.flags.is_set(Flags::Carry));
+    }
+
+    #[test]
+    fn alu_sbc_flags_carry() {
+        let result = ALU::sbc(0u8, 0, true);
+        assert_eq!(result.value, 255);
+        assert!(result.flags.is_set(Flags::Carry));
+        assert!(result.flags.is_set(Flags::Sign));
+
+        let result = ALU::sbc(1u8, 0, true);
+        assert!(result.flags.is_set(Flags::Zero));
+        assert!(!result.flags.is_set(Flags::Carry));
+    }
+
+    #[test]
+    fn alu_mul_keeps_high_byte() {
+        let result = ALU::mul(20u8, 30);
+        assert_eq!(result.value, 0x58);
+        assert!(result.flags.is_set(Flags::Carry));
+        assert!(result.flags.is_set(Flags::Overflow));
+
+        let result = ALU::mulh(20u8, 30);
+        assert_eq!(result.value, 0x02);
+
+        let result = ALU::mul(15u8, 17);
+        assert_eq!(result.value, 255);
+        assert!(!result.flags.is_set(Flags::Carry));
+        assert!(result.flags.is_set(Flags::Sign));
+
+        let result = ALU::mul(0u8, 17);
+        assert!(result.flags.is_set(Flags::Zero));
+    }
+
+    #[test]
+    fn alu_imul_is_signed() {
+        // -2 * 3 = -6 fits, the high byte is the sign extension
+        let result = ALU::imul(-2i8 as u8, 3);
+        assert_eq!(result.value, -6i8 as u8);
+        assert!(!result.flags.is_set(Flags::Overflow));
+        assert_eq!(ALU::imulh(-2i8 as u8, 3).value, 0xff);
+        assert_eq!(ALU::mulh(-2i8 as u8, 3).value, 0x02);
+
+        // -128 * -1 = 128 does not fit
+        let result = ALU::imul(0x80u8, 0xff);
+        assert_eq!(result.value, 0x80);
+        assert!(result.flags.is_set(Flags::Overflow));
+        assert_eq!(ALU::imulh(0x80u8, 0xff).value, 0x00);
+    }
+
+    #[test]
+    fn alu_div_and_rem() {
+        let result = ALU::div(203u8, 10).unwrap();
+        assert_eq!(result.value, 20);
+        assert_eq!(ALU::rem(203u8, 10).unwrap().value, 3);
+
+        let result = ALU::div(5u8, 10).unwrap();
+        assert!(result.flags.is_set(Flags::Zero));
+        assert!(!result.flags.is_set(Flags::Carry));
+
+        assert!(ALU::div(1u8, 0).is_none());
+        assert!(ALU::rem(1u8, 0).is_none());
+        assert!(ALU::idiv(1u8, 0).is_none());
+        assert!(ALU::irem(1u8, 0).is_none());
+    }
+
+    #[test]
+    fn alu_idiv_rounds_towards_zero() {
+        let result = ALU::idiv(-7i8 as u8, 2).unwrap();
+        assert_eq!(result.value, -3i8 as u8);
+        assert!(result.flags.is_set(Flags::Sign));
+        assert_eq!(ALU::irem(-7i8 as u8, 2).unwrap().value, -1i8 as u8);
+
+        // -128 / -1 wraps
+        let result = ALU::idiv(0x80u8, 0xff).unwrap();
+        assert_eq!(result.value, 0x80);
+        assert!(result.flags.is_set(Flags::Overflow));
+        assert_eq!(ALU::irem(0x80u8, 0xff).unwrap().value, 0);
+    }
+
+    #[test]
+    fn alu_shifts_carry_last_bit_out() {
+        let result = ALU::shl(0b0100_0001u8, 2);
+        assert_eq!(result.value, 0b0000_0100);
+        assert!(result.flags.is_set(Flags::Carry));
+
+        let result = ALU::shr(0b0000_0110u8, 2);
+        assert_eq!(result.value, 0b0000_0001);
+        assert!(result.flags.is_set(Flags::Carry));
+
+        let result = ALU::shr(0b0000_0101u8, 0);
+        assert_eq!(result.value, 0b0000_0101);
+        assert!(!result.flags.is_set(Flags::Carry));
+
+        let result = ALU::shl(0xffu8, 8);
+        assert_eq!(result.value, 0);
+        assert!(result.flags.is_set(Flags::Carry));
+        assert!(result.flags.is_set(Flags::Zero));
+        assert!(!ALU::shl(0xffu8, 9).flags.is_set(Flags::Carry));
+    }
+
+    #[test]
+    fn alu_asr_preserves_sign() {
+        let result = ALU::asr(0b1000_0010u8, 1);
+        assert_eq!(result.value, 0b1100_0001);
+        assert!(!result.flags.is_set(Flags::Carry));
+        assert!(result.flags.is_set(Flags::Sign));
+
+        assert_eq!(ALU::asr(0x40u8, 3).value, 0x08);
+        assert_eq!(ALU::asr(0x80u8, 12).value, 0xff);
+        assert!(ALU::asr(0x80u8, 12).flags.is_set(Flags::Carry));
+    }
+
+    #[test]
+    fn alu_rotates() {
+        let result = ALU::rol(0b1000_0001u8, 1);
+        assert_eq!(result.value, 0b0000_0011);
+        assert!(result.flags.is_set(Flags::Carry));
+
+        let result = ALU::ror(0b1000_0001u8, 1);
+        assert_eq!(result.value, 0b1100_0000);
+        assert!(result.flags.is_set(Flags::Carry));
+
+        let result = ALU::ror(0b0000_0010u8, 9);
+        assert_eq!(result.value, 0b0000_0001);
+        assert!(!result.flags.is_set(Flags::Carry));
+    }
+
+    #[test]
+    fn alu_rotates_through_carry() {
+        let result = ALU::rcl(0b1000_0000u8, 1, false);
+        assert_eq!(result.value, 0);
+        assert!(result.flags.is_set(Flags::Carry));
+
+        let result = ALU::rcl(0b0000_0000u8, 1, true);
+        assert_eq!(result.value, 1);
+        assert!(!result.flags.is_set(Flags::Carry));
+
+        let result = ALU::rcr(0b0000_0001u8, 1, true);
+        assert_eq!(result.value, 0b1000_0000);
+        assert!(result.flags.is_set(Flags::Carry));
+
+        // nine rotations of the nine bit value return to the start
+        let result = ALU::rcr(0x5au8, 9, true);
+        assert_eq!(result.value, 0x5a);
+        assert!(result.flags.is_set(Flags::Carry));
+    }
 }
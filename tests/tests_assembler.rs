@@ -0,0 +1,116 @@
+#[cfg(test)]
+mod tests {
+    use mrt_cpu::{
+        compiler::compiler::{CompileError, Compiler},
+        types::Directive,
+    };
+
+    fn assemble(source: &str) -> Result<Vec<u8>, CompileError> {
+        let mut output = vec![];
+        Compiler::new(source.as_bytes(), &mut output).compile()?;
+
+        Ok(output)
+    }
+
+    #[test]
+    fn assembler_resolves_backward_label() {
+        let output = assemble("start:\nHLT\nLDI r0 start\n").unwrap();
+        assert_eq!(output, [0x00, 0x10, 0x00]);
+    }
+
+    #[test]
+    fn assembler_resolves_forward_label() {
+        let output = assemble("LDI r0 hi(end)\nLDI r1 lo(end)\nend:\nHLT\n").unwrap();
+        assert_eq!(output, [0x10, 0x00, 0x11, 0x04, 0x00]);
+    }
+
+    #[test]
+    fn assembler_label_may_look_like_register() {
+        let output = assemble("LDI r0 rel\nrel:\nHLT\n").unwrap();
+        assert_eq!(output, [0x10, 0x02, 0x00]);
+    }
+
+    #[test]
+    fn assembler_errors_on_unknown_label() {
+        let result = assemble("LDI r0 nowhere\n");
+        assert!(matches!(result, Err(CompileError::UnknownLabel(name)) if name == "nowhere"));
+    }
+
+    #[test]
+    fn assembler_errors_on_duplicate_label() {
+        let result = assemble("twice:\nHLT\ntwice:\nHLT\n");
+        assert!(matches!(result, Err(CompileError::DuplicateLabel(name)) if name == "twice"));
+    }
+
+    #[test]
+    fn assembler_errors_on_unknown_selector() {
+        let result = assemble("LDI r0 mid(x)\nx:\n");
+        assert!(matches!(result, Err(CompileError::UnknownSymbol(name)) if name == "mid"));
+    }
+
+    #[test]
+    fn assembler_emits_raw_bytes() {
+        let output = assemble(".db 1, 2 0xff \"ab\"\n").unwrap();
+        assert_eq!(output, [1, 2, 0xff, b'a', b'b']);
+    }
+
+    #[test]
+    fn assembler_emits_words_most_significant_byte_first() {
+        let output = assemble(".dw 0x1234 7 end\nend:\n").unwrap();
+        assert_eq!(output, [0x12, 0x34, 0x00, 0x07, 0x00, 0x06]);
+    }
+
+    #[test]
+    fn assembler_emits_words_of_selected_word_size() {
+        let output = assemble(".word 0x12\n.wordsize 16\n.word 0x1234 0x56\n").unwrap();
+        assert_eq!(output, [0x12, 0x12, 0x34, 0x00, 0x56]);
+
+        assert!(matches!(
+            assemble(".word 0x1234\n"),
+            Err(CompileError::InvalidDirectiveArguments(Directive::Word))
+        ));
+        assert!(matches!(
+            assemble(".wordsize 12\n"),
+            Err(CompileError::InvalidDirectiveArguments(Directive::WordSize))
+        ));
+    }
+
+    #[test]
+    fn assembler_emits_strings() {
+        let output = assemble(".ascii \"a#\\\"\"\n.asciiz \"\\n\"\n").unwrap();
+        assert_eq!(output, [b'a', b'#', b'"', b'\n', 0]);
+    }
+
+    #[test]
+    fn assembler_fills_and_aligns() {
+        let output = assemble(".fill 3 0xaa\n.align 4\n.fill 2\nHLT\n").unwrap();
+        assert_eq!(output, [0xaa, 0xaa, 0xaa, 0, 0, 0, 0x00]);
+    }
+
+    #[test]
+    fn assembler_org_moves_output_and_labels() {
+        let output = assemble("LDI r0 lo(data)\n.org 0x08\ndata:\n.db 42\n").unwrap();
+        assert_eq!(output, [0x10, 0x08, 0, 0, 0, 0, 0, 0, 42]);
+    }
+
+    #[test]
+    fn assembler_errors_on_org_backwards() {
+        let result = assemble("HLT\nHLT\n.org 1\n");
+        assert!(matches!(result, Err(CompileError::InvalidOrigin(1))));
+    }
+
+    #[test]
+    fn assembler_errors_on_unknown_directive() {
+        let result = assemble(".byte 1\n");
+        assert!(matches!(result, Err(CompileError::UnknownDirective(name)) if name == ".byte"));
+    }
+
+    #[test]
+    fn assembler_errors_on_label_as_layout_argument() {
+        let result = assemble(".fill count\ncount:\n");
+        assert!(matches!(
+            result,
+            Err(CompileError::InvalidDirectiveArguments(Directive::Fill))
+        ));
+    }
+}
@@ -0,0 +1,92 @@
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use mrt_cpu::batch::{self, Status};
+
+    // Writes the source to a file of its own in the temporary directory, the path has no extension
+    fn source_file(name: &str, source: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("mrt_cpu_batch_{}", name));
+        std::fs::write(path.with_extension("asm"), source).unwrap();
+        path
+    }
+
+    fn run(args: &[&str]) -> Option<Status> {
+        batch::run(&args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>())
+    }
+
+    fn assemble(name: &str, source: &str) -> String {
+        let path = source_file(name, source);
+        let input = path.with_extension("asm").display().to_string();
+        assert_eq!(run(&["asm", &input]), Some(Status::Success));
+
+        path.with_extension("rom").display().to_string()
+    }
+
+    #[test]
+    fn batch_asm_writes_rom_and_listing() {
+        let path = source_file("asm", "LDI r1 2\nHLT");
+        let input = path.with_extension("asm").display().to_string();
+        let output = path.with_extension("bin").display().to_string();
+
+        assert_eq!(
+            run(&["asm", &input, "-o", &output, "--listing"]),
+            Some(Status::Success)
+        );
+        assert_eq!(std::fs::read(&output).unwrap(), vec![0x11, 0x02, 0x00]);
+        assert!(PathBuf::from(output + ".lst").exists());
+    }
+
+    #[test]
+    fn batch_asm_reports_compilation_error() {
+        let path = source_file("error", "FOO r1");
+        let input = path.with_extension("asm").display().to_string();
+
+        assert_eq!(run(&["asm", &input]), Some(Status::CompilationError));
+        assert_eq!(run(&["asm", "/nonexistent/input.asm"]), Some(Status::Usage));
+    }
+
+    #[test]
+    fn batch_run_exit_status() {
+        let halts = assemble("halts", "LDI r1 1\nHLT");
+        assert_eq!(run(&["run", &halts]), Some(Status::Success));
+
+        let faults = assemble("faults", "DIV r1 r1 r0\nHLT");
+        assert_eq!(run(&["run", &faults]), Some(Status::Fault));
+
+        // the step limit is checked in between writing the serial output
+        let loops = assemble("loops", "loop: JMP loop");
+        assert_eq!(
+            run(&["run", &loops, "--max-steps", "0x800", "--word-size", "16"]),
+            Some(Status::Timeout)
+        );
+        assert_eq!(
+            run(&["run", &halts, "--ram-size", "16", "--max-steps", "10"]),
+            Some(Status::Success)
+        );
+    }
+
+    #[test]
+    fn batch_disasm_reads_rom() {
+        let rom = assemble("disasm", "JMP end\nend: HLT");
+        assert_eq!(run(&["disasm", &rom]), Some(Status::Success));
+        assert_eq!(run(&["disasm", &rom, "--raw"]), Some(Status::Success));
+    }
+
+    #[test]
+    fn batch_arguments() {
+        assert_eq!(run(&[]), None);
+        assert_eq!(run(&["--word-size", "16"]), None);
+        assert_eq!(run(&["help"]), Some(Status::Success));
+
+        assert_eq!(run(&["compile"]), Some(Status::Usage));
+        assert_eq!(run(&["run"]), Some(Status::Usage));
+        assert_eq!(run(&["run", "a.rom", "b.rom"]), Some(Status::Usage));
+        assert_eq!(run(&["run", "a.rom", "--max-steps"]), Some(Status::Usage));
+        assert_eq!(
+            run(&["run", "a.rom", "--max-steps", "x"]),
+            Some(Status::Usage)
+        );
+        assert_eq!(run(&["disasm", "a.rom", "--fold"]), Some(Status::Usage));
+    }
+}
@@ -0,0 +1,115 @@
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use mrt_cpu::{
+        compiler::instruction::Instruction,
+        machine::{
+            computer::System,
+            flags::{Flags, FlagsRegister},
+        },
+        new_compiler,
+        types::{BranchCondition, Opcode},
+    };
+
+    // Compares `a` with `b` and reports whether `branch` was taken
+    fn taken(branch: &str, a: u8, b: u8) -> bool {
+        let source = format!(
+            "
+            LDI r1 {a}
+            LDI r2 {b}
+            LDI r3 hi(taken)
+            LDI r4 lo(taken)
+            CMP r1 r2
+            {branch} r3 r4
+            LDI r0 0
+            HLT
+            taken:
+            LDI r0 1
+            HLT
+        "
+        );
+        let rom = new_compiler::compile(source.as_bytes(), Path::new("test.asm")).unwrap();
+
+        let mut sys = System::new(0);
+        sys.load_rom(*rom).unwrap();
+        while !sys.tick() {}
+        sys.get_regs()[0] == 1
+    }
+
+    #[test]
+    fn branch_conditions_round_trip() {
+        for value in 0..=13 {
+            let condition = BranchCondition::try_from(value).unwrap();
+            let opcode = Opcode::from_branch_condition(condition);
+            assert_eq!(opcode.branch_condition(), Some(condition));
+            assert_eq!(opcode.cycles(), 3);
+
+            let bytes =
+                Instruction::DoubleReg(opcode, 1.try_into().unwrap(), 2.try_into().unwrap())
+                    .serialize();
+            assert_eq!(bytes, [0x51, 0x20 | value]);
+            assert_eq!(
+                Instruction::disassemble(&bytes).unwrap().to_string(),
+                format!("{:?} R1 R2", opcode)
+            );
+        }
+
+        assert!(BranchCondition::try_from(14).is_err());
+        assert_eq!(Opcode::JC.branch_condition(), None);
+    }
+
+    #[test]
+    fn branch_conditions_follow_flags() {
+        let mut flags = FlagsRegister::new();
+        assert!(flags.satisfies(BranchCondition::NotZero));
+        assert!(flags.satisfies(BranchCondition::GreaterOrEqual));
+        assert!(flags.satisfies(BranchCondition::Always));
+        assert!(!flags.satisfies(BranchCondition::Sign));
+
+        flags.set(Flags::Sign);
+        assert!(flags.satisfies(BranchCondition::Less));
+        flags.set(Flags::Overflow);
+        assert!(!flags.satisfies(BranchCondition::Less));
+        assert!(flags.satisfies(BranchCondition::Greater));
+        assert!(flags.satisfies(BranchCondition::Overflow));
+
+        flags.set(Flags::Zero);
+        assert!(flags.satisfies(BranchCondition::LessOrEqual));
+        assert!(flags.satisfies(BranchCondition::BelowOrEqual));
+        assert!(!flags.satisfies(BranchCondition::Above));
+    }
+
+    #[test]
+    fn branch_signed_comparisons() {
+        // -128 < 1, although it is the bigger unsigned value
+        assert!(taken("JL", 0x80, 1));
+        assert!(!taken("JGE", 0x80, 1));
+        assert!(taken("JG", 1, 0xff));
+        assert!(taken("JLE", 0xff, 0xff));
+        assert!(!taken("JG", 5, 5));
+        assert!(taken("JGE", 0x7f, 0x80));
+    }
+
+    #[test]
+    fn branch_unsigned_comparisons() {
+        assert!(taken("JA", 0x80, 1));
+        assert!(!taken("JBE", 0x80, 1));
+        assert!(taken("JB", 1, 0xff));
+        assert!(taken("JAE", 5, 5));
+        assert!(taken("JBE", 5, 5));
+        assert!(!taken("JA", 5, 5));
+    }
+
+    #[test]
+    fn branch_on_single_flags() {
+        assert!(taken("JZ", 3, 3));
+        assert!(!taken("JNZ", 3, 3));
+        assert!(taken("JNC", 3, 3));
+        assert!(taken("JS", 1, 2));
+        assert!(taken("JNS", 2, 1));
+        assert!(taken("JO", 0x80, 1));
+        assert!(taken("JNO", 0x80, 0x80));
+        assert!(taken("JMP", 0, 0));
+    }
+}
@@ -0,0 +1,259 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::any::Any;
+
+    use mrt_cpu::machine::{
+        bus::{Bus, BusError, Device, MemoryRegion},
+        computer::System,
+        debugger::StopReason,
+        devices::serial::{InputSource, Serial},
+        storage::RAM,
+    };
+
+    use crate::common::system;
+
+    // Remembers the last write, reads return the offset
+    #[derive(Default)]
+    struct Register {
+        last_write: Option<(u16, u8)>,
+    }
+
+    impl Device for Register {
+        fn name(&self) -> &'static str {
+            "register"
+        }
+
+        fn size(&self) -> u16 {
+            4
+        }
+
+        fn read(&mut self, offset: u16) -> u8 {
+            offset as u8
+        }
+
+        fn write(&mut self, offset: u16, value: u8) {
+            self.last_write = Some((offset, value));
+        }
+
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+
+        fn as_any_mut(&mut self) -> &mut dyn Any {
+            self
+        }
+    }
+
+    #[test]
+    fn bus_routes_accesses_to_devices() {
+        let mut bus = Bus::new(RAM::new(0x100));
+        bus.attach(0x10, Box::new(Register::default())).unwrap();
+
+        assert_eq!(bus.read(0x12).unwrap(), 2);
+        bus.write(0x13, 0xaa).unwrap();
+        assert_eq!(
+            bus.device::<Register>().unwrap().last_write,
+            Some((3, 0xaa))
+        );
+
+        // addresses around the device are ram
+        bus.write(0x14, 0xbb).unwrap();
+        assert_eq!(bus.read(0x14).unwrap(), 0xbb);
+        assert!(bus.is_mapped(0x10));
+        assert!(!bus.is_mapped(0x0f));
+
+        assert!(bus.read(0x100).is_err());
+    }
+
+    #[test]
+    fn bus_rejects_overlapping_devices() {
+        let mut bus = Bus::new(RAM::new(0x100));
+        bus.attach(0x10, Box::new(Register::default())).unwrap();
+
+        assert!(matches!(
+            bus.attach(0x13, Box::new(Serial::new())),
+            Err(BusError::Overlap("register"))
+        ));
+        assert!(matches!(
+            bus.attach(0x0d, Box::new(Register::default())),
+            Err(BusError::Overlap("register"))
+        ));
+        assert!(matches!(
+            bus.attach(0xfffe, Box::new(Register::default())),
+            Err(BusError::OutOfRange)
+        ));
+
+        assert!(bus.attach(0x14, Box::new(Serial::new())).is_ok());
+    }
+
+    #[test]
+    fn bus_detaches_device() {
+        let mut bus = Bus::new(RAM::new(0x100));
+        bus.attach(0x10, Box::new(Register::default())).unwrap();
+
+        assert!(bus.detach("register").is_some());
+        assert!(bus.detach("register").is_none());
+        assert!(bus.device::<Register>().is_none());
+        assert!(!bus.is_mapped(0x10));
+    }
+
+    #[test]
+    fn bus_describes_memory_map() {
+        let sys = System::new(0x100);
+        assert_eq!(
+            sys.bus().memory_map().regions,
+            vec![
+                MemoryRegion {
+                    start: 0,
+                    end: 1,
+                    name: "serial"
+                },
+                MemoryRegion {
+                    start: 2,
+                    end: 3,
+                    name: "interrupt"
+                },
+                MemoryRegion {
+                    start: 4,
+                    end: 7,
+                    name: "timer"
+                },
+                MemoryRegion {
+                    start: 0,
+                    end: 0xff,
+                    name: "ram"
+                },
+            ]
+        );
+
+        assert_eq!(
+            sys.bus().memory_map().to_string(),
+            "0x0000-0x0001 serial\n0x0002-0x0003 interrupt\n0x0004-0x0007 timer\n0x0000-0x00ff ram\n"
+        );
+    }
+
+    #[test]
+    fn bus_captures_serial_output() {
+        let source = std::fs::read_to_string("programs/helloworld.asm").unwrap();
+        let mut sys = system(&source, 256);
+
+        assert_eq!(sys.run(None, || false), StopReason::Halted);
+
+        let serial = sys.bus_mut().device_mut::<Serial>().unwrap();
+        assert_eq!(serial.output(), b"HELLO WORLD");
+        assert_eq!(serial.take_output(), b"HELLO WORLD");
+        assert!(serial.output().is_empty());
+    }
+
+    #[test]
+    fn bus_device_writes_bypass_ram() {
+        let mut sys = system(
+            "
+            LDI r0 0
+            LDI r1 0x41
+            SB r1 r0 r0
+            HLT
+        ",
+            256,
+        );
+
+        let first_byte = sys.get_mem(0);
+        assert_eq!(sys.run(None, || false), StopReason::Halted);
+
+        // ram below the serial port still holds the first instruction
+        assert_eq!(sys.get_mem(0), first_byte);
+        assert_eq!(sys.bus().device::<Serial>().unwrap().output(), b"A");
+
+        // writes to devices can not be undone
+        sys.step_back().unwrap();
+        assert_eq!(sys.bus().device::<Serial>().unwrap().output(), b"A");
+    }
+
+    // Hands out the given lines one at a time, taking the output like a terminal would
+    struct Lines {
+        lines: Vec<&'static str>,
+    }
+
+    impl InputSource for Lines {
+        fn receive(&mut self, output: &mut Vec<u8>) -> Option<Vec<u8>> {
+            output.clear();
+
+            if self.lines.is_empty() {
+                return None;
+            }
+
+            Some(self.lines.remove(0).as_bytes().to_vec())
+        }
+    }
+
+    #[test]
+    fn bus_serial_reports_received_data() {
+        let mut serial = Serial::new();
+        assert_eq!(serial.read(Serial::STATUS), 0);
+        assert_eq!(serial.read(Serial::DATA), 0);
+
+        serial.push_input(b"ab");
+        assert_eq!(serial.read(Serial::STATUS), Serial::STATUS_DATA_AVAILABLE);
+        assert_eq!(serial.read(Serial::DATA), b'a');
+        assert_eq!(serial.read(Serial::DATA), b'b');
+        assert_eq!(serial.read(Serial::STATUS), 0);
+        assert_eq!(serial.pending_input(), 0);
+    }
+
+    #[test]
+    fn bus_serial_echoes_queued_input() {
+        let source = std::fs::read_to_string("programs/echo.asm").unwrap();
+        let mut sys = system(&source, 256);
+
+        let serial = sys.bus_mut().device_mut::<Serial>().unwrap();
+        serial.push_input(b"echo\nignored");
+
+        assert_eq!(sys.run(Some(10_000), || false), StopReason::Halted);
+
+        let serial = sys.bus_mut().device_mut::<Serial>().unwrap();
+        assert_eq!(serial.output(), b"echo\n");
+        assert_eq!(serial.pending_input(), 7);
+    }
+
+    #[test]
+    fn bus_serial_reads_from_input_source() {
+        let source = std::fs::read_to_string("programs/echo.asm").unwrap();
+        let mut sys = system(&source, 256);
+
+        let serial = sys.bus_mut().device_mut::<Serial>().unwrap();
+        serial.push_input(b"ab");
+        serial.set_input_source(Some(Box::new(Lines {
+            lines: vec!["c", "d\n"],
+        })));
+
+        assert_eq!(sys.run(Some(10_000), || false), StopReason::Halted);
+
+        // the source took the output written before it was polled once the last line was read
+        let serial = sys.bus_mut().device_mut::<Serial>().unwrap();
+        assert_eq!(serial.take_output(), b"\n");
+    }
+
+    #[test]
+    fn bus_serial_drops_exhausted_input_source() {
+        let mut serial = Serial::new();
+        serial.set_input_source(Some(Box::new(Lines { lines: vec!["x"] })));
+
+        assert_eq!(serial.read(Serial::DATA), b'x');
+        assert_eq!(serial.read(Serial::STATUS), 0);
+        assert_eq!(serial.read(Serial::STATUS), 0);
+    }
+
+    #[test]
+    fn bus_serial_status_does_not_wait_for_input_source() {
+        let mut serial = Serial::new();
+        serial.set_input_source(Some(Box::new(Lines { lines: vec!["x"] })));
+
+        assert_eq!(serial.read(Serial::STATUS), 0);
+        assert_eq!(serial.pending_input(), 0);
+
+        serial.tick(1);
+        assert_eq!(serial.read(Serial::STATUS), Serial::STATUS_DATA_AVAILABLE);
+    }
+}
@@ -0,0 +1,154 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::{atomic::AtomicBool, Arc};
+
+    use mrt_cpu::{
+        cli::{
+            command::{Arguments, Command, Outcome, Parameter, ParameterKind, Registry},
+            Cli, CliError,
+        },
+        machine::word::{Word, WordSize},
+    };
+
+    fn cli() -> Cli {
+        Cli::new(Arc::new(AtomicBool::new(false)))
+    }
+
+    // Writes a byte, registered by the tests like a library user would
+    struct Poke {
+        parameters: Vec<Parameter>,
+    }
+
+    impl Poke {
+        fn new() -> Self {
+            Self {
+                parameters: vec![
+                    Parameter::required("address", ParameterKind::Number),
+                    Parameter::required("value", ParameterKind::Number),
+                ],
+            }
+        }
+    }
+
+    impl<W: Word> Command<W> for Poke {
+        fn name(&self) -> &str {
+            "poke"
+        }
+
+        fn aliases(&self) -> &[&str] {
+            &["pk"]
+        }
+
+        fn parameters(&self) -> &[Parameter] {
+            &self.parameters
+        }
+
+        fn help(&self) -> &str {
+            "write a byte"
+        }
+
+        fn execute(&self, cli: &mut Cli<W>, arguments: &Arguments) -> Result<Outcome, CliError> {
+            let address = arguments.get(0).unwrap().parse::<u16>().unwrap();
+            let value = arguments.get(1).unwrap().parse::<u8>().unwrap();
+
+            match cli.system_mut().set_mem(address, value) {
+                Ok(()) => Ok(Outcome::Continue),
+                Err(_) => Err(CliError::OperationError),
+            }
+        }
+    }
+
+    #[test]
+    fn command_arguments_are_checked_against_parameters() {
+        let parameters = [
+            Parameter::required("address", ParameterKind::Number),
+            Parameter::optional("mode", ParameterKind::Choice(&["r", "w"])),
+            Parameter::flag("--all"),
+        ];
+
+        let arguments = Arguments::parse(&["x", "--all", "0x10", "r"], &parameters).unwrap();
+        assert_eq!(arguments.command(), ["x", "0x10", "r"]);
+        assert!(arguments.has_flag("--all"));
+
+        assert!(matches!(
+            Arguments::parse(&["x"], &parameters),
+            Err(CliError::MissingParameter("address"))
+        ));
+        assert!(matches!(
+            Arguments::parse(&["x", "y"], &parameters),
+            Err(CliError::InvalidParameterType("address", _))
+        ));
+        assert!(matches!(
+            Arguments::parse(&["x", "1", "rw"], &parameters),
+            Err(CliError::FailedParameterConstraint("mode"))
+        ));
+        assert!(matches!(
+            Arguments::parse(&["x", "1", "r", "2"], &parameters),
+            Err(CliError::UnexpectedParameter(argument)) if argument == "2"
+        ));
+    }
+
+    #[test]
+    fn command_text_takes_remaining_arguments() {
+        let parameters = [Parameter::optional("text", ParameterKind::Text)];
+        let arguments = Arguments::parse(&["echo", "a", "b"], &parameters).unwrap();
+
+        assert_eq!(arguments.command(), ["echo", "a", "b"]);
+    }
+
+    #[test]
+    fn command_registry_dispatches_by_name_and_alias() {
+        let registry = Registry::<u8>::new();
+        let mut cli = cli();
+
+        assert_eq!(
+            registry.execute(&mut cli, &["quit"]).unwrap(),
+            Outcome::Exit
+        );
+        assert_eq!(
+            registry.execute(&mut cli, &["word_size", "16"]).unwrap(),
+            Outcome::WordSize(WordSize::Bits16)
+        );
+        assert!(matches!(
+            registry.execute(&mut cli, &["frobnicate"]),
+            Err(CliError::UnrecognizedCommand(_))
+        ));
+        assert!(matches!(
+            registry.execute(&mut cli, &["compile", "in.asm", "--listing"]),
+            Err(CliError::FailedParameterConstraint(_))
+        ));
+    }
+
+    #[test]
+    fn command_help_is_generated() {
+        let registry = Registry::<u8>::new();
+        let help = registry.help(None).unwrap();
+
+        assert!(help.contains("    load_rom, lr [rom_file] - load a rom\n"));
+        assert!(help.contains("    compile, com [file] <out> <--new> <--listing> - "));
+        assert_eq!(help.lines().count(), registry.iter().count() + 2);
+
+        let help = registry.help(Some("bk")).unwrap();
+        assert!(help.starts_with("back, bk <step_count>\n"));
+        assert!(help.contains("<step_count> - decimal or hexadecimal number"));
+
+        assert!(registry.help(Some("frobnicate")).is_err());
+    }
+
+    #[test]
+    fn command_registered_by_user() {
+        let mut registry = Registry::<u8>::new();
+        registry.register(Box::new(Poke::new()));
+
+        let mut cli = cli();
+        registry.execute(&mut cli, &["pk", "3", "7"]).unwrap();
+        assert_eq!(cli.system().get_mem(3), Ok(7));
+
+        let help = registry.help(None).unwrap();
+        assert!(help.contains("poke, pk [address] [value] - write a byte"));
+        assert!(matches!(
+            registry.execute(&mut cli, &["poke", "3"]),
+            Err(CliError::MissingParameter("value"))
+        ));
+    }
+}
@@ -2,9 +2,20 @@
 mod tests {
     use std::path::Path;
 
-    use mrt_cpu::new_compiler::{
-        error::{Position, TokenTypeConversionError, TokenizationError},
-        token::{tokenize, Token, TokenType},
+    use mrt_cpu::{
+        compiler::{compiler::Compiler, instruction::Instruction},
+        new_compiler::{
+            ast::{OperandKind, StatementKind},
+            compile as compile_stream, compile_with_diagnostics,
+            diagnostic::Severity,
+            error::{
+                CodeGenerationError, CompilationError, ParseError, Position,
+                TokenTypeConversionError, TokenizationError,
+            },
+            parser::parse,
+            token::{tokenize, Token, TokenType},
+        },
+        types::{Directive, LabelSelector, Opcode},
     };
 
     fn create_mock_position() -> Position {
@@ -36,6 +47,20 @@ mod tests {
         assert_eq!(token.ttype(), TokenType::Symbol);
     }
 
+    #[test]
+    fn compiler_can_tokenize_symbol_with_underscore() {
+        let mut token = Token::new(create_mock_position());
+
+        for c in "_my_label2".chars() {
+            assert!(token.take(c).is_ok());
+        }
+
+        assert_eq!(token.ttype(), TokenType::Symbol);
+
+        let rom = compile_stream("my_label:\nJMP my_label".as_bytes(), Path::new("test.asm"));
+        assert!(rom.is_ok());
+    }
+
     #[test]
     fn compiler_can_tokenize_number() {
         let mut token = Token::new(create_mock_position());
@@ -71,11 +96,11 @@ mod tests {
     fn compiler_errors_on_tokenizing_unknown_character() {
         let mut token = Token::new(create_mock_position());
 
-        let result = token.take('_'); // '_' is not a recognized character at the moment
+        let result = token.take('@');
         assert!(result.is_err());
 
         let error = result.err().unwrap();
-        assert_eq!(error, TokenTypeConversionError::UnknownCharacter('_'));
+        assert_eq!(error, TokenTypeConversionError::UnknownCharacter('@'));
         assert_eq!(token.ttype(), TokenType::Unknown); // Not yet set
     }
 
@@ -175,9 +200,9 @@ mod tests {
     #[test]
     fn compiler_fails_tokenizing_and_shows_correct_position() {
         let path = Path::new("");
-        let input_stream = "123_".as_bytes();
+        let input_stream = "123@".as_bytes();
 
-        // should tokenize as Number { 123 }, with an error at '_'
+        // should tokenize as Number { 123 }, with an error at '@'
         let result = tokenize(&input_stream, path);
         assert!(result.is_err());
 
@@ -189,7 +214,7 @@ mod tests {
             assert_eq!(column, 4);
 
             if let TokenTypeConversionError::UnknownCharacter(c) = conversion_error {
-                assert_eq!(c, '_');
+                assert_eq!(c, '@');
             } else {
                 panic!();
             }
@@ -197,4 +222,278 @@ mod tests {
             panic!()
         };
     }
+
+    fn compile(source: &str) -> Result<Vec<u8>, CompilationError> {
+        Ok(*compile_stream(source.as_bytes(), Path::new(""))?)
+    }
+
+    #[test]
+    fn compiler_tokenizer_tokenizes_hexadecimal_letters() {
+        let tokens = tokenize("0xff 0x1A".as_bytes(), Path::new("")).unwrap();
+
+        assert_eq!(tokens.len(), 3);
+        assert_eq!(tokens[0].ttype(), TokenType::Number);
+        assert_eq!(tokens[0].text(), "0xff");
+        assert_eq!(tokens[2].text(), "0x1A");
+    }
+
+    #[test]
+    fn compiler_tokenizer_tokenizes_string_with_escaped_quote() {
+        let tokens = tokenize("\"a \\\" #\":".as_bytes(), Path::new("")).unwrap();
+
+        assert_eq!(tokens.len(), 2);
+        assert_eq!(tokens[0].ttype(), TokenType::String);
+        assert_eq!(tokens[1].ttype(), TokenType::Punctuation);
+    }
+
+    #[test]
+    fn compiler_tokenizer_splits_punctuation() {
+        let tokens = tokenize("hi(a),,".as_bytes(), Path::new("")).unwrap();
+
+        let types = tokens.iter().map(|token| token.ttype()).collect::<Vec<_>>();
+        assert_eq!(
+            types,
+            [
+                TokenType::Symbol,
+                TokenType::Punctuation,
+                TokenType::Symbol,
+                TokenType::Punctuation,
+                TokenType::Punctuation,
+                TokenType::Punctuation
+            ]
+        );
+    }
+
+    #[test]
+    fn compiler_token_position_is_first_character() {
+        let tokens = tokenize("HLT\n  LDI".as_bytes(), Path::new("")).unwrap();
+
+        assert_eq!(tokens[0].position().get_line_info(), (0, 1));
+        assert_eq!(tokens[2].position().get_line_info(), (1, 3));
+    }
+
+    #[test]
+    fn compiler_parses_statements() {
+        let tokens = tokenize(
+            "start: LDI r1, lo(start) # load\n.db \"x\"\n".as_bytes(),
+            Path::new(""),
+        );
+        let program = parse(*tokens.unwrap()).unwrap();
+
+        assert_eq!(program.statements.len(), 3);
+        assert!(
+            matches!(&program.statements[0].kind, StatementKind::Label(name) if name == "start")
+        );
+        assert!(matches!(
+            &program.statements[1].kind,
+            StatementKind::Instruction(Opcode::LDI, operands) if matches!(
+                operands[1].kind,
+                OperandKind::Label(_, LabelSelector::Low)
+            )
+        ));
+        assert!(matches!(
+            &program.statements[2].kind,
+            StatementKind::Directive(Directive::Db, operands) if operands.len() == 1
+        ));
+
+        assert_eq!(program.statements[1].position.get_line_info(), (0, 8));
+        assert_eq!(program.statements[2].position.get_line_info(), (1, 1));
+    }
+
+    #[test]
+    fn compiler_parser_errors_on_unknown_instruction() {
+        let result = compile("\n  NOP\n");
+        assert!(matches!(
+            result,
+            Err(CompilationError::Parser(ParseError::UnknownInstruction(position, name)))
+                if name == "NOP" && position.get_line_info() == (1, 3)
+        ));
+    }
+
+    #[test]
+    fn compiler_parser_errors_on_trailing_token() {
+        let result = compile("HLT: :\n");
+        assert!(matches!(
+            result,
+            Err(CompilationError::Parser(ParseError::UnexpectedToken(_, text))) if text == ":"
+        ));
+    }
+
+    #[test]
+    fn compiler_generates_all_registers() {
+        let output = compile("LDI r15 1\nTRIPLE:\nADD r11 r12 r13\n").unwrap();
+        assert_eq!(output, [0x1f, 0x01, 0x2b, 0xcd]);
+    }
+
+    #[test]
+    fn compiler_generates_labels_and_directives() {
+        let output = compile("LDI r0 hi(end)\n.org 4\n.dw end\nend: .asciiz \"a\"").unwrap();
+        assert_eq!(output, [0x10, 0x00, 0, 0, 0x00, 0x06, b'a', 0]);
+    }
+
+    #[test]
+    fn compiler_codegen_errors_on_operand_count() {
+        let result = compile("ADD r0 r1\n");
+        assert!(matches!(
+            result,
+            Err(CompilationError::CodeGeneration(
+                CodeGenerationError::OperandCount(_, 3, 2)
+            ))
+        ));
+    }
+
+    #[test]
+    fn compiler_codegen_errors_on_imm4_out_of_range() {
+        let result = compile("SHL r0 r1 16\n");
+        assert!(matches!(
+            result,
+            Err(CompilationError::CodeGeneration(
+                CodeGenerationError::ValueOutOfRange(_, 16, 15)
+            ))
+        ));
+    }
+
+    #[test]
+    fn compiler_codegen_errors_on_unknown_label() {
+        let result = compile("LDI r0 lo(nowhere)\n");
+        assert!(matches!(
+            result,
+            Err(CompilationError::CodeGeneration(CodeGenerationError::UnknownLabel(_, name)))
+                if name == "nowhere"
+        ));
+    }
+
+    #[test]
+    fn compiler_encodes_carry_arithmetic_as_extended_instructions() {
+        let output = compile("ADC r1 r2 r3\nSBC r4 r5 r6\nCMP r7 r8\n").unwrap();
+        assert_eq!(
+            output,
+            [0xf1, 0x03, 0x23, 0xf4, 0x04, 0x56, 0xf7, 0x05, 0x80]
+        );
+
+        let mut expected = vec![];
+        Compiler::new(
+            "ADC r1 r2 r3\nSBC r4 r5 r6\nCMP r7 r8\n".as_bytes(),
+            &mut expected,
+        )
+        .compile()
+        .unwrap();
+        assert_eq!(output, expected);
+
+        let disassembled = [0, 3, 6].map(|offset| {
+            Instruction::disassemble(&output[offset..])
+                .unwrap()
+                .to_string()
+        });
+        assert_eq!(disassembled, ["ADC R1 R2 R3", "SBC R4 R5 R6", "CMP R7 R8"]);
+
+        assert!(Instruction::disassemble(&output[..2]).is_err());
+    }
+
+    #[test]
+    fn compiler_encodes_branch_condition_in_jnz() {
+        let source = "JNZ r1 r2\nJGE r3 r4\nJMP r5 r6\nJB r7 r8\n";
+        let output = compile(source).unwrap();
+        assert_eq!(output, [0x51, 0x20, 0x53, 0x48, 0x55, 0x6d, 0xb7, 0x80]);
+
+        let mut expected = vec![];
+        Compiler::new(source.as_bytes(), &mut expected)
+            .compile()
+            .unwrap();
+        assert_eq!(output, expected);
+
+        let disassembled = [0, 2, 4, 6].map(|offset| {
+            Instruction::disassemble(&output[offset..])
+                .unwrap()
+                .to_string()
+        });
+        assert_eq!(
+            disassembled,
+            ["JNZ R1 R2", "JGE R3 R4", "JMP R5 R6", "JC R7 R8"]
+        );
+
+        assert!(Instruction::disassemble(&[0x51, 0x2e]).is_err());
+    }
+
+    #[test]
+    fn compiler_encodes_rotates_as_extended_instructions() {
+        let source = "ROL r1 r2 3\nRCRV r4 r5 r6\n";
+        let output = compile(source).unwrap();
+        assert_eq!(output, [0xf1, 0x12, 0x23, 0xf4, 0x1d, 0x56]);
+
+        let disassembled = [0, 3].map(|offset| {
+            Instruction::disassemble(&output[offset..])
+                .unwrap()
+                .to_string()
+        });
+        assert_eq!(disassembled, ["ROL R1 R2 3", "RCRV R4 R5 R6"]);
+
+        assert!(compile("ROL r1 r2 16\n").is_err());
+    }
+
+    #[test]
+    fn compiler_output_matches_old_compiler() {
+        for program in ["helloworld.asm", "sandbox.asm"] {
+            let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+                .join("programs")
+                .join(program);
+            let source = std::fs::read(&path).unwrap();
+
+            let mut expected = vec![];
+            Compiler::new(source.as_slice(), &mut expected)
+                .compile()
+                .unwrap();
+
+            let output = *compile_stream(&source, &path).unwrap();
+            assert_eq!(output, expected, "{}", program);
+        }
+    }
+
+    #[test]
+    fn compiler_collects_multiple_errors() {
+        let (output, diagnostics) =
+            compile_with_diagnostics("LDI r0 300\nADD r0 r1\nHLT\n".as_bytes(), Path::new(""));
+
+        assert!(output.is_none());
+        assert_eq!(diagnostics.count(Severity::Error), 2);
+
+        let codes = diagnostics.iter().map(|d| d.code).collect::<Vec<_>>();
+        assert_eq!(codes, ["E0304", "E0301"]);
+    }
+
+    #[test]
+    fn compiler_collects_errors_on_multiple_lines() {
+        let (_, diagnostics) =
+            compile_with_diagnostics("NOP\nHLT\nLDI r0 hi(\n".as_bytes(), Path::new(""));
+
+        assert_eq!(diagnostics.count(Severity::Error), 2);
+    }
+
+    #[test]
+    fn compiler_warns_about_unused_label() {
+        let (output, diagnostics) =
+            compile_with_diagnostics("unused:\nHLT\n".as_bytes(), Path::new(""));
+
+        assert_eq!(output, Some(vec![0x00]));
+        assert!(!diagnostics.has_errors());
+        assert_eq!(diagnostics.count(Severity::Warning), 1);
+    }
+
+    #[test]
+    fn compiler_renders_diagnostic_with_source_snippet() {
+        let source = "HLT\n  LDI r1 lo(nowhere)\n";
+        let (_, diagnostics) = compile_with_diagnostics(source.as_bytes(), Path::new("a.asm"));
+
+        let rendered = diagnostics.to_string();
+        let expected = "error[E0305]: unknown label `nowhere`
+ --> a.asm:2:10
+  |
+2 |   LDI r1 lo(nowhere)
+  |          ^^^^^^^^^^^
+  = help: define the label with `name:`
+";
+
+        assert!(rendered.starts_with(expected), "{}", rendered);
+        assert!(rendered.ends_with("1 error(s), 0 warning(s) generated"));
+    }
 }
@@ -0,0 +1,197 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use mrt_cpu::{
+        compiler::compiler::Compiler,
+        machine::debugger::{
+            AccessKind, BreakpointKind, Comparison, Condition, ConditionOperand, StopReason,
+            WatchMode,
+        },
+        new_compiler,
+        symbols::SymbolTable,
+    };
+
+    use crate::common::{system, COUNTER};
+
+    #[test]
+    fn debugger_parses_condition() {
+        let condition = Condition::parse("r3 == 0x10").unwrap();
+        assert_eq!(condition.left, ConditionOperand::Register(3));
+        assert_eq!(condition.comparison, Comparison::Equal);
+        assert_eq!(condition.right, ConditionOperand::Constant(0x10));
+
+        let condition = Condition::parse("[0x20]>=r1").unwrap();
+        assert_eq!(condition.left, ConditionOperand::Memory(0x20));
+        assert_eq!(condition.comparison, Comparison::GreaterOrEqual);
+        assert_eq!(condition.right, ConditionOperand::Register(1));
+    }
+
+    #[test]
+    fn debugger_rejects_invalid_condition() {
+        assert!(Condition::parse("r3").is_err());
+        assert!(Condition::parse("r16 == 1").is_err());
+        assert!(Condition::parse("r1 == 256").is_err());
+        assert!(Condition::parse("r1 == r2 == r3").is_err());
+    }
+
+    #[test]
+    fn debugger_runs_until_halted() {
+        let mut sys = system(COUNTER, 256);
+        assert_eq!(sys.run(None, || false), StopReason::Halted);
+        assert_eq!(sys.get_mem(0x80), Ok(0));
+    }
+
+    #[test]
+    fn debugger_stops_at_step_limit() {
+        let mut sys = system(COUNTER, 256);
+        assert_eq!(sys.run(Some(3), || false), StopReason::StepLimit);
+        assert_eq!(sys.get_ip(), 6);
+    }
+
+    #[test]
+    fn debugger_stops_when_interrupted() {
+        let mut sys = system(COUNTER, 256);
+        assert_eq!(sys.run(None, || true), StopReason::Interrupted);
+    }
+
+    #[test]
+    fn debugger_stops_at_breakpoint() {
+        let mut sys = system(COUNTER, 256);
+        let id = sys
+            .breakpoints_mut()
+            .add(BreakpointKind::Address(0x0a), None);
+
+        assert_eq!(sys.run(None, || false), StopReason::Breakpoint(id));
+        assert_eq!(sys.get_ip(), 0x0a);
+        assert_eq!(sys.get_regs()[0], 0);
+
+        // continuing executes the instruction at the breakpoint first
+        assert_eq!(sys.run(None, || false), StopReason::Breakpoint(id));
+        assert_eq!(sys.get_regs()[0], 1);
+        assert_eq!(sys.breakpoints().get(id).unwrap().hits, 2);
+    }
+
+    #[test]
+    fn debugger_evaluates_breakpoint_condition() {
+        let mut sys = system(COUNTER, 256);
+        let condition = Condition::parse("r0 == 100").unwrap();
+        let id = sys
+            .breakpoints_mut()
+            .add(BreakpointKind::Address(0x0a), Some(condition));
+
+        assert_eq!(sys.run(None, || false), StopReason::Breakpoint(id));
+        assert_eq!(sys.get_regs()[0], 100);
+    }
+
+    #[test]
+    fn debugger_ignores_disabled_breakpoint() {
+        let mut sys = system(COUNTER, 256);
+        let id = sys
+            .breakpoints_mut()
+            .add(BreakpointKind::Address(0x0a), None);
+
+        sys.breakpoints_mut().set_enabled(id, false).unwrap();
+        assert_eq!(sys.run(None, || false), StopReason::Halted);
+        assert_eq!(sys.breakpoints().get(id).unwrap().hits, 0);
+    }
+
+    #[test]
+    fn debugger_deletes_breakpoint() {
+        let mut sys = system(COUNTER, 256);
+        let id = sys
+            .breakpoints_mut()
+            .add(BreakpointKind::Address(0x0a), None);
+
+        assert!(sys.breakpoints_mut().delete(id).is_ok());
+        assert!(sys.breakpoints_mut().delete(id).is_err());
+        assert!(sys.breakpoints().is_empty());
+        assert_eq!(sys.run(None, || false), StopReason::Halted);
+    }
+
+    #[test]
+    fn debugger_stops_at_write_watchpoint() {
+        let mut sys = system(COUNTER, 256);
+        let id = sys
+            .breakpoints_mut()
+            .add(BreakpointKind::Watch(0x80, WatchMode::Write), None);
+
+        let StopReason::Watchpoint(hit, access) = sys.run(None, || false) else {
+            panic!("expected watchpoint");
+        };
+
+        assert_eq!(hit, id);
+        assert_eq!(access.address, 0x80);
+        assert_eq!(access.kind, AccessKind::Write);
+        assert_eq!(access.value, 1);
+
+        // stopped after the store
+        assert_eq!(sys.get_ip(), 0x0e);
+        assert_eq!(sys.get_mem(0x80), Ok(1));
+    }
+
+    #[test]
+    fn debugger_distinguishes_watchpoint_access_kind() {
+        let mut sys = system(
+            "
+            LDI r0 0
+            LDI r1 0x80
+            LB r2 r0 r1
+            SB r2 r0 r1
+            HLT
+        ",
+            256,
+        );
+
+        let read = sys
+            .breakpoints_mut()
+            .add(BreakpointKind::Watch(0x80, WatchMode::Read), None);
+
+        let write = sys
+            .breakpoints_mut()
+            .add(BreakpointKind::Watch(0x81, WatchMode::ReadWrite), None);
+
+        assert!(matches!(
+            sys.run(None, || false),
+            StopReason::Watchpoint(id, _) if id == read
+        ));
+
+        // the watched neighbouring address is never accessed
+        assert_eq!(sys.run(None, || false), StopReason::Halted);
+        assert_eq!(sys.breakpoints().get(write).unwrap().hits, 0);
+    }
+
+    #[test]
+    fn debugger_symbol_table_round_trips() {
+        let mut symbols = SymbolTable::new();
+        symbols.insert("loop", 0x0a);
+        symbols.insert("message", 0x1234);
+
+        let parsed = SymbolTable::parse(&symbols.to_string()).unwrap();
+        assert_eq!(parsed.get("loop"), Some(0x0a));
+        assert_eq!(parsed.get("message"), Some(0x1234));
+        assert_eq!(parsed.name_of(0x1234), Some("message"));
+        assert_eq!(parsed.get("print"), None);
+
+        assert!(SymbolTable::parse("0x0010").is_err());
+        assert!(SymbolTable::parse("label 0x0010").is_err());
+    }
+
+    #[test]
+    fn debugger_symbol_table_is_generated_by_both_compilers() {
+        let path = Path::new("test.asm");
+        let tokens = new_compiler::token::tokenize(COUNTER.as_bytes(), path).unwrap();
+        let program = new_compiler::parser::parse(*tokens).unwrap();
+        assert_eq!(
+            new_compiler::codegen::symbols(&program).get("loop"),
+            Some(0x0a)
+        );
+
+        let mut output = vec![];
+        let mut compiler = Compiler::new(COUNTER.as_bytes(), &mut output);
+        compiler.compile().unwrap();
+        assert_eq!(compiler.symbols().get("loop"), Some(0x0a));
+    }
+}
@@ -0,0 +1,122 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use mrt_cpu::machine::{
+        computer::System,
+        debugger::StopReason,
+        fault::{ExecutionFault, FaultPolicy, Step},
+    };
+
+    use crate::common::word_system;
+
+    // Divides by zero, the handler at 0x20 counts the faults in r5
+    const TRAPPED: &str = "
+        LDI r1 7
+        DIV r2 r1 r0
+        LDI r3 1
+        HLT
+
+        .org 0x20
+        LDI r6 1
+        ADD r5 r5 r6
+        RETI
+    ";
+
+    #[test]
+    fn fault_illegal_opcode_halts_in_front_of_instruction() {
+        let mut sys = System::new(64);
+
+        // branch condition 0xf and extended opcode 0xee are not assigned
+        sys.load_rom(vec![0x50, 0x0f]).unwrap();
+        assert_eq!(sys.step(), Err(ExecutionFault::IllegalOpcode));
+        assert_eq!(sys.get_ip(), 0);
+        assert!(sys.tick());
+
+        sys.load_rom(vec![0xf0, 0xee]).unwrap();
+        assert_eq!(
+            sys.run(Some(10), || false),
+            StopReason::Fault(ExecutionFault::IllegalOpcode)
+        );
+        assert_eq!(sys.journal().len(), 0);
+    }
+
+    #[test]
+    fn fault_out_of_bounds_access() {
+        let mut sys = word_system::<u8>("LDI r0 1\nSB r0 r0 r0\nLB r1 r0 r0", 64);
+        sys.tick();
+        assert_eq!(sys.step(), Err(ExecutionFault::OutOfBoundsWrite(0x0101)));
+        assert_eq!(sys.get_ip(), 2);
+        assert!(sys.last_accesses().is_empty());
+
+        sys.set_fault_policy(FaultPolicy::Ignore);
+        sys.step().unwrap();
+        assert_eq!(
+            sys.step(),
+            Ok(Step::Ignored(ExecutionFault::OutOfBoundsRead(0x0101)))
+        );
+
+        assert_eq!(sys.get_mem(64), Err(ExecutionFault::OutOfBoundsRead(64)));
+        assert_eq!(
+            sys.set_mem(64, 1),
+            Err(ExecutionFault::OutOfBoundsWrite(64))
+        );
+    }
+
+    #[test]
+    fn fault_word_access_crossing_end_of_ram() {
+        // the low byte of a word stored at 0x3f would land at 0x40
+        let mut sys = word_system::<u16>("LDI r0 0\nLDI r1 0x3f\nSB r1 r0 r1\nHLT", 64);
+        assert_eq!(
+            sys.run(None, || false),
+            StopReason::Fault(ExecutionFault::OutOfBoundsWrite(0x40))
+        );
+        assert_eq!(sys.get_mem(0x3f), Ok(0));
+    }
+
+    #[test]
+    fn fault_policy_ignore_skips_instruction() {
+        let mut sys = word_system::<u8>(TRAPPED, 64);
+        sys.set_fault_policy(FaultPolicy::Ignore);
+
+        assert_eq!(sys.run(None, || false), StopReason::Halted);
+        assert_eq!(sys.get_regs()[2], 0);
+        assert_eq!(sys.get_regs()[3], 1);
+
+        // skipping can be undone like an executed instruction
+        sys.run_back(Some(2), || false);
+        assert_eq!(sys.get_ip(), 2);
+    }
+
+    #[test]
+    fn fault_policy_trap_enters_handler() {
+        let mut sys = word_system::<u8>(TRAPPED, 64);
+        sys.set_fault_policy(FaultPolicy::Trap(0x20));
+
+        sys.tick();
+        assert_eq!(sys.step(), Ok(Step::Trapped(ExecutionFault::DivideByZero)));
+        assert_eq!(sys.get_ip(), 0x20);
+        assert_eq!(sys.interrupts().saved.map(|(ip, _)| ip), Some(5));
+
+        // RETI continues after the faulting instruction
+        assert_eq!(sys.run(None, || false), StopReason::Halted);
+        assert_eq!(sys.get_regs()[5], 1);
+        assert_eq!(sys.get_regs()[3], 1);
+    }
+
+    #[test]
+    fn fault_in_handler_halts() {
+        let mut sys = word_system::<u8>(
+            ".org 0\nDIV r2 r1 r0\nHLT\n.org 0x20\nDIV r2 r1 r0\nRETI",
+            64,
+        );
+        sys.set_fault_policy(FaultPolicy::Trap(0x20));
+
+        assert_eq!(
+            sys.run(None, || false),
+            StopReason::Fault(ExecutionFault::DivideByZero)
+        );
+        assert_eq!(sys.get_ip(), 0x20);
+        assert!(sys.interrupts().in_handler());
+    }
+}
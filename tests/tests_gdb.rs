@@ -0,0 +1,191 @@
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{Read, Write},
+        net::{TcpListener, TcpStream},
+        path::Path,
+        thread,
+    };
+
+    use mrt_cpu::{
+        gdb::{target_xml, GdbStub},
+        machine::computer::System,
+        new_compiler,
+    };
+
+    // Stores r3 += 4 to 0x43, 0x42 and 0x41
+    const PROGRAM: &str = "
+        LDI r1 0x43
+        LDI r2 4
+        loop:
+        ADD r3 r3 r2
+        SB r3 r0 r1
+        LDI r4 1
+        SUB r1 r1 r4
+        LDI r4 0x40
+        SUB r4 r1 r4
+        JNZ loop
+        HLT
+    ";
+
+    // Client speaking the remote serial protocol like gdb does
+    struct Client {
+        stream: TcpStream,
+        acknowledge: bool,
+    }
+
+    impl Client {
+        fn read_byte(&mut self) -> u8 {
+            let mut byte = [0];
+            self.stream.read_exact(&mut byte).unwrap();
+            byte[0]
+        }
+
+        // Sends the packet and returns the reply
+        fn request(&mut self, packet: &str) -> String {
+            let checksum = packet.bytes().fold(0u8, |sum, byte| sum.wrapping_add(byte));
+            write!(self.stream, "${}#{:02x}", packet, checksum).unwrap();
+
+            if self.acknowledge {
+                assert_eq!(self.read_byte(), b'+');
+            }
+
+            assert_eq!(self.read_byte(), b'$');
+            let mut reply = vec![];
+            loop {
+                match self.read_byte() {
+                    b'#' => break,
+                    byte => reply.push(byte),
+                }
+            }
+
+            let mut checksum = [0; 2];
+            self.stream.read_exact(&mut checksum).unwrap();
+            let checksum = u8::from_str_radix(std::str::from_utf8(&checksum).unwrap(), 16);
+            assert_eq!(
+                checksum.unwrap(),
+                reply.iter().fold(0u8, |sum, byte| sum.wrapping_add(*byte))
+            );
+
+            if self.acknowledge {
+                self.stream.write_all(b"+").unwrap();
+            }
+
+            String::from_utf8(reply).unwrap()
+        }
+    }
+
+    // Serves a machine running the program on a free port to the client running the script in
+    // a thread of its own, returns the machine once the client detached
+    fn session<F>(source: &str, script: F) -> System
+    where
+        F: FnOnce(&mut Client) + Send + 'static,
+    {
+        let rom = new_compiler::compile(source.as_bytes(), Path::new("test.asm")).unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+
+        let client = thread::spawn(move || {
+            let stream = TcpStream::connect(address).unwrap();
+            stream.set_nodelay(true).unwrap();
+
+            let mut client = Client {
+                stream,
+                acknowledge: true,
+            };
+            script(&mut client);
+        });
+
+        let mut system = System::new(256);
+        system.load_rom(*rom).unwrap();
+
+        let (stream, _) = listener.accept().unwrap();
+        stream.set_nodelay(true).unwrap();
+        GdbStub::new(&mut system, stream).serve().unwrap();
+        client.join().unwrap();
+
+        system
+    }
+
+    #[test]
+    fn gdb_target_description_lists_registers() {
+        let xml = target_xml::<u16>();
+
+        assert!(xml.contains("<reg name=\"r15\" bitsize=\"16\" type=\"int\" regnum=\"15\"/>"));
+        assert!(xml.contains("<reg name=\"ip\" bitsize=\"16\" type=\"code_ptr\"/>"));
+        assert!(target_xml::<u8>().contains("<reg name=\"r0\" bitsize=\"8\""));
+    }
+
+    #[test]
+    fn gdb_session() {
+        let system = session(PROGRAM, |client| {
+            assert!(client
+                .request("qSupported:swbreak+")
+                .contains("qXfer:features:read+"));
+            assert_eq!(client.request("?"), "S05");
+
+            // the description is read in chunks
+            let xml = target_xml::<u8>();
+            let first = client.request("qXfer:features:read:target.xml:0,20");
+            assert_eq!(first, format!("m{}", &xml[..0x20]));
+            let rest = client.request(&format!(
+                "qXfer:features:read:target.xml:20,{:x}",
+                xml.len()
+            ));
+            assert_eq!(rest, format!("l{}", &xml[0x20..]));
+
+            assert_eq!(client.request("QStartNoAckMode"), "OK");
+            client.acknowledge = false;
+
+            // registers r0-r15, sp, ip and flags
+            let registers = client.request("g");
+            assert_eq!(registers.len(), (16 + 2 + 2 + 1) * 2);
+            assert_eq!(&registers[32..], "0100000000");
+
+            assert_eq!(client.request("m0,4"), "11431204");
+            assert_eq!(client.request("mfff0,4"), "E14");
+
+            assert_eq!(client.request("s"), "S05");
+            assert_eq!(client.request("p1"), "43");
+            assert_eq!(client.request("p11"), "0002");
+
+            // stops before the store of the loop
+            assert_eq!(client.request("Z0,6,1"), "OK");
+            assert_eq!(client.request("c"), "T05swbreak:;");
+            assert_eq!(client.request("p11"), "0006");
+            assert_eq!(client.request("p3"), "04");
+
+            assert_eq!(client.request("P3=10"), "OK");
+            assert_eq!(client.request("z0,6,1"), "OK");
+            assert_eq!(client.request("z0,6,1"), "E01");
+
+            // watchpoint on the store of the next iteration
+            assert_eq!(client.request("Z2,42,1"), "OK");
+            assert_eq!(client.request("c"), "T05watch:42;");
+            assert_eq!(client.request("m42,2"), "1410");
+
+            assert_eq!(client.request("M40,2:abcd"), "OK");
+            assert_eq!(client.request("m40,2"), "abcd");
+
+            assert_eq!(client.request("z2,42,1"), "OK");
+            assert_eq!(client.request("c"), "W00");
+            assert_eq!(client.request("vMustReplyEmpty"), "");
+            assert_eq!(client.request("D"), "OK");
+        });
+
+        assert_eq!(system.get_mem(0x40), Ok(0xab));
+        assert_eq!(system.get_mem(0x41), Ok(0x18));
+        assert!(system.breakpoints().is_empty());
+    }
+
+    #[test]
+    fn gdb_reports_faults_as_signals() {
+        let system = session("LDI r1 1\nDIV r2 r1 r0\nHLT", |client| {
+            assert_eq!(client.request("c"), "S08");
+            assert_eq!(client.request("p11"), "0002");
+            assert_eq!(client.request("D"), "OK");
+        });
+
+        assert_eq!(system.get_ip(), 2);
+    }
+}
@@ -0,0 +1,222 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use mrt_cpu::{
+        compiler::instruction::Instruction,
+        machine::{
+            computer::System,
+            debugger::StopReason,
+            devices::{
+                interrupt::InterruptController,
+                serial::{InputSource, Serial},
+            },
+            flags::Flags,
+            snapshot::Snapshot,
+        },
+        types::Opcode,
+    };
+
+    use crate::common::system;
+
+    // Counts r0 up forever, the handler at 0x20 counts r1 up
+    const COUNTER: &str = "
+        LDI r2 1
+        LDI r3 hi(loop)
+        LDI r4 lo(loop)
+        EI
+        loop:
+        ADD r0 r0 r2
+        JNZ r3 r4
+        HLT
+
+        .org 0x20
+        ADD r1 r1 r2
+        RETI
+    ";
+
+    fn counter() -> System {
+        let mut sys = system(COUNTER, 256);
+        sys.bus_mut()
+            .device_mut::<InterruptController>()
+            .unwrap()
+            .set_vector(0x20);
+
+        sys
+    }
+
+    #[test]
+    fn interrupts_extended_instructions_are_encoded_behind_prefix() {
+        assert_eq!(
+            Instruction::NoParam(Opcode::EI).serialize(),
+            vec![0xf0, 0x00]
+        );
+        assert_eq!(
+            Instruction::NoParam(Opcode::DI).serialize(),
+            vec![0xf0, 0x01]
+        );
+        assert_eq!(
+            Instruction::NoParam(Opcode::RETI).serialize(),
+            vec![0xf0, 0x02]
+        );
+        assert_eq!(Instruction::get_length(Opcode::RETI), 2);
+
+        let instruction = Instruction::disassemble(&[0xf0, 0x02]).unwrap();
+        assert!(matches!(instruction, Instruction::NoParam(Opcode::RETI)));
+        assert!(Instruction::disassemble(&[0xf0, 0xff]).is_err());
+    }
+
+    #[test]
+    fn interrupts_are_disabled_by_default() {
+        let mut sys = system("LDI r0 1\nLDI r0 2\nHLT", 256);
+        sys.raise_interrupt();
+
+        assert_eq!(sys.run(None, || false), StopReason::Halted);
+        assert!(!sys.interrupts().in_handler());
+        assert_eq!(sys.get_regs()[0], 2);
+    }
+
+    #[test]
+    fn interrupts_enter_and_return_from_handler() {
+        let mut sys = counter();
+        sys.run(Some(6), || false);
+        assert!(sys.interrupts().enabled);
+
+        sys.raise_interrupt();
+        let (ip, flags) = (sys.get_ip(), *sys.get_flags_register());
+
+        // entering the handler takes a tick of its own
+        sys.tick();
+        assert_eq!(sys.get_ip(), 0x20);
+        assert_eq!(sys.interrupts().saved, Some((ip, flags)));
+        assert!(!sys.interrupts().enabled);
+
+        // the handler changes the flags, RETI restores them
+        sys.tick();
+        assert_eq!(sys.get_regs()[1], 1);
+        assert!(!sys.get_flags_register().is_set(Flags::Zero));
+
+        sys.tick();
+        assert_eq!(sys.get_ip(), ip);
+        assert_eq!(*sys.get_flags_register(), flags);
+        assert!(sys.interrupts().enabled);
+        assert!(!sys.interrupts().in_handler());
+    }
+
+    #[test]
+    fn interrupts_are_not_nested() {
+        let mut sys = counter();
+        sys.run(Some(4), || false);
+
+        sys.raise_interrupt();
+        sys.tick();
+        sys.raise_interrupt();
+        sys.tick();
+        assert_eq!(sys.get_ip(), 0x22);
+
+        // taken after returning from the first one
+        sys.tick();
+        sys.tick();
+        assert_eq!(sys.get_ip(), 0x20);
+    }
+
+    #[test]
+    fn interrupts_masked_by_di() {
+        let mut sys = system("EI\nDI\nLDI r0 1\nHLT", 256);
+        sys.run(Some(2), || false);
+        assert!(!sys.interrupts().enabled);
+
+        sys.raise_interrupt();
+        sys.tick();
+        assert_eq!(sys.get_regs()[0], 1);
+    }
+
+    #[test]
+    fn interrupts_reti_outside_handler_does_nothing() {
+        let mut sys = system("RETI\nHLT", 256);
+        sys.tick();
+        assert_eq!(sys.get_ip(), 2);
+        assert!(!sys.interrupts().enabled);
+    }
+
+    #[test]
+    fn interrupts_raised_by_serial_input() {
+        let source = std::fs::read_to_string("programs/interrupts.asm").unwrap();
+        let mut sys = system(&source, 256);
+
+        sys.run(Some(100), || false);
+        assert!(sys.bus().device::<Serial>().unwrap().output().is_empty());
+
+        let serial = sys.bus_mut().device_mut::<Serial>().unwrap();
+        serial.push_input(b"irq");
+
+        sys.run(Some(100), || false);
+        let serial = sys.bus_mut().device_mut::<Serial>().unwrap();
+        assert_eq!(serial.output(), b"irq");
+        assert_eq!(serial.pending_input(), 0);
+        assert!(!sys.interrupts().in_handler());
+    }
+
+    // Input that arrives after a number of polls, the program never waits for it
+    struct Delayed {
+        polls: usize,
+        bytes: &'static [u8],
+    }
+
+    impl InputSource for Delayed {
+        fn receive(&mut self, _: &mut Vec<u8>) -> Option<Vec<u8>> {
+            panic!("blocked on input");
+        }
+
+        fn poll(&mut self, _: &mut Vec<u8>) -> Option<Vec<u8>> {
+            self.polls = self.polls.saturating_sub(1);
+            match self.polls {
+                0 => Some(std::mem::take(&mut self.bytes).to_vec()),
+                _ => Some(vec![]),
+            }
+        }
+    }
+
+    #[test]
+    fn interrupts_raised_by_serial_input_source() {
+        let source = std::fs::read_to_string("programs/interrupts.asm").unwrap();
+        let mut sys = system(&source, 256);
+
+        let serial = sys.bus_mut().device_mut::<Serial>().unwrap();
+        serial.set_input_source(Some(Box::new(Delayed {
+            polls: 50,
+            bytes: b"irq",
+        })));
+
+        sys.run(Some(200), || false);
+        let serial = sys.bus_mut().device_mut::<Serial>().unwrap();
+        assert_eq!(serial.output(), b"irq");
+        assert_eq!(serial.pending_input(), 0);
+    }
+
+    #[test]
+    fn interrupts_entry_can_be_undone() {
+        let mut sys = counter();
+        sys.run(Some(6), || false);
+        let before = sys.snapshot();
+
+        sys.raise_interrupt();
+        sys.run(Some(2), || false);
+        assert!(sys.interrupts().in_handler());
+
+        sys.run_back(Some(2), || false);
+        assert_eq!(sys.snapshot(), before);
+    }
+
+    #[test]
+    fn interrupts_state_is_part_of_snapshot() {
+        let mut sys = counter();
+        sys.run(Some(6), || false);
+        sys.raise_interrupt();
+        sys.tick();
+
+        let snapshot = sys.snapshot();
+        assert!(snapshot.interrupts.in_handler());
+        assert_eq!(Snapshot::deserialize(&snapshot.serialize()), Ok(snapshot));
+    }
+}
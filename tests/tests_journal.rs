@@ -0,0 +1,112 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use mrt_cpu::machine::debugger::{BreakpointKind, StopReason, WatchMode};
+
+    use crate::common::{system, COUNTER};
+
+    #[test]
+    fn journal_step_back_restores_state() {
+        let mut sys = system(COUNTER, 256);
+        let snapshots = (0..20)
+            .map(|_| {
+                let snapshot = sys.snapshot();
+                sys.tick();
+                snapshot
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(sys.journal().len(), 20);
+
+        for snapshot in snapshots.iter().rev() {
+            assert!(sys.step_back().is_some());
+            assert_eq!(&sys.snapshot(), snapshot);
+        }
+
+        assert!(sys.step_back().is_none());
+    }
+
+    #[test]
+    fn journal_step_back_reverts_memory_writes() {
+        let mut sys = system(COUNTER, 256);
+        assert_eq!(sys.run(Some(7), || false), StopReason::StepLimit);
+        assert_eq!(sys.get_mem(0x80), Ok(1));
+
+        let reverted = sys.step_back().unwrap();
+        assert_eq!(reverted.len(), 1);
+        assert_eq!(reverted[0].address, 0x80);
+        assert_eq!(reverted[0].value, 1);
+        assert_eq!(sys.get_mem(0x80), Ok(0));
+    }
+
+    #[test]
+    fn journal_does_not_record_halt() {
+        let mut sys = system("HLT", 256);
+        assert!(sys.tick());
+        assert!(sys.journal().is_empty());
+    }
+
+    #[test]
+    fn journal_drops_oldest_entries_beyond_limit() {
+        let mut sys = system(COUNTER, 256);
+        sys.journal_mut().set_limit(4);
+
+        assert_eq!(sys.run(Some(10), || false), StopReason::StepLimit);
+        assert_eq!(sys.journal().len(), 4);
+
+        assert_eq!(sys.run_back(None, || false), StopReason::StartOfHistory);
+        assert_eq!(sys.get_regs()[0], 1);
+
+        sys.journal_mut().set_limit(0);
+        sys.tick();
+        assert!(sys.journal().is_empty());
+    }
+
+    #[test]
+    fn journal_is_cleared_when_loading_rom() {
+        let mut sys = system(COUNTER, 256);
+        sys.run(Some(5), || false);
+
+        sys.load_rom(vec![0]).unwrap();
+        assert!(sys.journal().is_empty());
+    }
+
+    #[test]
+    fn journal_reverse_continue_stops_at_breakpoint() {
+        let mut sys = system(COUNTER, 256);
+        assert_eq!(sys.run(Some(5 + 3 * 10), || false), StopReason::StepLimit);
+        assert_eq!(sys.get_regs()[0], 10);
+
+        let id = sys
+            .breakpoints_mut()
+            .add(BreakpointKind::Address(0x0a), None);
+
+        // the breakpoint at the current ip is left first, it triggers again one iteration earlier
+        assert_eq!(sys.run_back(None, || false), StopReason::Breakpoint(id));
+        assert_eq!(sys.get_ip(), 0x0a);
+        assert_eq!(sys.get_regs()[0], 9);
+
+        assert_eq!(sys.run_back(None, || false), StopReason::Breakpoint(id));
+        assert_eq!(sys.get_regs()[0], 8);
+    }
+
+    #[test]
+    fn journal_reverse_continue_stops_at_watchpoint() {
+        let mut sys = system(COUNTER, 256);
+        assert_eq!(sys.run(Some(5 + 3 * 10), || false), StopReason::StepLimit);
+
+        let id = sys
+            .breakpoints_mut()
+            .add(BreakpointKind::Watch(0x80, WatchMode::Write), None);
+
+        let StopReason::Watchpoint(hit, access) = sys.run_back(None, || false) else {
+            panic!("expected watchpoint");
+        };
+
+        assert_eq!(hit, id);
+        assert_eq!(access.value, 10);
+        assert_eq!(sys.get_mem(0x80), Ok(9));
+        assert_eq!(sys.get_ip(), 0x0c);
+    }
+}
@@ -0,0 +1,79 @@
+#[cfg(test)]
+mod tests {
+    use mrt_cpu::{
+        compiler::instruction::Instruction,
+        types::{Opcode, OpcodeConversionError},
+    };
+
+    fn decode(bytes: &[u8]) -> Result<(Opcode, u16), OpcodeConversionError> {
+        Opcode::decode(|offset| bytes.get(offset as usize).copied())
+    }
+
+    #[test]
+    fn opcode_extensions_round_trip() {
+        let mut extension = 0;
+        while let Ok(opcode) = Opcode::from_extension(extension) {
+            assert_eq!(opcode.extension(), Some(extension));
+            assert!(opcode.is_extended());
+
+            let mut bytes = vec![Opcode::EXTENSION << 4];
+            bytes.extend(opcode.extension_bytes().unwrap());
+            bytes.push(0);
+            assert!(matches!(decode(&bytes), Ok((decoded, 2)) if decoded == opcode));
+
+            extension += 1;
+        }
+
+        assert!(extension > 16);
+        assert_eq!(Opcode::ADD.extension(), None);
+    }
+
+    #[test]
+    fn opcode_extension_escapes_to_next_page() {
+        assert_eq!(Opcode::encode_extension(0x05), [0x05]);
+        assert_eq!(Opcode::encode_extension(254), [254]);
+        assert_eq!(Opcode::encode_extension(255), [0xff, 0]);
+        assert_eq!(Opcode::encode_extension(600), [0xff, 0xff, 90]);
+
+        // beyond the end of the table
+        assert!(matches!(
+            decode(&[0xf0, 0xff, 0xff, 90]),
+            Err(OpcodeConversionError::NoSuchOpcode)
+        ));
+        assert!(matches!(
+            decode(&[0xf0, 0xff]),
+            Err(OpcodeConversionError::Truncated)
+        ));
+
+        // a run of escape bytes longer than any extended opcode
+        let mut bytes = vec![0xf0];
+        bytes.extend([0xff; 300]);
+        assert!(matches!(
+            decode(&bytes),
+            Err(OpcodeConversionError::NoSuchOpcode)
+        ));
+    }
+
+    #[test]
+    fn opcode_decodes_primary_and_branch_opcodes() {
+        assert!(matches!(decode(&[0x21, 0x23]), Ok((Opcode::ADD, 1))));
+        assert!(matches!(decode(&[0x51, 0x21]), Ok((Opcode::JZ, 1))));
+        assert!(matches!(
+            decode(&[0x51]),
+            Err(OpcodeConversionError::Truncated)
+        ));
+        assert!(matches!(decode(&[]), Err(OpcodeConversionError::Truncated)));
+    }
+
+    #[test]
+    fn opcode_length_includes_extension() {
+        assert_eq!(Instruction::get_length(Opcode::HLT), 1);
+        assert_eq!(Instruction::get_length(Opcode::ADD), 2);
+        assert_eq!(Instruction::get_length(Opcode::RET), 2);
+        assert_eq!(Instruction::get_length(Opcode::ADC), 3);
+
+        let instruction = Instruction::disassemble(&[0xf1, 0x08, 0x20]).unwrap();
+        assert_eq!(instruction.length(), 3);
+        assert_eq!(instruction.serialize(), [0xf1, 0x08, 0x20]);
+    }
+}
@@ -0,0 +1,159 @@
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use mrt_cpu::{
+        compiler::fold::fold,
+        machine::{computer::System, debugger::StopReason},
+        new_compiler::{
+            self, compile_with_diagnostics,
+            diagnostic::Severity,
+            error::{CodeGenerationError, CompilationError},
+        },
+        types::Register,
+    };
+
+    fn compile(source: &str) -> Vec<u8> {
+        *new_compiler::compile(source.as_bytes(), Path::new("test.asm")).unwrap()
+    }
+
+    fn codegen_error(source: &str) -> CodeGenerationError {
+        match new_compiler::compile(source.as_bytes(), Path::new("test.asm")) {
+            Err(CompilationError::CodeGeneration(error)) => error,
+            result => panic!("expected a code generation error, got {:?}", result),
+        }
+    }
+
+    const DIRECT: &str = "
+        LDI16 r4:r5, message
+        CALL load
+        SB r2, [0x40]
+        JMP done
+        load:
+        LB r2, [value]
+        MOV r3, r2
+        RET
+        done: HLT
+        value: .db 7
+        message: .db 0
+    ";
+
+    #[test]
+    fn pseudo_ldi16_loads_register_pair() {
+        assert_eq!(compile("LDI16 r4:r5 0x1234"), vec![0x14, 0x12, 0x15, 0x34]);
+        assert_eq!(
+            compile("LDI16 r0:r9 end\nend:"),
+            vec![0x10, 0x00, 0x19, 0x04]
+        );
+    }
+
+    #[test]
+    fn pseudo_mov_expands_to_or() {
+        assert_eq!(compile("MOV r1, r2"), vec![0xe1, 0x22]);
+    }
+
+    #[test]
+    fn pseudo_direct_addressing_loads_address_registers() {
+        // LDI r14, LDI r15 followed by the instruction using them
+        assert_eq!(
+            compile("JMP 0x1234"),
+            vec![0x1e, 0x12, 0x1f, 0x34, 0x5e, 0xfd]
+        );
+        assert_eq!(
+            compile("LB r2 [0x1234]"),
+            vec![0x1e, 0x12, 0x1f, 0x34, 0x42, 0xef]
+        );
+
+        // register pairs are left as they are
+        assert_eq!(compile("JMP r2 r3"), vec![0x52, 0x3d]);
+    }
+
+    #[test]
+    fn pseudo_program_runs() {
+        let mut sys = System::new(256);
+        sys.load_rom(compile(DIRECT)).unwrap();
+
+        assert_eq!(sys.run(Some(100), || false), StopReason::Halted);
+        let regs = sys.get_regs();
+        assert_eq!((regs[4], regs[5]), (0x00, 0x23));
+        assert_eq!(regs[2], 7);
+        assert_eq!(regs[3], 7);
+        assert_eq!(sys.get_mem(0x40), Ok(7));
+        assert_eq!(sys.get_sp() as usize, sys.stack_base());
+    }
+
+    #[test]
+    fn pseudo_errors_on_invalid_operands() {
+        assert!(matches!(
+            codegen_error("LDI16 r4 1"),
+            CodeGenerationError::ExpectedRegisterPair(_)
+        ));
+        assert!(matches!(
+            codegen_error("MOV r1"),
+            CodeGenerationError::OperandCount(_, 2, 1)
+        ));
+        assert!(matches!(
+            codegen_error("SB r15 [0x40]"),
+            CodeGenerationError::ClobberedRegister(_, Register::R15)
+        ));
+        assert!(matches!(
+            codegen_error("ADD r1 r2 [0x40]"),
+            CodeGenerationError::ExpectedRegister(_)
+        ));
+    }
+
+    #[test]
+    fn pseudo_labels_in_memory_operands_are_used() {
+        let source = "LB r0 [value]\nHLT\nvalue: .db 1";
+        let (output, diagnostics) = compile_with_diagnostics(source.as_bytes(), Path::new(""));
+
+        assert!(output.is_some());
+        assert_eq!(diagnostics.count(Severity::Warning), 0);
+    }
+
+    #[test]
+    fn pseudo_listing_shows_expansion() {
+        let listing = new_compiler::listing(DIRECT.as_bytes(), Path::new("test.asm")).unwrap();
+        let lines = listing.lines().collect::<Vec<_>>();
+
+        assert_eq!(lines[0], "0000               LDI16 r4:r5, message");
+        assert_eq!(lines[1], "0000  14 00            LDI R4 0x0");
+        assert_eq!(lines[2], "0002  15 23            LDI R5 0x23");
+        assert_eq!(lines[3], "0004               CALL load");
+        assert_eq!(lines[6], "0008  fe 08 f0         CALL R14 R15");
+        assert!(lines.contains(&"0021  00           done: HLT"));
+    }
+
+    #[test]
+    fn pseudo_disassembly_folds_sequences() {
+        let rom = compile(DIRECT);
+        let symbols = new_compiler::codegen::symbols(&{
+            let tokens = new_compiler::token::tokenize(DIRECT.as_bytes(), Path::new("")).unwrap();
+            new_compiler::parser::parse(*tokens).unwrap()
+        });
+        let name_of = |address| symbols.name_of(address).map(str::to_string);
+
+        let mut folded = vec![];
+        let mut ip = 0;
+        while let Some(instruction) = fold(&rom[ip..], name_of) {
+            ip += instruction.length as usize;
+            folded.push(instruction.text);
+        }
+
+        assert_eq!(
+            folded,
+            [
+                "LDI16 R4:R5 message",
+                "CALL load",
+                "SB R2 [0x0040]",
+                "JMP done",
+                "LB R2 [value]",
+                "MOV R3 R2",
+            ]
+        );
+
+        // loads that are not a label address into a pair stay unfolded
+        assert_eq!(fold(&[0x14, 0x12, 0x15, 0x34], name_of), None);
+        assert_eq!(fold(&[0x14, 0x00, 0x16, 0x23], name_of), None);
+    }
+}
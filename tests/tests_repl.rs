@@ -0,0 +1,88 @@
+#[cfg(test)]
+mod tests {
+    use mrt_cpu::{
+        cli::command::Registry,
+        repl::{split_arguments, ArgumentError, CommandCompleter},
+    };
+
+    fn completer() -> CommandCompleter {
+        let mut completer = CommandCompleter::new();
+        completer.set_commands(Registry::<u8>::new().signatures());
+        completer
+    }
+
+    fn completions(completer: &CommandCompleter, line: &str) -> (usize, Vec<String>) {
+        let (start, candidates) = completer.completions(line, line.len());
+        let candidates = candidates.into_iter().map(|pair| pair.replacement);
+
+        (start, candidates.collect())
+    }
+
+    #[test]
+    fn repl_splits_at_any_whitespace() {
+        assert_eq!(
+            split_arguments("  w  0x10\t0xd3 ").unwrap(),
+            ["w", "0x10", "0xd3"]
+        );
+        assert!(split_arguments("   ").unwrap().is_empty());
+    }
+
+    #[test]
+    fn repl_quotes_keep_whitespace() {
+        assert_eq!(
+            split_arguments("input \"a  b\" 'c d' e\"f g\"").unwrap(),
+            ["input", "a  b", "c d", "ef g"]
+        );
+        assert_eq!(split_arguments("echo \"\" x").unwrap(), ["echo", "", "x"]);
+        assert_eq!(
+            split_arguments(r#"echo \"a\ b '\n'"#).unwrap(),
+            ["echo", "\"a b", "\\n"]
+        );
+        assert_eq!(
+            split_arguments("echo \"a"),
+            Err(ArgumentError::UnterminatedQuote)
+        );
+    }
+
+    #[test]
+    fn repl_completes_commands_and_labels() {
+        let mut completer = completer();
+        completer.set_labels(vec!["loop".to_string(), "main".to_string()]);
+
+        assert_eq!(
+            completions(&completer, "rev"),
+            (0, vec!["reverse-continue".to_string()])
+        );
+        assert_eq!(
+            completions(&completer, "break lo"),
+            (6, vec!["loop".to_string()])
+        );
+        assert_eq!(completions(&completer, "b x").1, Vec::<String>::new());
+
+        // labels only where an address is expected
+        assert_eq!(completions(&completer, "step l").1, Vec::<String>::new());
+    }
+
+    #[test]
+    fn repl_completes_choices_and_flags() {
+        let completer = completer();
+
+        assert_eq!(
+            completions(&completer, "fault_policy t"),
+            (13, vec!["trap".to_string()])
+        );
+        assert_eq!(
+            completions(&completer, "com in.asm out.rom --l").1,
+            ["--listing"]
+        );
+    }
+
+    #[test]
+    fn repl_completes_file_paths() {
+        let completer = completer();
+        let (start, candidates) = completions(&completer, "lr Cargo.to");
+
+        assert_eq!(start, 3);
+        assert_eq!(candidates, ["Cargo.toml"]);
+    }
+}
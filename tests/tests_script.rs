@@ -0,0 +1,109 @@
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::Write,
+        process::{Command, Stdio},
+        sync::{atomic::AtomicBool, Arc},
+    };
+
+    use mrt_cpu::{
+        batch::Status,
+        cli::{script_commands, Cli, CliError},
+    };
+
+    fn cli() -> Cli {
+        Cli::new(Arc::new(AtomicBool::new(false)))
+    }
+
+    #[test]
+    fn script_skips_comments_and_blank_lines() {
+        let script =
+            "# setup\nram_size 256\n\n   w 0x10 0xd3  \n  # check\nassert [0x10] == 0xd3\n";
+
+        assert_eq!(
+            script_commands(script),
+            ["ram_size 256", "w 0x10 0xd3", "assert [0x10] == 0xd3"]
+        );
+    }
+
+    #[test]
+    fn script_assert_compares_memory_and_registers() {
+        let mut cli = cli();
+        cli.write_memory(vec!["w", "0x10", "0xd3"]).unwrap();
+
+        assert!(cli.assert(vec!["assert", "[0x10]", "==", "0xd3"]).is_ok());
+        assert!(cli.assert(vec!["assert", "r2", "<", "[0x10]"]).is_ok());
+        assert!(matches!(
+            cli.assert(vec!["assert", "r2", "==", "0xd3"]),
+            Err(CliError::AssertionFailed)
+        ));
+    }
+
+    #[test]
+    fn script_assert_requires_condition() {
+        let cli = cli();
+
+        assert!(matches!(
+            cli.assert(vec!["assert"]),
+            Err(CliError::MissingParameter(_))
+        ));
+        assert!(matches!(
+            cli.assert(vec!["assert", "r2", "0xd3"]),
+            Err(CliError::InvalidParameterType(_, _))
+        ));
+    }
+
+    // Runs the script with `mrt-cpu --script`, returns the exit code and the output
+    fn run_script(name: &str, script: &str) -> (Option<i32>, String) {
+        let path = std::env::temp_dir().join(format!("mrt_cpu_script_{}.txt", name));
+        std::fs::write(&path, script).unwrap();
+
+        let output = Command::new(env!("CARGO_BIN_EXE_mrt-cpu"))
+            .arg("--script")
+            .arg(&path)
+            .output()
+            .unwrap();
+
+        (
+            output.status.code(),
+            String::from_utf8_lossy(&output.stdout).to_string(),
+        )
+    }
+
+    #[test]
+    fn script_stops_at_failed_command() {
+        let (code, output) = run_script("passes", "echo one\nassert r0 == 0\necho two\n");
+        assert_eq!(code, Some(Status::Success as i32));
+        assert!(output.contains("two"));
+
+        let (code, output) = run_script("unknown", "echo one\nbogus_cmd\necho two\n");
+        assert_eq!(code, Some(Status::Usage as i32));
+        assert!(output.contains("one") && !output.contains("two"));
+
+        let (code, _) = run_script("unreadable", "lr /nonexistent.rom\n");
+        assert_eq!(code, Some(Status::Usage as i32));
+
+        let (code, output) = run_script("assertion", "assert r0 == 1\necho two\n");
+        assert_eq!(code, Some(Status::AssertionFailed as i32));
+        assert!(!output.contains("two"));
+    }
+
+    #[test]
+    fn script_failure_at_prompt_keeps_exit_status() {
+        let mut prompt = Command::new(env!("CARGO_BIN_EXE_mrt-cpu"))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+
+        let mut stdin = prompt.stdin.take().unwrap();
+        stdin
+            .write_all(b"assert r0 == 1\nassert r0 == 0\nexit\n")
+            .unwrap();
+        drop(stdin);
+
+        let output = prompt.wait_with_output().unwrap();
+        assert!(String::from_utf8_lossy(&output.stdout).contains("Assertion failed"));
+        assert_eq!(output.status.code(), Some(Status::Success as i32));
+    }
+}
@@ -0,0 +1,121 @@
+#[cfg(test)]
+mod tests {
+    use mrt_cpu::{
+        machine::{
+            computer::System,
+            snapshot::{Snapshot, SnapshotError, SNAPSHOT_VERSION},
+        },
+        types::Opcode,
+    };
+
+    // LDI r1 0xff, ADD r1 r1 r1, SB r1 r0 r2, HLT
+    fn system() -> System {
+        let mut sys = System::new(32);
+        let rom = vec![
+            (Opcode::LDI as u8) << 4 | 1,
+            0xff,
+            (Opcode::ADD as u8) << 4 | 1,
+            0x11,
+            (Opcode::LDI as u8) << 4 | 2,
+            0x10,
+            (Opcode::SB as u8) << 4 | 1,
+            0x02,
+            Opcode::HLT as u8,
+        ];
+
+        sys.load_rom(rom).unwrap();
+        sys
+    }
+
+    #[test]
+    fn snapshot_restores_state() {
+        let mut sys = system();
+        let snapshot = sys.snapshot();
+
+        for _ in 0..4 {
+            sys.tick();
+        }
+
+        assert_ne!(sys.snapshot(), snapshot);
+
+        assert!(sys.cycles() > 0);
+
+        sys.restore(&snapshot);
+        assert_eq!(sys.snapshot(), snapshot);
+        assert_eq!(sys.cycles(), 0);
+        assert_eq!(sys.get_ip(), 0);
+        assert_eq!(sys.get_regs()[1], 0);
+        assert_eq!(sys.get_mem(0x10), Ok(0));
+    }
+
+    #[test]
+    fn snapshot_serialization_round_trips() {
+        let mut sys = system();
+        for _ in 0..4 {
+            sys.tick();
+        }
+
+        let snapshot = sys.snapshot();
+        let data = snapshot.serialize();
+        assert_eq!(&data[..4], b"MRTS");
+        assert_eq!(data[4], SNAPSHOT_VERSION);
+
+        assert_eq!(Snapshot::deserialize(&data), Ok(snapshot));
+    }
+
+    #[test]
+    fn snapshot_rejects_invalid_data() {
+        let data = system().snapshot().serialize();
+
+        assert_eq!(
+            Snapshot::deserialize(b"ROM!"),
+            Err(SnapshotError::InvalidMagic)
+        );
+
+        let mut future = data.clone();
+        future[4] = SNAPSHOT_VERSION + 1;
+        assert_eq!(
+            Snapshot::deserialize(&future),
+            Err(SnapshotError::UnsupportedVersion(SNAPSHOT_VERSION + 1))
+        );
+
+        assert_eq!(
+            Snapshot::deserialize(&data[..data.len() - 1]),
+            Err(SnapshotError::Truncated)
+        );
+
+        let mut trailing = data.clone();
+        trailing.push(0);
+        assert_eq!(
+            Snapshot::deserialize(&trailing),
+            Err(SnapshotError::TrailingData)
+        );
+    }
+
+    #[test]
+    fn snapshot_diff_reports_changes() {
+        let mut sys = system();
+        let before = sys.snapshot();
+
+        for _ in 0..4 {
+            sys.tick();
+        }
+
+        let diff = before.diff(&sys.snapshot());
+        assert_eq!(diff.ip, Some((0, 8)));
+        assert_eq!(diff.registers, vec![(1, 0, 0xfe), (2, 0, 0x10)]);
+        assert_eq!(diff.memory, vec![(0x10, 0, 0xfe)]);
+        assert_eq!(diff.ram_size, None);
+
+        let flags = diff
+            .flags
+            .iter()
+            .map(|(flag, set)| (flag.to_string(), *set));
+        assert_eq!(
+            flags.collect::<Vec<_>>(),
+            vec![("C".to_string(), true), ("S".to_string(), true)]
+        );
+
+        assert!(before.diff(&before).is_empty());
+    }
+}
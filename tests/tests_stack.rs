@@ -0,0 +1,246 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use mrt_cpu::{
+        compiler::instruction::Instruction,
+        machine::{
+            computer::{StackError, STACK_RESERVE},
+            debugger::StopReason,
+            fault::ExecutionFault,
+            snapshot::Snapshot,
+        },
+        new_compiler,
+        types::Opcode,
+    };
+
+    use crate::common::word_system;
+
+    fn compile(source: &str) -> Vec<u8> {
+        *new_compiler::compile(source.as_bytes(), Path::new("test.asm")).unwrap()
+    }
+
+    // Calls a routine that doubles r1, keeping r2 on the stack
+    const ROUTINE: &str = "
+        LDI r1 0x11
+        LDI r2 0x22
+        LDI r3 hi(double)
+        LDI r4 lo(double)
+        CALL r3 r4
+        HLT
+
+        double:
+        PUSH r2
+        ADD r2 r1 r1
+        ADD r1 r2 r0
+        POP r2
+        RET
+    ";
+
+    #[test]
+    fn stack_instructions_are_extended() {
+        let bytes = compile("PUSH r3\nPOP r4\nCALL r1 r2\nRET");
+        assert_eq!(
+            bytes,
+            [0xf3, 0x06, 0xf4, 0x07, 0xf1, 0x08, 0x20, 0xf0, 0x09]
+        );
+
+        let disassembled = [0, 2, 4, 7].map(|offset| {
+            Instruction::disassemble(&bytes[offset..])
+                .unwrap()
+                .to_string()
+        });
+        assert_eq!(disassembled, ["PUSH R3", "POP R4", "CALL R1 R2", "RET"]);
+        assert_eq!(Instruction::get_length(Opcode::CALL), 3);
+    }
+
+    #[test]
+    fn stack_starts_empty_at_end_of_ram() {
+        let sys = word_system::<u8>("HLT", 64);
+        assert_eq!(sys.stack_base(), 64);
+        assert_eq!(sys.get_sp(), 64);
+        assert!(sys.stack().is_empty());
+    }
+
+    #[test]
+    fn stack_push_and_pop_registers() {
+        let mut sys = word_system::<u8>(
+            "LDI r1 0x11\nLDI r2 0x22\nPUSH r1\nPUSH r2\nPOP r3\nPOP r4\nHLT",
+            64,
+        );
+        sys.run(Some(4), || false);
+        assert_eq!(sys.get_sp(), 62);
+        assert_eq!(sys.stack(), [0x22, 0x11]);
+
+        assert_eq!(sys.run(None, || false), StopReason::Halted);
+        assert_eq!(sys.get_regs()[3], 0x22);
+        assert_eq!(sys.get_regs()[4], 0x11);
+        assert_eq!(sys.get_sp(), 64);
+    }
+
+    #[test]
+    fn stack_uses_end_of_address_space() {
+        let mut sys = word_system::<u8>(
+            "LDI r1 0x11\nLDI r2 0x22\nPUSH r1\nPUSH r2\nPOP r3\nPOP r4\nHLT",
+            0x10000,
+        );
+
+        // sp wraps to 0 for an empty stack at 0x10000
+        assert_eq!(sys.stack_base(), 0x10000);
+        assert_eq!(sys.get_sp(), 0);
+        assert!(sys.stack().is_empty());
+
+        sys.run(Some(4), || false);
+        assert_eq!(sys.get_sp(), 0xfffe);
+        assert_eq!(sys.stack(), [0x22, 0x11]);
+        assert_eq!(sys.get_mem(0xffff).unwrap(), 0x11);
+
+        assert_eq!(sys.run(None, || false), StopReason::Halted);
+        assert_eq!(sys.get_regs()[3], 0x22);
+        assert_eq!(sys.get_regs()[4], 0x11);
+        assert_eq!(sys.get_sp(), 0);
+        assert!(sys.stack().is_empty());
+
+        // nothing to pop from the empty stack
+        let mut sys = word_system::<u8>("POP r0", 0x10000);
+        assert!(matches!(sys.run(None, || false), StopReason::Fault(_)));
+    }
+
+    #[test]
+    fn stack_call_returns_after_call() {
+        let mut sys = word_system::<u8>(ROUTINE, 64);
+        sys.run(Some(6), || false);
+
+        // return address, then the saved r2
+        assert_eq!(sys.stack(), [0x22, 0x00, 0x0b]);
+
+        assert_eq!(sys.run(None, || false), StopReason::Halted);
+        assert_eq!(sys.get_ip(), 0x0b);
+        assert_eq!(sys.get_regs()[1], 0x22);
+        assert_eq!(sys.get_regs()[2], 0x22);
+        assert!(sys.stack().is_empty());
+    }
+
+    #[test]
+    fn stack_pushes_words() {
+        let mut sys = word_system::<u16>("LDI r1 0x12\nSHL r1 r1 8\nPUSH r1\nPOP r2\nHLT", 64);
+        sys.run(Some(3), || false);
+        assert_eq!(sys.stack(), [0x12, 0x00]);
+
+        sys.run(None, || false);
+        assert_eq!(sys.get_regs()[2], 0x1200);
+    }
+
+    #[test]
+    fn stack_overflow_halts_before_devices() {
+        let mut sys = word_system::<u8>(
+            "
+            LDI r1 hi(loop)
+            LDI r2 lo(loop)
+            loop:
+            PUSH r0
+            JMP r1 r2
+        ",
+            64,
+        );
+
+        // the devices end at 0x0008
+        assert_eq!(
+            sys.run(Some(1000), || false),
+            StopReason::Fault(ExecutionFault::Stack(StackError::Overflow))
+        );
+        assert_eq!(sys.get_sp(), 0x08);
+        assert_eq!(sys.get_ip(), 0x04);
+        assert_eq!(sys.stack().len(), 56);
+    }
+
+    // Calls a routine and keeps a string at the end of a rom of more than 64 bytes
+    const LARGE: &str = "
+        CALL routine
+        HLT
+        .fill 0x40
+        routine:
+        RET
+        message:
+        .asciiz \"end\"
+    ";
+
+    #[test]
+    fn stack_has_room_behind_large_rom() {
+        let rom = compile(LARGE);
+        assert!(rom.len() > 64);
+
+        let mut sys = word_system::<u8>(LARGE, 64);
+        assert_eq!(sys.stack_base(), rom.len() + STACK_RESERVE);
+        assert_eq!(sys.run(None, || false), StopReason::Halted);
+
+        let ram = sys.bus().ram().as_slice();
+        assert_eq!(&ram[..rom.len()], rom.as_slice());
+    }
+
+    #[test]
+    fn stack_overflows_into_rom() {
+        let rom = compile(LARGE);
+        let mut sys = word_system::<u8>(LARGE, rom.len());
+
+        assert_eq!(
+            sys.run(None, || false),
+            StopReason::Fault(ExecutionFault::Stack(StackError::Overflow))
+        );
+        assert_eq!(sys.bus().ram().as_slice(), rom.as_slice());
+    }
+
+    #[test]
+    fn stack_underflow_halts() {
+        let underflow = StopReason::Fault(ExecutionFault::Stack(StackError::Underflow));
+        let mut sys = word_system::<u8>("LDI r0 1\nPOP r0\nHLT", 64);
+        assert_eq!(sys.run(None, || false), underflow);
+        assert_eq!(sys.get_ip(), 2);
+        assert_eq!(sys.get_regs()[0], 1);
+
+        let mut sys = word_system::<u8>("RET", 64);
+        assert_eq!(sys.run(None, || false), underflow);
+        assert_eq!(sys.get_ip(), 0);
+        assert_eq!(sys.get_sp(), 64);
+    }
+
+    #[test]
+    fn stack_pointer_is_undone() {
+        let mut sys = word_system::<u8>(ROUTINE, 64);
+        sys.run(Some(6), || false);
+        assert_eq!(sys.get_sp(), 61);
+
+        sys.run_back(Some(2), || false);
+        assert_eq!(sys.get_sp(), 64);
+        assert_eq!(sys.get_mem(62), Ok(0));
+        assert_eq!(sys.get_ip(), 0x08);
+    }
+
+    #[test]
+    fn stack_pointer_is_part_of_snapshot() {
+        let mut sys = word_system::<u8>(ROUTINE, 64);
+        let before = sys.snapshot();
+        sys.run(Some(6), || false);
+
+        let snapshot = sys.snapshot();
+        assert_eq!(snapshot.sp, 61);
+        assert_eq!(
+            Snapshot::deserialize(&snapshot.serialize()),
+            Ok(snapshot.clone())
+        );
+        assert_eq!(before.diff(&snapshot).sp, Some((64, 61)));
+
+        sys.restore(&before);
+        assert_eq!(sys.get_sp(), 64);
+    }
+
+    #[test]
+    fn stack_cycles_of_call_and_ret() {
+        assert_eq!(Opcode::PUSH.cycles(), 3);
+        assert_eq!(Opcode::POP.cycles(), 3);
+        assert_eq!(Opcode::CALL.cycles(), 5);
+        assert_eq!(Opcode::RET.cycles(), 4);
+    }
+}
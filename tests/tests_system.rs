@@ -1,13 +1,19 @@
 #[cfg(test)]
 mod tests {
-    use mrt_cpu::{machine::computer::System, types::Opcode};
+    use std::path::Path;
+
+    use mrt_cpu::{
+        machine::{computer::System, devices::serial::Serial, flags::Flags},
+        new_compiler::compile,
+        types::Opcode,
+    };
 
     #[test]
     fn system_can_load_rom() {
         let mut sys = System::new(1);
         let result = sys.load_rom([Opcode::XOR as u8].to_vec());
         assert!(result.is_ok());
-        assert_eq!(sys.get_mem(0), Opcode::XOR as u8);
+        assert_eq!(sys.get_mem(0), Ok(Opcode::XOR as u8));
     }
 
     #[test]
@@ -17,8 +23,8 @@ mod tests {
         let result = sys.load_rom([Opcode::HLT as u8].to_vec());
         assert!(result.is_ok());
 
-        sys.set_mem(1, 255);
-        assert_eq!(sys.get_mem(1), 255);
+        sys.set_mem(1, 255).unwrap();
+        assert_eq!(sys.get_mem(1), Ok(255));
     }
 
     #[test]
@@ -28,7 +34,7 @@ mod tests {
         let result = sys.load_rom([Opcode::XOR as u8].to_vec());
         assert!(result.is_ok());
 
-        assert_eq!(sys.get_mem(0), Opcode::XOR as u8);
+        assert_eq!(sys.get_mem(0), Ok(Opcode::XOR as u8));
     }
 
     // Tests for individual instructions
@@ -42,4 +48,165 @@ mod tests {
 
         assert_eq!(sys.get_ip(), 0x0000);
     }
+
+    fn run(source: &str) -> System {
+        let rom = compile(source.as_bytes(), Path::new("test.asm")).unwrap();
+
+        let mut sys = System::new(0);
+        sys.load_rom(*rom).unwrap();
+        while !sys.tick() {}
+        sys
+    }
+
+    // sets the carry flag by adding 0xff + 1
+    const CARRY: &str = "LDI r14 0xff\nLDI r15 1\nADD r15 r14 r15\n";
+
+    #[test]
+    fn system_instr_adc_adds_carry() {
+        let sys = run("LDI r1 0x10\nLDI r2 0x20\nADC r0 r1 r2\nHLT");
+        assert_eq!(sys.get_regs()[0], 0x30);
+
+        let sys = run(&format!(
+            "{CARRY}LDI r1 0x10\nLDI r2 0x20\nADC r0 r1 r2\nHLT"
+        ));
+        assert_eq!(sys.get_regs()[0], 0x31);
+        assert!(!sys.get_flags_register().is_set(Flags::Carry));
+
+        let sys = run(&format!("{CARRY}LDI r1 0xff\nLDI r2 0\nADC r0 r1 r2\nHLT"));
+        assert_eq!(sys.get_regs()[0], 0x00);
+        assert!(sys.get_flags_register().is_set(Flags::Carry));
+        assert!(sys.get_flags_register().is_set(Flags::Zero));
+    }
+
+    #[test]
+    fn system_instr_sbc_subtracts_borrow() {
+        let sys = run(&format!(
+            "{CARRY}LDI r1 0x10\nLDI r2 0x01\nSBC r0 r1 r2\nHLT"
+        ));
+        assert_eq!(sys.get_regs()[0], 0x0e);
+        assert!(!sys.get_flags_register().is_set(Flags::Carry));
+
+        let sys = run(&format!("{CARRY}LDI r1 0\nLDI r2 0\nSBC r0 r1 r2\nHLT"));
+        assert_eq!(sys.get_regs()[0], 0xff);
+        assert!(sys.get_flags_register().is_set(Flags::Carry));
+    }
+
+    #[test]
+    fn system_instr_cmp_only_sets_flags() {
+        let sys = run("LDI r1 5\nLDI r2 5\nCMP r1 r2\nHLT");
+        assert_eq!(sys.get_regs()[1], 5);
+        assert!(sys.get_flags_register().is_set(Flags::Zero));
+
+        let sys = run("LDI r1 4\nLDI r2 5\nCMP r1 r2\nHLT");
+        assert_eq!(sys.get_regs()[1], 4);
+        assert!(sys.get_flags_register().is_set(Flags::Carry));
+        assert!(!sys.get_flags_register().is_set(Flags::Zero));
+
+        let sys = run(&format!("{CARRY}LDI r1 6\nLDI r2 5\nCMP r1 r2\nHLT"));
+        assert!(!sys.get_flags_register().is_set(Flags::Carry));
+    }
+
+    #[test]
+    fn system_adds_and_subtracts_multi_byte_values() {
+        // 0x12ff + 0x0101 = 0x1400 with the high byte in r1, the low byte in r2
+        let sys = run("
+            LDI r1 0x12
+            LDI r2 0xff
+            LDI r3 0x01
+            LDI r4 0x01
+            ADD r2 r2 r4
+            ADC r1 r1 r3
+            HLT
+        ");
+        assert_eq!(sys.get_regs()[1], 0x14);
+        assert_eq!(sys.get_regs()[2], 0x00);
+
+        // 0x1400 - 0x0001 = 0x13ff
+        let sys = run("
+            LDI r1 0x14
+            LDI r2 0x00
+            LDI r3 0x00
+            LDI r4 0x01
+            SUB r2 r2 r4
+            SBC r1 r1 r3
+            HLT
+        ");
+        assert_eq!(sys.get_regs()[1], 0x13);
+        assert_eq!(sys.get_regs()[2], 0xff);
+    }
+
+    #[test]
+    fn system_instr_mul_and_div() {
+        let sys = run(
+            "LDI r1 20\nLDI r2 30\nMUL r3 r1 r2\nMULH r4 r1 r2\nDIV r5 r1 r2\nMOD r6 r2 r1\nHLT",
+        );
+        assert_eq!(sys.get_regs()[3], 0x58);
+        assert_eq!(sys.get_regs()[4], 0x02);
+        assert_eq!(sys.get_regs()[5], 0);
+        assert_eq!(sys.get_regs()[6], 10);
+
+        let sys = run("LDI r1 0xf9\nLDI r2 2\nIDIV r3 r1 r2\nIMOD r4 r1 r2\nIMUL r5 r1 r2\nIMULH r6 r1 r2\nHLT");
+        assert_eq!(sys.get_regs()[3], -3i8 as u8);
+        assert_eq!(sys.get_regs()[4], -1i8 as u8);
+        assert_eq!(sys.get_regs()[5], -14i8 as u8);
+        assert_eq!(sys.get_regs()[6], 0xff);
+    }
+
+    #[test]
+    fn system_divide_by_zero_halts_before_instruction() {
+        let mut sys = System::new(0);
+        let rom = compile(
+            "LDI r1 7\nDIV r2 r1 r0\nLDI r3 1\nHLT".as_bytes(),
+            Path::new("test.asm"),
+        );
+        sys.load_rom(*rom.unwrap()).unwrap();
+
+        assert!(!sys.tick());
+        assert!(sys.tick());
+        assert_eq!(sys.get_ip(), 2);
+        assert_eq!(sys.get_regs()[2], 0);
+        assert_eq!(sys.journal().len(), 1);
+    }
+
+    #[test]
+    fn system_prints_decimal_number() {
+        let source = std::fs::read_to_string("programs/decimal.asm").unwrap();
+        let rom = compile(source.as_bytes(), Path::new("decimal.asm")).unwrap();
+
+        let mut sys = System::new(256);
+        sys.load_rom(*rom).unwrap();
+        while !sys.tick() {}
+
+        assert_eq!(sys.bus().device::<Serial>().unwrap().output(), b"203");
+        assert!(sys.stack().is_empty());
+    }
+
+    #[test]
+    fn system_instr_rotates_and_shifts() {
+        let sys = run("LDI r1 0x81\nROL r2 r1 1\nROR r3 r1 4\nASR r4 r1 1\nHLT");
+        assert_eq!(sys.get_regs()[2], 0x03);
+        assert_eq!(sys.get_regs()[3], 0x18);
+        assert_eq!(sys.get_regs()[4], 0xc0);
+        assert!(sys.get_flags_register().is_set(Flags::Carry));
+
+        let sys = run("LDI r1 0x81\nLDI r5 3\nSHLV r2 r1 r5\nSHRV r3 r1 r5\nASRV r4 r1 r5\nROLV r6 r1 r5\nRORV r7 r1 r5\nHLT");
+        assert_eq!(sys.get_regs()[2], 0x08);
+        assert_eq!(sys.get_regs()[3], 0x10);
+        assert_eq!(sys.get_regs()[4], 0xf0);
+        assert_eq!(sys.get_regs()[6], 0x0c);
+        assert_eq!(sys.get_regs()[7], 0x30);
+    }
+
+    #[test]
+    fn system_rotates_multi_byte_values_through_carry() {
+        // 0x0180 << 1 = 0x0300 with the high byte in r1, the low byte in r2
+        let sys = run("LDI r1 0x01\nLDI r2 0x80\nSHL r2 r2 1\nRCL r1 r1 1\nHLT");
+        assert_eq!(sys.get_regs()[1], 0x03);
+        assert_eq!(sys.get_regs()[2], 0x00);
+
+        // and back
+        let sys = run("LDI r1 0x03\nLDI r2 0x00\nLDI r3 1\nSHR r1 r1 1\nRCRV r2 r2 r3\nHLT");
+        assert_eq!(sys.get_regs()[1], 0x01);
+        assert_eq!(sys.get_regs()[2], 0x80);
+    }
 }
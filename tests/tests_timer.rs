@@ -0,0 +1,181 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use mrt_cpu::{
+        machine::{
+            bus::Device,
+            computer::{System, INTERRUPT_CYCLES},
+            debugger::StopReason,
+            devices::{serial::Serial, timer::Timer},
+        },
+        types::Opcode,
+    };
+
+    use crate::common::system;
+
+    // large enough for the accesses of the cycle tests, e.g. `SB r0 r0 r0` with r0 = 2
+    const RAM_SIZE: usize = 0x400;
+
+    #[test]
+    fn timer_cycles_follow_cost_table() {
+        let mut sys = system("LDI r0 1\nADD r0 r0 r0\nSB r0 r0 r0\nEI\nHLT", RAM_SIZE);
+        assert_eq!(sys.run(None, || false), StopReason::Halted);
+
+        // the halting instruction is not executed
+        let expected =
+            Opcode::LDI.cycles() + Opcode::ADD.cycles() + Opcode::SB.cycles() + Opcode::EI.cycles();
+        assert_eq!(sys.cycles(), expected);
+        assert_eq!(expected, 9);
+    }
+
+    #[test]
+    fn timer_cycles_of_delay_loop() {
+        let mut sys = system(
+            "
+            LDI r0 10
+            LDI r1 1
+            LDI r2 hi(loop)
+            LDI r3 lo(loop)
+            loop:
+            SUB r0 r0 r1
+            JNZ r2 r3
+            HLT
+        ",
+            RAM_SIZE,
+        );
+
+        assert_eq!(sys.run(None, || false), StopReason::Halted);
+        assert_eq!(sys.cycles(), 4 * 2 + 10 * (2 + 3));
+    }
+
+    #[test]
+    fn timer_cycles_are_undone_and_reset() {
+        let mut sys = system("LDI r0 1\nLB r1 r0 r0\nHLT", RAM_SIZE);
+        sys.run(None, || false);
+        assert_eq!(sys.cycles(), 5);
+
+        sys.step_back().unwrap();
+        assert_eq!(sys.cycles(), 2);
+
+        sys.load_rom(vec![0]).unwrap();
+        assert_eq!(sys.cycles(), 0);
+    }
+
+    #[test]
+    fn timer_state_is_undone_with_cycles() {
+        // enables the timer, then counts with it
+        let mut sys = system(
+            "
+            LDI r0 0
+            LDI r1 7
+            LDI r2 1
+            SB r2 r0 r1
+            loop:
+            LDI r3 hi(loop)
+            LDI r4 lo(loop)
+            JMP r3 r4
+            ",
+            RAM_SIZE,
+        );
+
+        sys.run(Some(40), || false);
+        let timer = |sys: &System| sys.bus().device::<Timer>().unwrap().counter();
+        let counter = timer(&sys);
+        assert!(counter > 0);
+
+        sys.run(Some(10), || false);
+        assert_ne!(timer(&sys), counter);
+
+        sys.run_back(Some(10), || false);
+        assert_eq!(timer(&sys), counter);
+
+        // back to before the timer was enabled
+        sys.run_back(None, || false);
+        assert_eq!(sys.cycles(), 0);
+        assert_eq!(timer(&sys), 0);
+        assert_eq!(
+            sys.bus_mut()
+                .device_mut::<Timer>()
+                .unwrap()
+                .read(Timer::CONTROL),
+            0
+        );
+    }
+
+    #[test]
+    fn timer_interrupt_entry_takes_cycles() {
+        let mut sys = system("EI\nLDI r0 1\nHLT", RAM_SIZE);
+        sys.tick();
+        sys.raise_interrupt();
+        sys.tick();
+
+        assert_eq!(sys.cycles(), Opcode::EI.cycles() + INTERRUPT_CYCLES);
+    }
+
+    #[test]
+    fn timer_counts_prescaled_cycles() {
+        let mut timer = Timer::new();
+        timer.write(Timer::PRESCALER, 3);
+
+        // stopped until enabled
+        timer.tick(100);
+        assert_eq!(timer.counter(), 0);
+
+        timer.write(Timer::CONTROL, Timer::CONTROL_ENABLE);
+        timer.tick(3);
+        assert_eq!(timer.counter(), 0);
+        timer.tick(1);
+        assert_eq!(timer.counter(), 1);
+        timer.tick(9);
+        assert_eq!(timer.read(Timer::COUNTER), 3);
+    }
+
+    #[test]
+    fn timer_reloads_on_overflow() {
+        let mut timer = Timer::new();
+        timer.write(Timer::COUNTER, 0xfe);
+        timer.write(Timer::RELOAD, 0x80);
+        timer.write(
+            Timer::CONTROL,
+            Timer::CONTROL_ENABLE | Timer::CONTROL_INTERRUPT_ENABLE,
+        );
+
+        timer.tick(1);
+        assert!(!timer.overflowed());
+        timer.tick(1);
+        assert!(timer.overflowed());
+        assert!(timer.interrupt_requested());
+        assert_eq!(timer.counter(), 0x80);
+        assert_eq!(
+            timer.read(Timer::CONTROL),
+            Timer::CONTROL_ENABLE | Timer::CONTROL_INTERRUPT_ENABLE | Timer::STATUS_OVERFLOW
+        );
+
+        // writing the flag as 1 clears it, as 0 leaves it
+        timer.write(Timer::CONTROL, Timer::CONTROL_ENABLE);
+        assert!(timer.overflowed());
+        assert!(!timer.interrupt_requested());
+        timer.write(
+            Timer::CONTROL,
+            Timer::CONTROL_ENABLE | Timer::STATUS_OVERFLOW,
+        );
+        assert!(!timer.overflowed());
+    }
+
+    #[test]
+    fn timer_interrupts_program_periodically() {
+        let source = std::fs::read_to_string("programs/timer.asm").unwrap();
+        let mut sys = system(&source, RAM_SIZE);
+
+        assert_eq!(sys.run(Some(10_000), || false), StopReason::Halted);
+        assert_eq!(
+            sys.bus().device::<Serial>().unwrap().output(),
+            b".........."
+        );
+
+        // ten overflows of 64 cycles each after setting up the timer
+        assert!((640..720).contains(&sys.cycles()));
+        assert!(!sys.interrupts().in_handler());
+    }
+}
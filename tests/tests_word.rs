@@ -0,0 +1,168 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use mrt_cpu::{
+        machine::{
+            alu as ALU,
+            computer::System,
+            debugger::{Condition, StopReason},
+            devices::serial::Serial,
+            flags::Flags,
+            snapshot::Snapshot,
+            word::WordSize,
+        },
+        new_compiler,
+    };
+
+    use crate::common::word_system;
+
+    fn compile(source: &str) -> Vec<u8> {
+        *new_compiler::compile(source.as_bytes(), Path::new("test.asm")).unwrap()
+    }
+
+    #[test]
+    fn word_alu_uses_full_width() {
+        let result = ALU::add(0xffu16, 1);
+        assert_eq!(result.value, 0x100);
+        assert!(!result.flags.is_set(Flags::Carry));
+
+        let result = ALU::add(0xffffu16, 1);
+        assert_eq!(result.value, 0);
+        assert!(result.flags.is_set(Flags::Carry));
+        assert!(result.flags.is_set(Flags::Zero));
+
+        let result = ALU::sub(0x80u16, 1);
+        assert!(!result.flags.is_set(Flags::Sign));
+
+        let result = ALU::add(0x7fffu16, 1);
+        assert!(result.flags.is_set(Flags::Sign));
+        assert!(result.flags.is_set(Flags::Overflow));
+
+        let result = ALU::mul(0x100u16, 0x180);
+        assert_eq!(result.value, 0x8000);
+        assert_eq!(ALU::mulh(0x100u16, 0x180).value, 0x01);
+        assert_eq!(ALU::idiv(0x8000u16, 2).unwrap().value, 0xc000);
+
+        assert!(ALU::is_signed(0x8000u16));
+        assert!(!ALU::is_signed(0x80u16));
+    }
+
+    const WIDE: &str = "
+        LDI r1 0xff
+        LDI r2 1
+        ADD r3 r1 r2
+        SHL r4 r3 4
+        LDI r5 0x20
+        LDI r0 0
+        SB r4 r0 r5
+        LB r6 r0 r5
+        HLT
+    ";
+
+    #[test]
+    fn word_registers_hold_16_bits() {
+        let mut sys = word_system::<u16>(WIDE, 256);
+        assert_eq!(sys.run(None, || false), StopReason::Halted);
+
+        let regs = sys.get_regs();
+        assert_eq!(regs[3], 0x100);
+        assert_eq!(regs[4], 0x1000);
+        assert_eq!(regs[6], 0x1000);
+
+        // stored with the most significant byte first
+        assert_eq!(sys.get_mem(0x20), Ok(0x10));
+        assert_eq!(sys.get_mem(0x21), Ok(0x00));
+    }
+
+    #[test]
+    fn word_8_bit_machine_runs_same_encoding() {
+        let mut sys = word_system::<u8>(WIDE, 256);
+        assert_eq!(sys.run(None, || false), StopReason::Halted);
+
+        let regs = sys.get_regs();
+        assert_eq!(regs[3], 0);
+        assert!(sys.get_flags_register().is_set(Flags::Zero));
+        assert_eq!(sys.get_mem(0x20), Ok(0));
+    }
+
+    #[test]
+    fn word_stores_low_byte_to_devices() {
+        let mut sys = word_system::<u16>(
+            "
+            LDI r0 1
+            SHL r0 r0 8
+            LDI r1 0x41
+            OR r1 r1 r0
+            LDI r0 0
+            SB r1 r0 r0
+            HLT
+        ",
+            256,
+        );
+
+        assert_eq!(sys.run(None, || false), StopReason::Halted);
+        assert_eq!(sys.get_regs()[1], 0x141);
+        assert_eq!(sys.bus().device::<Serial>().unwrap().output(), b"A");
+    }
+
+    #[test]
+    fn word_store_can_be_undone() {
+        let mut sys = word_system::<u16>(WIDE, 256);
+        sys.run(Some(7), || false);
+        assert_eq!(sys.get_mem(0x20), Ok(0x10));
+
+        sys.step_back().unwrap();
+        assert_eq!(sys.get_mem(0x20), Ok(0));
+        assert_eq!(sys.get_mem(0x21), Ok(0));
+        assert_eq!(sys.get_regs()[4], 0x1000);
+    }
+
+    #[test]
+    fn word_size_is_part_of_snapshot() {
+        let mut sys = word_system::<u16>(WIDE, 256);
+        sys.run(None, || false);
+
+        let snapshot = sys.snapshot();
+        assert_eq!(snapshot.word_size, WordSize::Bits16);
+        assert_eq!(snapshot.regs[4], 0x1000);
+
+        let data = snapshot.serialize();
+        assert_eq!(data[5], 16);
+        assert_eq!(Snapshot::deserialize(&data), Ok(snapshot.clone()));
+
+        let mut restored = System::<u16>::with_ram_size(256);
+        restored.restore(&snapshot);
+        assert_eq!(restored.get_regs()[6], 0x1000);
+
+        let diff = word_system::<u8>(WIDE, 256).snapshot().diff(&snapshot);
+        assert_eq!(diff.word_size, Some((WordSize::Bits8, WordSize::Bits16)));
+    }
+
+    #[test]
+    fn word_condition_constants_fit_word_size() {
+        assert!(Condition::parse("r1 == 0x100").is_err());
+
+        let condition = Condition::parse_sized("r4 == 0x1000", WordSize::Bits16).unwrap();
+        let mut sys = word_system::<u16>(WIDE, 256);
+        sys.run(None, || false);
+        assert!(condition.evaluate(&sys));
+    }
+
+    #[test]
+    fn word_directive_follows_word_size() {
+        assert_eq!(compile(".word 0x12 1"), vec![0x12, 0x01]);
+        assert_eq!(
+            compile(".wordsize 16\n.word 0x1234 1"),
+            vec![0x12, 0x34, 0x00, 0x01]
+        );
+
+        let source = ".word 0x100";
+        assert!(new_compiler::compile(source.as_bytes(), Path::new("test.asm")).is_err());
+
+        let source = ".wordsize 32";
+        assert!(new_compiler::compile(source.as_bytes(), Path::new("test.asm")).is_err());
+    }
+}